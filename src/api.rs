@@ -1,46 +1,28 @@
+use crate::api_collection::CollectionAuth;
+use crate::assertions::{self, Assertion, AssertionResult};
+use crate::client::{self, ClientOptions};
 use crate::common::{TestConfig, TestResult, TestRunner};
 use crate::error::{Error, Result};
+use crate::oauth2::TokenCache;
+use crate::request_body::{self, BodyType};
+use crate::response::{BufferedResponse, ResponseType};
+use crate::response_schema;
+use crate::retry::{self, RetryOutcome};
+use crate::signing;
+use crate::snapshot::SnapshotConfig;
+use crate::timing::{self, PhaseTimings, TimingLimits};
 use async_trait::async_trait;
 use chrono::Utc;
 use jsonschema::JSONSchema;
-use log::{info, warn};
-use reqwest::{Client, Method, Response};
+use log::info;
+use reqwest::{Client, Method};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::path::Path;
 use std::time::{Duration, Instant};
-use tokio_retry::strategy::{jitter, ExponentialBackoff};
-use tokio_retry::Retry;
-
-#[derive(Debug, Serialize, Deserialize, Default)]
-pub struct RetryConfig {
-    #[serde(default = "default_max_retries")]
-    pub max_retries: u32,
-    #[serde(default = "default_initial_delay")]
-    pub initial_delay_ms: u64,
-    #[serde(default = "default_max_delay")]
-    pub max_delay_ms: u64,
-    #[serde(default = "default_retry_status_codes")]
-    pub retry_status_codes: Vec<u16>,
-    #[serde(default)]
-    pub retry_on_timeout: bool,
-    #[serde(default)]
-    pub retry_on_connection_error: bool,
-}
 
-fn default_max_retries() -> u32 {
-    3
-}
-
-fn default_initial_delay() -> u64 {
-    100
-}
-
-fn default_max_delay() -> u64 {
-    5000
-}
-
-fn default_retry_status_codes() -> Vec<u16> {
-    vec![408, 429, 500, 502, 503, 504]
-}
+pub use crate::retry::RetryConfig;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiTestConfig {
@@ -50,14 +32,29 @@ pub struct ApiTestConfig {
     pub method: String,
     pub headers: Option<serde_json::Value>,
     pub body: Option<serde_json::Value>,
+    /// How the body is encoded (json, form, multipart, raw or binary_file)
+    #[serde(default)]
+    pub body_type: BodyType,
+    /// Content-Type for raw and binary_file bodies
+    pub content_type: Option<String>,
     pub expected_status: Option<u16>,
     pub expected_body: Option<serde_json::Value>,
     #[serde(default = "default_timeout")]
     pub timeout: u64,
     #[serde(default)]
     pub max_response_time: Option<u64>,
+    /// Limits for each request phase (max_dns_ms, max_connect_ms, max_ttfb_ms, max_download_ms)
+    #[serde(flatten)]
+    pub timing_limits: TimingLimits,
     pub expected_headers: Option<serde_json::Value>,
     pub json_schema: Option<serde_json::Value>,
+    pub assertions: Option<Vec<Assertion>>,
+    /// Authentication added to the request (basic, bearer, api_key, oauth2, hmac, aws_sigv4)
+    pub auth: Option<CollectionAuth>,
+    /// Override the response type detected from the Content-Type header
+    pub response_type: Option<ResponseType>,
+    /// Compare the response with a stored snapshot
+    pub snapshot: Option<SnapshotConfig>,
     #[serde(default)]
     pub retry: RetryConfig,
 }
@@ -66,19 +63,48 @@ fn default_timeout() -> u64 {
     30 // Default timeout of 30 seconds
 }
 
+impl ApiTestConfig {
+    /// Resolve file paths used by the request body and snapshots relative to the configuration directory
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        if let Some(body) = self.body.as_mut() {
+            request_body::resolve_paths(self.body_type, body, base_dir);
+        }
+        if let Some(snapshot) = self.snapshot.as_mut() {
+            snapshot.resolve_paths(base_dir);
+        }
+    }
+}
+
 pub struct ApiTestRunner {
     client: Client,
+    client_options: ClientOptions,
+    update_snapshots: bool,
+    tokens: TokenCache,
 }
 
 impl ApiTestRunner {
     pub fn new() -> Self {
         Self {
-            client: Client::builder()
-                .timeout(Duration::from_secs(30))
+            client: timing::timed_client_builder()
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            client_options: ClientOptions::default(),
+            update_snapshots: false,
+            tokens: TokenCache::new(),
         }
     }
+
+    /// Override the TLS and proxy settings of every test config
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.client_options = client_options;
+        self
+    }
+
+    /// Rewrite stored snapshots instead of comparing against them
+    pub fn with_update_snapshots(mut self, update_snapshots: bool) -> Self {
+        self.update_snapshots = update_snapshots;
+        self
+    }
 }
 
 impl Default for ApiTestRunner {
@@ -88,104 +114,29 @@ impl Default for ApiTestRunner {
 }
 
 impl ApiTestRunner {
-    async fn execute_request_with_retry(&self, config: &ApiTestConfig) -> Result<Response> {
-        let retry_strategy = ExponentialBackoff::from_millis(config.retry.initial_delay_ms)
-            .max_delay(Duration::from_millis(config.retry.max_delay_ms))
-            .map(jitter) // Add jitter to prevent thundering herd
-            .take(config.retry.max_retries as usize);
-
-        let mut attempt = 1;
-        let result = Retry::spawn(retry_strategy, || {
-            let attempt_num = attempt;
-            attempt += 1;
-            async move {
-                info!(
-                    "Attempt {} of {}",
-                    attempt_num,
-                    config.retry.max_retries + 1
-                );
-                match self.execute_request(config).await {
-                    Ok(response) => {
-                        let status = response.status();
-                        let status_code = status.as_u16();
-
-                        // Check if we should retry based on status code
-                        if config.retry.retry_status_codes.contains(&status_code) {
-                            warn!(
-                                "Request failed with status {} on attempt {}, retrying...",
-                                status_code, attempt_num
-                            );
-                            Err(Error::TestError(format!(
-                                "Retryable status code: {} (attempt {}/{})",
-                                status_code,
-                                attempt_num,
-                                config.retry.max_retries + 1
-                            )))
-                        } else {
-                            Ok(response)
-                        }
-                    }
-                    Err(e) => {
-                        // Check if we should retry based on error type
-                        let should_retry = match &e {
-                            Error::RequestError(req_err) => {
-                                if req_err.is_timeout() && config.retry.retry_on_timeout {
-                                    warn!(
-                                        "Request timed out on attempt {}, retrying...",
-                                        attempt_num
-                                    );
-                                    true
-                                } else if req_err.is_connect()
-                                    && config.retry.retry_on_connection_error
-                                {
-                                    warn!(
-                                        "Connection error on attempt {}, retrying...",
-                                        attempt_num
-                                    );
-                                    true
-                                } else {
-                                    false
-                                }
-                            }
-                            _ => false,
-                        };
-
-                        if should_retry {
-                            Err(e)
-                        } else {
-                            Err(Error::TestError(format!(
-                                "Non-retryable error on attempt {}: {}",
-                                attempt_num, e
-                            )))
-                        }
-                    }
-                }
-            }
-        })
-        .await;
+    async fn execute_request_with_retry(&self, config: &ApiTestConfig) -> Result<RetryOutcome> {
+        let client = client::client_for(
+            &self.client,
+            &config.base.client_options(),
+            &self.client_options,
+            timing::timed_client_builder(),
+        )?;
 
-        match result {
-            Ok(response) => {
-                info!("Request succeeded after {} attempts", attempt - 1);
-                Ok(response)
-            }
-            Err(e) => {
-                warn!(
-                    "All {} retry attempts failed: {}",
-                    config.retry.max_retries + 1,
-                    e
-                );
-                Err(e)
-            }
-        }
+        Ok(
+            retry::execute_with_retry(&config.retry, || self.execute_request(&client, config))
+                .await,
+        )
     }
 
-    async fn execute_request(&self, config: &ApiTestConfig) -> Result<Response> {
+    async fn execute_request(
+        &self,
+        client: &Client,
+        config: &ApiTestConfig,
+    ) -> Result<BufferedResponse> {
         let method = Method::from_bytes(config.method.as_bytes())
             .map_err(|e| Error::ValidationError(format!("Invalid HTTP method: {}", e)))?;
 
-        let mut request = self
-            .client
+        let mut request = client
             .request(method, &config.url)
             .timeout(Duration::from_secs(config.timeout));
 
@@ -199,73 +150,152 @@ impl ApiTestRunner {
             }
         }
 
+        // OAuth2 tokens and signatures are added when sending
+        let send_auth = config.auth.as_ref().filter(|auth| auth.applies_on_send());
+        if let Some(auth) = config.auth.as_ref().filter(|auth| !auth.applies_on_send()) {
+            request = auth.apply(request, |value| Ok(value.to_string()))?;
+        }
+
         if let Some(body) = &config.body {
-            request = request.json(body);
+            if let Some(auth) = send_auth {
+                signing::check_body_type(auth, config.body_type)?;
+            }
+            request = request_body::apply_body(
+                request,
+                config.body_type,
+                body,
+                config.content_type.as_deref(),
+            )?;
         }
 
+        let mut timings = PhaseTimings::default();
+
         info!("Sending {} request to {}", config.method, config.url);
-        let response = request.send().await?;
-        Ok(response)
+        let sent = Instant::now();
+        let (response, setup) = timing::measure(async {
+            match send_auth {
+                Some(auth) if auth.is_oauth2() => self.tokens.send(client, auth, request).await,
+                Some(auth) => signing::send(client, auth, request).await,
+                None => Ok(request.send().await?),
+            }
+        })
+        .await;
+        timings.record_headers(sent.elapsed(), setup);
+        let response = response?;
+        BufferedResponse::read(response, timings, config.response_type).await
     }
 
-    async fn validate_response(
+    /// Evaluate every configured check against the response.
+    ///
+    /// Checks never short-circuit: each one is recorded with its expected and
+    /// actual value so that a single run reports every problem.
+    fn validate_response(
         &self,
-        response: Response,
+        response: &BufferedResponse,
         config: &ApiTestConfig,
         duration: f64,
-    ) -> Result<serde_json::Value> {
+    ) -> Result<(serde_json::Value, Vec<AssertionResult>)> {
+        let mut checks = Vec::new();
+
         // Validate response time if specified
         if let Some(max_time) = config.max_response_time {
-            if duration > max_time as f64 {
-                return Err(Error::TestError(format!(
-                    "Response time exceeded maximum allowed time. Expected: {}s, Got: {:.2}s",
-                    max_time, duration
-                )));
-            }
+            let passed = duration <= max_time as f64;
+            checks.push(
+                AssertionResult::check("response_time", json!(max_time), json!(duration), passed)
+                    .with_message(format!(
+                        "Response time exceeded maximum allowed time. Expected: {}s, Got: {:.2}s",
+                        max_time, duration
+                    )),
+            );
         }
 
         // Validate status code if specified
+        let status_code = response.status.as_u16();
         if let Some(expected_status) = config.expected_status {
-            if response.status().as_u16() != expected_status {
-                return Err(Error::TestError(format!(
+            checks.push(
+                AssertionResult::check(
+                    "status",
+                    json!(expected_status),
+                    json!(status_code),
+                    status_code == expected_status,
+                )
+                .with_message(format!(
                     "Expected status {} but got {}",
-                    expected_status,
-                    response.status().as_u16()
-                )));
-            }
+                    expected_status, status_code
+                )),
+            );
         }
 
         // Validate response headers if specified
-        if let Some(expected_headers) = &config.expected_headers {
-            if let Some(expected_obj) = expected_headers.as_object() {
-                for (key, expected_value) in expected_obj {
-                    if let Some(actual_value) = response.headers().get(key) {
+        if let Some(expected_obj) = config.expected_headers.as_ref().and_then(|h| h.as_object()) {
+            for (key, expected_value) in expected_obj {
+                let expected_str = expected_value.as_str().unwrap_or("");
+                let name = format!("header:{}", key);
+                let check = match response.headers.get(key) {
+                    Some(actual_value) => {
                         let actual_str = actual_value.to_str().unwrap_or("");
-                        let expected_str = expected_value.as_str().unwrap_or("");
-                        if actual_str != expected_str {
-                            return Err(Error::TestError(format!(
-                                "Response header mismatch for '{}'. Expected: {}, Got: {}",
-                                key, expected_str, actual_str
-                            )));
-                        }
-                    } else {
-                        return Err(Error::TestError(format!(
-                            "Expected header '{}' not found in response",
-                            key
-                        )));
+                        AssertionResult::check(
+                            name,
+                            json!(expected_str),
+                            json!(actual_str),
+                            actual_str == expected_str,
+                        )
+                        .with_message(format!(
+                            "Response header mismatch for '{}'. Expected: {}, Got: {}",
+                            key, expected_str, actual_str
+                        ))
                     }
-                }
+                    None => AssertionResult::check(name, json!(expected_str), Value::Null, false)
+                        .with_message(format!("Expected header '{}' not found in response", key)),
+                };
+                checks.push(check);
             }
         }
 
-        let body_text = response.text().await?;
-        let actual_body: serde_json::Value = match serde_json::from_str(&body_text) {
-            Ok(json) => json,
-            Err(_) => {
-                return Err(Error::TestError(format!(
-                    "Response is not valid JSON: {}",
-                    body_text
-                )));
+        // Validate phase timings if limits are specified
+        checks.extend(config.timing_limits.check(&response.timings));
+
+        // Unless the type was set explicitly, a body that parses as JSON is
+        // checked as JSON whatever its Content-Type
+        let body = match config.response_type {
+            None => Cow::Owned(response.body.clone().or_json()),
+            Some(_) => Cow::Borrowed(&response.body),
+        };
+
+        // JSON-only checks need a parsed document
+        let actual_body = match (body.response_type, body.json()) {
+            (ResponseType::Json, Some(json)) => json.clone(),
+            (response_type, _) => {
+                if response_type == ResponseType::Json {
+                    let body_text = body.text();
+                    checks.push(
+                        AssertionResult::check(
+                            "body",
+                            json!("valid JSON"),
+                            json!(body_text),
+                            false,
+                        )
+                        .with_message(format!("Response is not valid JSON: {}", body_text)),
+                    );
+                } else if config.json_schema.is_some() || config.expected_body.is_some() {
+                    checks.push(
+                        AssertionResult::check(
+                            "body",
+                            json!("json"),
+                            json!(response_type.as_str()),
+                            false,
+                        )
+                        .with_message(format!(
+                            "json_schema and expected_body require a JSON response, got {}",
+                            response_type.as_str()
+                        )),
+                    );
+                }
+                // Every assertion is still reported, failing where it needs a parsed body
+                if let Some(list) = &config.assertions {
+                    checks.extend(assertions::evaluate_all_body(list, &body));
+                }
+                return Ok((body.to_details(), checks));
             }
         };
 
@@ -273,39 +303,42 @@ impl ApiTestRunner {
         if let Some(schema) = &config.json_schema {
             let compiled_schema = JSONSchema::compile(schema)
                 .map_err(|e| Error::ValidationError(format!("Invalid JSON Schema: {}", e)))?;
-
-            let validation_result = compiled_schema.validate(&actual_body);
-            if let Err(errors) = validation_result {
-                let error_messages: Vec<String> = errors.map(|e| format!("{}", e)).collect();
-                return Err(Error::TestError(format!(
-                    "JSON Schema validation failed:\n{}",
-                    error_messages.join("\n")
-                )));
-            }
+            checks.push(response_schema::check(&compiled_schema, &actual_body));
         }
 
         // Validate specific fields if expected_body is specified
-        if let Some(expected_body) = &config.expected_body {
-            if let Some(expected_obj) = expected_body.as_object() {
-                for (key, expected_value) in expected_obj {
-                    if let Some(actual_value) = actual_body.get(key) {
-                        if actual_value != expected_value {
-                            return Err(Error::TestError(format!(
-                                "Field '{}' mismatch. Expected: {:?}, Got: {:?}",
-                                key, expected_value, actual_value
-                            )));
-                        }
-                    } else {
-                        return Err(Error::TestError(format!(
-                            "Expected field '{}' not found in response body",
-                            key
-                        )));
+        if let Some(expected_obj) = config.expected_body.as_ref().and_then(|b| b.as_object()) {
+            for (key, expected_value) in expected_obj {
+                let name = format!("body:{}", key);
+                let check = match actual_body.get(key) {
+                    Some(actual_value) => AssertionResult::check(
+                        name,
+                        expected_value.clone(),
+                        actual_value.clone(),
+                        actual_value == expected_value,
+                    )
+                    .with_message(format!(
+                        "Field '{}' mismatch. Expected: {:?}, Got: {:?}",
+                        key, expected_value, actual_value
+                    )),
+                    None => {
+                        AssertionResult::check(name, expected_value.clone(), Value::Null, false)
+                            .with_message(format!(
+                                "Expected field '{}' not found in response body",
+                                key
+                            ))
                     }
-                }
+                };
+                checks.push(check);
             }
         }
 
-        Ok(actual_body)
+        // Evaluate JSONPath assertions if specified
+        if let Some(list) = &config.assertions {
+            checks.extend(assertions::evaluate_all(list, &actual_body));
+        }
+
+        Ok((actual_body, checks))
     }
 }
 
@@ -315,54 +348,92 @@ impl TestRunner for ApiTestRunner {
         let config = serde_json::from_value::<ApiTestConfig>(serde_json::to_value(config)?)?;
         let start = Instant::now();
 
-        match self.execute_request_with_retry(&config).await {
-            Ok(response) => {
+        let outcome = match self.execute_request_with_retry(&config).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
                 let duration = start.elapsed().as_secs_f64();
-                let status = response.status();
-                let headers = response.headers().clone();
+                return Ok(TestResult {
+                    name: config.base.name,
+                    status: "failed".to_string(),
+                    duration,
+                    details: Some(serde_json::json!({
+                        "error": e.to_string(),
+                        "response_time": duration
+                    })),
+                    timestamp: Utc::now().to_rfc3339(),
+                });
+            }
+        };
+        let duration = start.elapsed().as_secs_f64();
 
-                match self.validate_response(response, &config, duration).await {
-                    Ok(body) => Ok(TestResult {
-                        name: config.base.name,
-                        status: "passed".to_string(),
-                        duration,
-                        details: Some(serde_json::json!({
-                            "status_code": status.as_u16(),
-                            "response_time": duration,
-                            "headers": headers
-                                .iter()
-                                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-                                .collect::<std::collections::HashMap<_, _>>(),
-                            "body": body
-                        })),
-                        timestamp: Utc::now().to_rfc3339(),
-                    }),
-                    Err(e) => Ok(TestResult {
+        match outcome.result {
+            Ok(response) => match self.validate_response(&response, &config, duration) {
+                Ok((body, mut checks)) => {
+                    // Report whether the polling condition was reached
+                    checks.extend(outcome.retry_until);
+
+                    // Compare with the stored snapshot if configured
+                    if let Some(snapshot) = &config.snapshot {
+                        checks.extend(snapshot.check(
+                            &config.base.name,
+                            &body,
+                            &response.headers,
+                            self.update_snapshots,
+                        ));
+                    }
+
+                    let failures = assertions::failure_messages(&checks);
+                    let mut details = serde_json::json!({
+                        "status_code": response.status.as_u16(),
+                        "response_time": duration,
+                        "headers": response.header_map(),
+                        "body": body,
+                        "timing": response.timings,
+                        "attempts": outcome.attempts,
+                        "assertions": checks
+                    });
+                    if !failures.is_empty() {
+                        details["error"] = serde_json::json!(failures.join("\n"));
+                    }
+
+                    Ok(TestResult {
                         name: config.base.name,
-                        status: "failed".to_string(),
+                        status: if failures.is_empty() {
+                            "passed"
+                        } else {
+                            "failed"
+                        }
+                        .to_string(),
                         duration,
-                        details: Some(serde_json::json!({
-                            "error": e.to_string(),
-                            "status_code": status.as_u16(),
-                            "response_time": duration
-                        })),
+                        details: Some(details),
                         timestamp: Utc::now().to_rfc3339(),
-                    }),
+                    })
                 }
-            }
-            Err(e) => {
-                let duration = start.elapsed().as_secs_f64();
-                Ok(TestResult {
+                Err(e) => Ok(TestResult {
                     name: config.base.name,
                     status: "failed".to_string(),
                     duration,
                     details: Some(serde_json::json!({
                         "error": e.to_string(),
-                        "response_time": duration
+                        "status_code": response.status.as_u16(),
+                        "response_time": duration,
+                        "timing": response.timings,
+                        "attempts": outcome.attempts
                     })),
                     timestamp: Utc::now().to_rfc3339(),
-                })
-            }
+                }),
+            },
+            Err(e) => Ok(TestResult {
+                name: config.base.name,
+                status: "failed".to_string(),
+                duration,
+                details: Some(serde_json::json!({
+                    "error": e.to_string(),
+                    "response_time": duration,
+                    "attempts": outcome.attempts
+                })),
+                timestamp: Utc::now().to_rfc3339(),
+            }),
         }
     }
 }
@@ -1,25 +1,44 @@
 use crate::api::ApiTestRunner;
-use crate::common::{TestResult, TestRunner};
+use crate::assertions::{self, Assertion, AssertionResult};
+use crate::capture::{self, Capture, CaptureResponse};
+use crate::client::{self, ClientOptions, ProxyConfig, TlsConfig};
+use crate::collection_graph::DependencyGraph;
+use crate::common::{self, TestResult, TestRunner};
+use crate::cookies::{self, SeedCookie};
+use crate::environment::Environment;
 use crate::error::{Error, Result};
+use crate::interpolation::{self, Interpolator};
+use crate::oauth2::TokenCache;
+use crate::request_body::{self, BodyType};
+use crate::response::{BufferedResponse, ResponseBody};
+use crate::response_schema::SchemaCache;
+use crate::retry::{self, RetryConfig};
+use crate::scripting::{self, ScriptPhase};
+use crate::secrets::{self, Redactor};
+use crate::signing;
+use crate::timing::{self, PhaseTimings, TimingLimits};
 use chrono::Utc;
-use jsonpath_lib as jsonpath;
+use futures::stream::{FuturesUnordered, StreamExt};
 use log::{info, warn};
-use reqwest::{Client, Method};
+use reqwest::cookie::Jar;
+use reqwest::{Client, Method, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Authentication configuration for API collections
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CollectionAuth {
-    /// Authentication type (basic, bearer, api_key)
+    /// Authentication type (basic, bearer, api_key, oauth2, hmac, aws_sigv4)
     #[serde(rename = "type")]
     pub auth_type: String,
-    /// Username for basic auth
+    /// Username for basic auth and the oauth2 password grant
     pub username: Option<String>,
-    /// Password for basic auth
+    /// Password for basic auth and the oauth2 password grant
     pub password: Option<String>,
     /// Token for bearer auth
     pub token: Option<String>,
@@ -29,17 +48,221 @@ pub struct CollectionAuth {
     pub key_value: Option<String>,
     /// Header or query parameter for API key
     pub key_in: Option<String>,
+    /// OAuth2 grant (client_credentials, password, refresh_token, authorization_code)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grant_type: Option<String>,
+    /// OAuth2 token endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_url: Option<String>,
+    /// OAuth2 client ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// OAuth2 client secret, omitted for public clients
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    /// How the client authenticates to the token endpoint (body, basic)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_auth: Option<String>,
+    /// OAuth2 scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// OAuth2 audience
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+    /// Refresh token for the oauth2 refresh_token grant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Authorization code for the oauth2 authorization_code grant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// PKCE code verifier sent with the authorization code
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_verifier: Option<String>,
+    /// Redirect URI the authorization code was issued for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+    /// Shared secret for hmac auth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// String signed by hmac auth, with {method}, {host}, {path}, {query}, {timestamp}, {body} and {body_sha256}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_template: Option<String>,
+    /// Header carrying the hmac signature (default X-Signature)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_header: Option<String>,
+    /// Text placed before the hmac signature in its header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_prefix: Option<String>,
+    /// Encoding of the hmac signature (hex, base64)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_encoding: Option<String>,
+    /// Header carrying the signed Unix timestamp (default X-Timestamp)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp_header: Option<String>,
+    /// AWS access key ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_key_id: Option<String>,
+    /// AWS secret access key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+    /// AWS session token for temporary credentials
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    /// AWS region, such as us-east-1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// AWS service name, such as s3 or execute-api
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+}
+
+impl CollectionAuth {
+    /// Whether tokens are fetched from an OAuth2 token endpoint
+    pub fn is_oauth2(&self) -> bool {
+        self.auth_type == "oauth2"
+    }
+
+    /// Whether the complete request is signed just before it is sent
+    pub fn is_signature(&self) -> bool {
+        matches!(self.auth_type.as_str(), "hmac" | "aws_sigv4")
+    }
+
+    /// Whether the credentials are added when sending rather than by `apply`
+    pub fn applies_on_send(&self) -> bool {
+        self.is_oauth2() || self.is_signature()
+    }
+
+    /// A copy with every setting passed through `resolve`
+    pub fn resolve(&self, resolve: impl Fn(&str) -> Result<String>) -> Result<Self> {
+        let field = |value: &Option<String>| value.as_deref().map(&resolve).transpose();
+        Ok(Self {
+            auth_type: self.auth_type.clone(),
+            username: field(&self.username)?,
+            password: field(&self.password)?,
+            token: field(&self.token)?,
+            key_name: field(&self.key_name)?,
+            key_value: field(&self.key_value)?,
+            key_in: self.key_in.clone(),
+            grant_type: self.grant_type.clone(),
+            token_url: field(&self.token_url)?,
+            client_id: field(&self.client_id)?,
+            client_secret: field(&self.client_secret)?,
+            client_auth: self.client_auth.clone(),
+            scope: field(&self.scope)?,
+            audience: field(&self.audience)?,
+            refresh_token: field(&self.refresh_token)?,
+            code: field(&self.code)?,
+            code_verifier: field(&self.code_verifier)?,
+            redirect_uri: field(&self.redirect_uri)?,
+            secret: field(&self.secret)?,
+            canonical_template: self.canonical_template.clone(),
+            signature_header: field(&self.signature_header)?,
+            signature_prefix: field(&self.signature_prefix)?,
+            signature_encoding: self.signature_encoding.clone(),
+            timestamp_header: field(&self.timestamp_header)?,
+            access_key_id: field(&self.access_key_id)?,
+            secret_access_key: field(&self.secret_access_key)?,
+            session_token: field(&self.session_token)?,
+            region: field(&self.region)?,
+            service: field(&self.service)?,
+        })
+    }
+
+    /// Add the credentials to a request, passing each secret through `resolve`
+    pub fn apply(
+        &self,
+        mut req_builder: RequestBuilder,
+        resolve: impl Fn(&str) -> Result<String>,
+    ) -> Result<RequestBuilder> {
+        match self.auth_type.as_str() {
+            "basic" => {
+                let username = self.username.as_ref().ok_or_else(|| {
+                    Error::ValidationError("Username required for basic auth".to_string())
+                })?;
+                let password = self.password.as_ref().ok_or_else(|| {
+                    Error::ValidationError("Password required for basic auth".to_string())
+                })?;
+
+                req_builder = req_builder.basic_auth(resolve(username)?, Some(resolve(password)?));
+            }
+            "bearer" => {
+                let token = self.token.as_ref().ok_or_else(|| {
+                    Error::ValidationError("Token required for bearer auth".to_string())
+                })?;
+
+                req_builder = req_builder.bearer_auth(resolve(token)?);
+            }
+            "api_key" => {
+                let key_name = self.key_name.as_ref().ok_or_else(|| {
+                    Error::ValidationError("Key name required for API key auth".to_string())
+                })?;
+                let key_value = self.key_value.as_ref().ok_or_else(|| {
+                    Error::ValidationError("Key value required for API key auth".to_string())
+                })?;
+
+                let key_value = resolve(key_value)?;
+
+                // Determine if the key should be in header or query parameter
+                match self.key_in.as_deref() {
+                    Some("query") => {
+                        // Add as query parameter
+                        req_builder = req_builder.query(&[(key_name, key_value)]);
+                    }
+                    _ => {
+                        // Default to header
+                        req_builder = req_builder.header(key_name, key_value);
+                    }
+                }
+            }
+            "oauth2" | "hmac" | "aws_sigv4" => {
+                return Err(Error::ValidationError(format!(
+                    "{} credentials are added when the request is sent",
+                    self.auth_type
+                )));
+            }
+            _ => {
+                return Err(Error::ValidationError(format!(
+                    "Unsupported authentication type: {}",
+                    self.auth_type
+                )));
+            }
+        }
+
+        Ok(req_builder)
+    }
 }
 
 /// Default request configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CollectionDefaults {
     /// Default headers for all requests
     pub headers: Option<HashMap<String, String>>,
     /// Default timeout in seconds
     pub timeout: Option<u64>,
-    /// Default number of retries
+    /// Default number of retries, with the default retry policy
     pub retries: Option<u32>,
+    /// Default retry policy
+    pub retry: Option<RetryConfig>,
+}
+
+impl CollectionDefaults {
+    /// These defaults with `inner` applied over them: headers are merged, other fields replaced
+    pub fn merged(&self, inner: &CollectionDefaults) -> CollectionDefaults {
+        let headers = match (&self.headers, &inner.headers) {
+            (Some(outer), Some(inner)) => {
+                let mut headers = outer.clone();
+                headers.extend(inner.clone());
+                Some(headers)
+            }
+            (outer, inner) => inner.clone().or_else(|| outer.clone()),
+        };
+        CollectionDefaults {
+            headers,
+            timeout: inner.timeout.or(self.timeout),
+            retries: inner.retries.or(self.retries),
+            retry: inner.retry.clone().or_else(|| self.retry.clone()),
+        }
+    }
 }
 
 /// Run options for the collection
@@ -51,6 +274,14 @@ pub struct CollectionRunOptions {
     pub stop_on_failure: Option<bool>,
     /// Delay between requests in milliseconds
     pub delay_between_requests_ms: Option<u64>,
+    /// Keep cookies set by responses and send them with later requests
+    pub cookies: Option<bool>,
+    /// Maximum number of requests in flight when not sequential
+    pub max_concurrency: Option<usize>,
+}
+
+fn default_max_concurrency() -> usize {
+    4
 }
 
 /// A single request in a collection
@@ -70,23 +301,112 @@ pub struct CollectionRequest {
     pub headers: Option<HashMap<String, String>>,
     /// Request body
     pub body: Option<Value>,
+    /// How the body is encoded (json, form, multipart, raw or binary_file)
+    #[serde(default)]
+    pub body_type: BodyType,
+    /// Content-Type for raw and binary_file bodies
+    pub content_type: Option<String>,
     /// Expected HTTP status code
     pub expected_status: Option<u16>,
     /// Expected response body
     pub expected_body: Option<Value>,
     /// Expected response body type (object, array, etc.)
     pub expected_body_type: Option<String>,
+    /// JSONPath assertions on the response body
+    pub assertions: Option<Vec<Assertion>>,
+    /// JSON Schema the response body must match, which may `$ref` shared `schemas` and schema files
+    pub json_schema: Option<Value>,
+    /// Assertions on cookies, with the cookie name as `path`
+    pub cookie_assertions: Option<Vec<Assertion>>,
+    /// Limits for each request phase (max_dns_ms, max_connect_ms, max_ttfb_ms, max_download_ms)
+    #[serde(flatten)]
+    pub timing_limits: TimingLimits,
     /// Request dependencies (IDs of requests that must be executed before this one)
     pub depends_on: Option<Vec<String>>,
-    /// Variables to capture from the response
-    pub capture: Option<HashMap<String, String>>,
+    /// Variables to capture from the response: a JSONPath (optionally prefixed with
+    /// `jsonpath:`), `header:<name>`, `status`, `cookie:<name>` or `regex:<pattern>`
+    pub capture: Option<HashMap<String, Capture>>,
+    /// Rhai expression; the request only runs when it is true
+    pub run_if: Option<String>,
+    /// Rhai expression; the request is skipped when it is true
+    pub skip_if: Option<String>,
+    /// Send the request again until its response meets conditions
+    pub repeat_until: Option<RepeatUntil>,
+    /// Run the request once for each item of a JSON array variable
+    pub for_each: Option<ForEach>,
+    /// Rhai script run before the request is sent
+    pub pre_request: Option<String>,
+    /// Rhai script run after the response is received
+    pub post_response: Option<String>,
+    /// Retry policy, replacing the collection default
+    pub retry: Option<RetryConfig>,
+    /// Tags used to select requests with `--tag`
+    pub tags: Option<Vec<String>>,
+}
+
+/// Polling: send a request again until its response body meets conditions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatUntil {
+    /// Assertions on the response body that end the polling
+    pub conditions: Vec<Assertion>,
+    /// Delay between attempts in milliseconds (default: 1000)
+    #[serde(default = "default_repeat_interval")]
+    pub interval_ms: u64,
+    /// Attempts before the request fails (default: 10)
+    #[serde(default = "default_repeat_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_repeat_interval() -> u64 {
+    1000
+}
+
+fn default_repeat_attempts() -> u32 {
+    10
+}
+
+/// A loop over the items of a JSON array variable, such as a capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForEach {
+    /// Variable holding the JSON array
+    pub items: String,
+    /// Variable each item is bound to (default: item)
+    #[serde(rename = "as", default = "default_item_variable")]
+    pub variable: String,
+}
+
+fn default_item_variable() -> String {
+    "item".to_string()
 }
 
 impl CollectionRequest {
     /// Check if this is a simple request that could be handled by the ApiTestRunner
-    /// A simple request has no dependencies, no variable captures, and no complex validation
+    /// A simple request has no dependencies, no variable captures, no scripts, no polling and no complex validation
     pub fn is_simple_request(&self) -> bool {
-        self.depends_on.is_none() && self.capture.is_none()
+        self.depends_on.is_none()
+            && self.capture.is_none()
+            && self.cookie_assertions.is_none()
+            && self.pre_request.is_none()
+            && self.post_response.is_none()
+            && self.repeat_until.is_none()
+            && self.json_schema.is_none()
+    }
+
+    /// The retry policy of this request: its own `retry`, or the collection defaults.
+    ///
+    /// `defaults.retries` sets the number of retries of the default policy.
+    /// Without either, requests are sent once.
+    pub fn retry_policy(&self, defaults: Option<&CollectionDefaults>) -> RetryConfig {
+        if let Some(retry) = &self.retry {
+            return retry.clone();
+        }
+        let mut policy = defaults
+            .and_then(|d| d.retry.clone())
+            .unwrap_or_else(|| RetryConfig::with_retries(0));
+        if let Some(retries) = defaults.and_then(|d| d.retries) {
+            policy.max_retries = retries;
+        }
+        policy
     }
 }
 
@@ -106,11 +426,283 @@ pub struct ApiCollection {
     /// Default request configuration
     pub defaults: Option<CollectionDefaults>,
     /// Collection requests
+    #[serde(default)]
     pub requests: Vec<CollectionRequest>,
+    /// Folders of requests, run after the top-level requests
+    pub folders: Option<Vec<CollectionFolder>>,
     /// Environment-specific variables
     pub environments: Option<HashMap<String, HashMap<String, String>>>,
     /// Run options
     pub run_options: Option<CollectionRunOptions>,
+    /// TLS settings for all requests
+    pub tls: Option<TlsConfig>,
+    /// Proxy settings for all requests
+    pub proxy: Option<ProxyConfig>,
+    /// Cookies placed in the cookie jar before the first request
+    pub cookies: Option<Vec<SeedCookie>>,
+    /// Rhai script run before every request, ahead of the request's own script
+    pub pre_request: Option<String>,
+    /// Rhai script run after every response, ahead of the request's own script
+    pub post_response: Option<String>,
+    /// Process environment variables to use as variables, none are used otherwise
+    pub import_env: Option<EnvImport>,
+    /// Names of variables whose values are masked in logs, results and reports
+    pub secrets: Option<Vec<String>>,
+    /// JSON Schemas shared by requests, referenced as `#/schemas/<name>`
+    pub schemas: Option<HashMap<String, Value>>,
+    /// Directory of the collection file, used to resolve schema files
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+/// Process environment variables a collection opts in to
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvImport {
+    /// Names of variables to import
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Import every variable whose name starts with this prefix
+    pub prefix: Option<String>,
+}
+
+impl EnvImport {
+    /// The allowed variables among `vars`
+    pub fn select(
+        &self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> HashMap<String, String> {
+        vars.into_iter()
+            .filter(|(name, _)| {
+                self.allow.contains(name)
+                    || self
+                        .prefix
+                        .as_deref()
+                        .is_some_and(|prefix| !prefix.is_empty() && name.starts_with(prefix))
+            })
+            .collect()
+    }
+}
+
+/// A folder of requests, with settings inherited by its requests and subfolders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionFolder {
+    /// Folder name, used in `--folder` paths
+    pub name: String,
+    /// Folder description
+    pub description: Option<String>,
+    /// Authentication replacing the parent's
+    pub auth: Option<CollectionAuth>,
+    /// Defaults applied over the parent's
+    pub defaults: Option<CollectionDefaults>,
+    /// Variables overriding the parent's for requests in this folder
+    pub variables: Option<HashMap<String, String>>,
+    /// Folder requests
+    #[serde(default)]
+    pub requests: Vec<CollectionRequest>,
+    /// Subfolders, run after the folder's own requests
+    #[serde(default)]
+    pub folders: Vec<CollectionFolder>,
+}
+
+/// The settings a request inherits from the collection and its folders
+#[derive(Debug, Clone)]
+pub struct RequestScope {
+    /// Folder path such as `Orders/Refunds`, empty at collection level
+    pub folder: String,
+    /// The collection with the folder authentication and defaults applied
+    pub collection: ApiCollection,
+    /// Variables of the folders, the nearest folder winning
+    pub variables: HashMap<String, String>,
+}
+
+impl RequestScope {
+    /// Run variables with the folder variables applied over them, except
+    /// those set by `overrides`, which take precedence over folder variables
+    pub fn with_variables(
+        &self,
+        variables: &HashMap<String, String>,
+        overrides: &HashMap<String, String>,
+    ) -> HashMap<String, String> {
+        let mut scoped = variables.clone();
+        scoped.extend(
+            self.variables
+                .iter()
+                .filter(|(name, _)| !overrides.contains_key(*name))
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        scoped
+    }
+}
+
+/// A request of the collection or one of its folders
+#[derive(Debug, Clone)]
+pub struct ScopedRequest<'a> {
+    pub request: &'a CollectionRequest,
+    pub scope: Arc<RequestScope>,
+}
+
+impl ApiCollection {
+    /// TLS and proxy settings of this collection
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            tls: self.tls.clone(),
+            proxy: self.proxy.clone(),
+        }
+    }
+
+    /// Every request of the collection and its folders in run order: the
+    /// top-level requests first, then each folder depth-first
+    pub fn scoped_requests(&self) -> Vec<ScopedRequest<'_>> {
+        let mut base = self.clone();
+        base.requests = Vec::new();
+        base.folders = None;
+        let root = Arc::new(RequestScope {
+            folder: String::new(),
+            collection: base,
+            variables: HashMap::new(),
+        });
+
+        let mut requests: Vec<ScopedRequest> = self
+            .requests
+            .iter()
+            .map(|request| ScopedRequest {
+                request,
+                scope: root.clone(),
+            })
+            .collect();
+        for folder in self.folders.iter().flatten() {
+            add_folder_requests(folder, &root, &mut requests);
+        }
+        requests
+    }
+}
+
+fn add_folder_requests<'a>(
+    folder: &'a CollectionFolder,
+    parent: &RequestScope,
+    requests: &mut Vec<ScopedRequest<'a>>,
+) {
+    let mut collection = parent.collection.clone();
+    if let Some(auth) = &folder.auth {
+        collection.auth = Some(auth.clone());
+    }
+    if let Some(defaults) = &folder.defaults {
+        collection.defaults = Some(collection.defaults.unwrap_or_default().merged(defaults));
+    }
+    let mut variables = parent.variables.clone();
+    variables.extend(folder.variables.clone().unwrap_or_default());
+    let folder_path = if parent.folder.is_empty() {
+        folder.name.clone()
+    } else {
+        format!("{}/{}", parent.folder, folder.name)
+    };
+    let scope = Arc::new(RequestScope {
+        folder: folder_path,
+        collection,
+        variables,
+    });
+
+    requests.extend(folder.requests.iter().map(|request| ScopedRequest {
+        request,
+        scope: scope.clone(),
+    }));
+    for subfolder in &folder.folders {
+        add_folder_requests(subfolder, &scope, requests);
+    }
+}
+
+/// Resolve the body file paths of requests and their folders relative to `base_dir`
+fn resolve_body_paths<'a>(
+    requests: &mut [CollectionRequest],
+    folders: impl Iterator<Item = &'a mut CollectionFolder>,
+    base_dir: &Path,
+) {
+    for request in requests {
+        if let Some(body) = request.body.as_mut() {
+            request_body::resolve_paths(request.body_type, body, base_dir);
+        }
+    }
+    for folder in folders {
+        resolve_body_paths(&mut folder.requests, folder.folders.iter_mut(), base_dir);
+    }
+}
+
+/// Selects part of a collection to run. Each non-empty list narrows the
+/// selection; dependencies of selected requests are always run.
+#[derive(Debug, Clone, Default)]
+pub struct CollectionFilter {
+    /// Run only requests in these folders or their subfolders, by path such as `Orders/Refunds`
+    pub folders: Vec<String>,
+    /// Run only the requests with these IDs
+    pub requests: Vec<String>,
+    /// Run only requests with at least one of these tags
+    pub tags: Vec<String>,
+}
+
+impl CollectionFilter {
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty() && self.requests.is_empty() && self.tags.is_empty()
+    }
+
+    /// Whether a request is selected, without its dependencies
+    pub fn matches(&self, request: &ScopedRequest) -> bool {
+        let folder = request.scope.folder.as_str();
+        let in_folder = self.folders.iter().any(|path| {
+            let path = path.trim_matches('/');
+            folder == path || folder.starts_with(&format!("{}/", path))
+        });
+        let has_id = request
+            .request
+            .id
+            .as_ref()
+            .is_some_and(|id| self.requests.contains(id));
+        let tags = request.request.tags.as_deref().unwrap_or_default();
+        let has_tag = self.tags.iter().any(|tag| tags.contains(tag));
+
+        (self.folders.is_empty() || in_folder)
+            && (self.requests.is_empty() || has_id)
+            && (self.tags.is_empty() || has_tag)
+    }
+
+    /// Indices of the selected requests and their dependencies, in run order
+    pub fn select(
+        &self,
+        requests: &[ScopedRequest],
+        graph: &DependencyGraph,
+    ) -> Result<Vec<usize>> {
+        if self.is_empty() {
+            return Ok((0..requests.len()).collect());
+        }
+        let mut selected = vec![false; requests.len()];
+        for (index, request) in requests.iter().enumerate() {
+            if self.matches(request) {
+                selected[index] = true;
+                for ancestor in graph.ancestors(index) {
+                    selected[ancestor] = true;
+                }
+            }
+        }
+        if !selected.contains(&true) {
+            return Err(Error::ValidationError(format!(
+                "No requests match the selection ({})",
+                self.describe()
+            )));
+        }
+        Ok((0..requests.len()).filter(|i| selected[*i]).collect())
+    }
+
+    fn describe(&self) -> String {
+        [
+            ("folder", &self.folders),
+            ("request", &self.requests),
+            ("tag", &self.tags),
+        ]
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(kind, values)| format!("{}: {}", kind, values.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+    }
 }
 
 /// Result of a collection run
@@ -130,23 +722,142 @@ pub struct CollectionResult {
     pub variables: HashMap<String, String>,
 }
 
+/// HTTP state shared by every request in a collection run
+struct Session<'a> {
+    client: &'a Client,
+    jar: Option<&'a Jar>,
+    tokens: &'a TokenCache,
+    schemas: &'a SchemaCache,
+    interpolator: &'a Interpolator,
+    /// Names of the variables whose values are secrets
+    secret_names: &'a [&'a str],
+}
+
+impl<'a> Session<'a> {
+    /// The same session with the dynamic variables of one request
+    fn with_interpolator<'b>(&self, interpolator: &'b Interpolator) -> Session<'b>
+    where
+        'a: 'b,
+    {
+        Session {
+            interpolator,
+            ..*self
+        }
+    }
+
+    /// Interpolate variables and dynamic variables in a string
+    fn interpolate(&self, input: &str, variables: &HashMap<String, String>) -> Result<String> {
+        // Unknown variables are left as is
+        self.interpolator.interpolate(input, variables, |var_name| {
+            warn!("Variable '{}' not found during interpolation", var_name)
+        })
+    }
+}
+
+/// A collection request with its URL, headers and body resolved once, so
+/// scripts see the values that are sent
+struct ResolvedRequest {
+    url: String,
+    headers: HashMap<String, String>,
+    body: Option<Value>,
+}
+
+impl ResolvedRequest {
+    /// Resolve the URL, the default and request headers and the body with `resolve`
+    fn new(
+        request: &CollectionRequest,
+        collection: &ApiCollection,
+        mut resolve: impl FnMut(&str) -> Result<String>,
+    ) -> Result<Self> {
+        let mut headers = HashMap::new();
+        let default_headers = collection
+            .defaults
+            .as_ref()
+            .and_then(|d| d.headers.as_ref());
+        // Request headers override defaults
+        for (key, value) in default_headers
+            .into_iter()
+            .chain(&request.headers)
+            .flatten()
+        {
+            headers.insert(key.clone(), resolve(value)?);
+        }
+        let body = match &request.body {
+            Some(body) => Some(serde_json::from_str::<Value>(&resolve(
+                &serde_json::to_string(body)?,
+            )?)?),
+            None => None,
+        };
+
+        Ok(Self {
+            url: resolve(&request.url)?,
+            headers,
+            body,
+        })
+    }
+
+    /// The request as scripts see it
+    fn script_value(&self, request: &CollectionRequest) -> Value {
+        json!({
+            "name": request.name,
+            "method": request.method,
+            "url": self.url,
+            "headers": self.headers,
+            "body": self.body,
+        })
+    }
+}
+
 /// API collection runner
 pub struct ApiCollectionRunner {
     client: Client,
     api_runner: ApiTestRunner,
+    client_options: ClientOptions,
+    interpolator: Interpolator,
+    filter: CollectionFilter,
+    overrides: HashMap<String, String>,
 }
 
 impl ApiCollectionRunner {
     /// Create a new API collection runner
     pub fn new() -> Self {
         Self {
-            client: Client::builder()
-                .timeout(Duration::from_secs(30))
+            client: timing::timed_client_builder()
                 .build()
                 .unwrap_or_else(|_| Client::new()),
             api_runner: ApiTestRunner::new(),
+            client_options: ClientOptions::default(),
+            interpolator: Interpolator::new(),
+            filter: CollectionFilter::default(),
+            overrides: HashMap::new(),
         }
     }
+
+    /// Variables that override collection variables and environments, such as
+    /// those from an environment file and the command line
+    pub fn with_variables(mut self, variables: HashMap<String, String>) -> Self {
+        self.overrides = variables;
+        self
+    }
+
+    /// Run only part of every collection, with the dependencies of the selected requests
+    pub fn with_filter(mut self, filter: CollectionFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Generate the same dynamic variable values on every run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.interpolator = Interpolator::seeded(seed);
+        self
+    }
+
+    /// Override the TLS and proxy settings of every collection
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.api_runner = self.api_runner.with_client_options(client_options.clone());
+        self.client_options = client_options;
+        self
+    }
 }
 
 impl Default for ApiCollectionRunner {
@@ -156,10 +867,24 @@ impl Default for ApiCollectionRunner {
 }
 
 impl ApiCollectionRunner {
-    /// Load an API collection from a file
-    pub fn load_collection(path: &Path) -> Result<ApiCollection> {
-        let content = std::fs::read_to_string(path)?;
-        let collection: ApiCollection = serde_json::from_str(&content)?;
+    /// Load an API collection from a file, substituting `${NAME}` placeholders
+    /// from the environment and validating it
+    pub fn load_collection(path: &Path, environment: &Environment) -> Result<ApiCollection> {
+        let mut collection: ApiCollection =
+            common::load_typed_config(path, environment, Some("api_collection"))?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        collection.base_dir = base_dir.to_path_buf();
+
+        // Resolve body file paths relative to the collection file
+        resolve_body_paths(
+            &mut collection.requests,
+            collection.folders.iter_mut().flatten(),
+            base_dir,
+        );
+
+        // Report dependency problems before any request is sent
+        DependencyGraph::build(&collection)?;
+
         Ok(collection)
     }
 
@@ -171,6 +896,49 @@ impl ApiCollectionRunner {
     ) -> Result<CollectionResult> {
         let start = Instant::now();
 
+        // Select the requests to run, with their dependencies
+        let all_requests = collection.scoped_requests();
+        let all_graph = DependencyGraph::for_requests(
+            &collection.name,
+            &all_requests.iter().map(|r| r.request).collect::<Vec<_>>(),
+        )?;
+        let planned: Vec<&ScopedRequest> = self
+            .filter
+            .select(&all_requests, &all_graph)?
+            .into_iter()
+            .map(|index| &all_requests[index])
+            .collect();
+        let graph = DependencyGraph::for_requests(
+            &collection.name,
+            &planned.iter().map(|r| r.request).collect::<Vec<_>>(),
+        )?;
+
+        // Build the client once for the whole collection, with a fresh cookie jar if enabled
+        let cookies_enabled = collection
+            .run_options
+            .as_ref()
+            .and_then(|opts| opts.cookies)
+            .unwrap_or(false);
+        if collection.cookies.is_some() && !cookies_enabled {
+            return Err(Error::ValidationError(
+                "Seeding cookies requires run_options.cookies to be true".to_string(),
+            ));
+        }
+        let jar = cookies_enabled.then(|| Arc::new(Jar::default()));
+        let client = match &jar {
+            Some(jar) => collection
+                .client_options()
+                .merge(&self.client_options)
+                .configure(timing::timed_client_builder().cookie_provider(jar.clone()))?
+                .build()?,
+            None => client::client_for(
+                &self.client,
+                &collection.client_options(),
+                &self.client_options,
+                timing::timed_client_builder(),
+            )?,
+        };
+
         // Initialize variables with collection variables and environment variables
         let mut variables = HashMap::new();
         if let Some(collection_vars) = &collection.variables {
@@ -184,15 +952,55 @@ impl ApiCollectionRunner {
             }
         }
 
-        // Add environment variables from the system
-        for (key, value) in std::env::vars() {
-            variables.insert(key, value);
+        // Add the process environment variables the collection opts in to
+        if let Some(import_env) = &collection.import_env {
+            variables.extend(import_env.select(std::env::vars()));
+        }
+
+        // Environment file and command line variables win over the collection
+        variables.extend(self.overrides.clone());
+
+        // Secret values are masked in log lines from here on
+        let mut secret_values: Vec<String> = planned
+            .iter()
+            .flat_map(|planned| self.auth_secrets(&planned.scope.collection, &variables))
+            .collect();
+        secret_values.extend(secret_variables(collection, &variables));
+        secrets::register(secret_values.clone());
+
+        // Seed the cookie jar
+        if let (Some(jar), Some(seeds)) = (&jar, &collection.cookies) {
+            cookies::seed(jar, seeds, |value| {
+                self.interpolate_variables(value, &variables)
+            })?;
         }
 
+        // OAuth2 tokens are fetched on first use and reused for the rest of the run
+        let tokens = TokenCache::new();
+
+        // Response schemas are compiled once, reporting invalid schemas before any request is sent
+        let schemas = SchemaCache::new(collection.schemas.as_ref(), &collection.base_dir);
+        for schema in planned
+            .iter()
+            .filter_map(|p| p.request.json_schema.as_ref())
+        {
+            schemas.compile(schema)?;
+        }
+
+        let secret_names = secret_names(collection);
+        let session = Session {
+            client: &client,
+            jar: jar.as_deref(),
+            tokens: &tokens,
+            schemas: &schemas,
+            interpolator: &self.interpolator,
+            secret_names: &secret_names,
+        };
+
         info!(
             "Running collection: {} with {} requests",
             collection.name,
-            collection.requests.len()
+            planned.len()
         );
         info!("Using environment: {}", environment);
 
@@ -215,101 +1023,147 @@ impl ApiCollectionRunner {
             .and_then(|opts| opts.delay_between_requests_ms)
             .unwrap_or(0);
 
-        // Track completed requests and their results
-        let mut completed_requests = HashMap::new();
-        let mut request_results = Vec::new();
+        let mut request_results: Vec<TestResult> = Vec::new();
 
         // Run requests
         if sequential {
-            // Sequential execution
-            for request in &collection.requests {
-                // Check dependencies
-                if let Some(dependencies) = &request.depends_on {
-                    for dep_id in dependencies {
-                        if !completed_requests.contains_key(dep_id) {
-                            return Err(Error::ValidationError(format!(
-                                "Request '{}' depends on '{}', which has not been executed",
-                                request.name, dep_id
-                            )));
-                        }
+            // Sequential execution in dependency order.
+            // Values set during the run take precedence over folder variables.
+            let mut run_variables = HashMap::new();
+            for &index in graph.order() {
+                let ScopedRequest { request, scope } = planned[index];
+                let mut scoped = scope.with_variables(&variables, &self.overrides);
+                scoped.extend(run_variables.clone());
+
+                // Execute request, once per iteration of its loops
+                let interpolator = self.interpolator.for_request(index);
+                let session = session.with_interpolator(&interpolator);
+                let results = self
+                    .run_step(&session, request, &scope.collection, environment, &scoped)
+                    .await;
+
+                // Update variables with captured values and the current OAuth2 token
+                for result in &results {
+                    run_variables.extend(self.capture_variables(result));
+                }
+                run_variables.extend(tokens.variables().await);
+                variables.extend(run_variables.clone());
+                secrets::register(secret_variables(collection, &variables));
+
+                let failed = results.iter().any(|r| r.status == "failed");
+                request_results.extend(results);
+
+                // Check if we should stop on failure, skipping the remaining requests
+                if stop_on_failure && failed {
+                    let remaining = graph.order().iter().skip_while(|&&i| i != index).skip(1);
+                    for &skipped in remaining {
+                        request_results.push(outcome_result(
+                            &planned[skipped].request.name,
+                            "skipped",
+                            stopped_reason(&request.name),
+                        ));
                     }
+                    break;
                 }
 
-                // Execute request
-                let result = self
-                    .execute_request(request, collection, &variables)
-                    .await?;
-
-                // Store result
-                if let Some(id) = &request.id {
-                    completed_requests.insert(id.clone(), result.clone());
+                // Delay between requests if specified
+                if delay > 0 {
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
                 }
-                request_results.push(result.clone());
-
-                // Update variables with captured values
-                if let Some(captures) = &request.capture {
-                    if let Some(details) = &result.details {
-                        if let Some(response_body) = details.get("response_body") {
-                            for (var_name, json_path) in captures {
-                                if let Ok(values) = jsonpath::select(response_body, json_path) {
-                                    if !values.is_empty() {
-                                        let value = &values[0];
-                                        let value_str = match value {
-                                            Value::String(s) => s.clone(),
-                                            Value::Number(n) => n.to_string(),
-                                            Value::Bool(b) => b.to_string(),
-                                            Value::Null => "null".to_string(),
-                                            Value::Object(_) | Value::Array(_) => {
-                                                serde_json::to_string(value)
-                                                    .unwrap_or_else(|_| "{}".to_string())
-                                            }
-                                        };
-
-                                        info!(
-                                            "Captured variable '{}' with value: {}",
-                                            var_name, value_str
-                                        );
-                                        variables.insert(var_name.clone(), value_str);
-                                    } else {
-                                        warn!(
-                                            "JSONPath '{}' matched no values in response",
-                                            json_path
-                                        );
-                                    }
-                                } else {
-                                    warn!(
-                                        "Failed to evaluate JSONPath '{}' on response",
-                                        json_path
-                                    );
-                                }
-                            }
-                        } else {
-                            warn!("No response body found in result details");
-                        }
-                    } else {
-                        warn!("No details found in result");
+            }
+        } else {
+            // Parallel execution: requests start as soon as their dependencies have finished
+            let max_concurrency = collection
+                .run_options
+                .as_ref()
+                .and_then(|opts| opts.max_concurrency)
+                .unwrap_or_else(default_max_concurrency)
+                .max(1);
+            let count = planned.len();
+            let mut started = vec![false; count];
+            let mut results: Vec<Option<Vec<TestResult>>> = (0..count).map(|_| None).collect();
+            let mut captured: Vec<HashMap<String, String>> = vec![HashMap::new(); count];
+            let mut in_flight = FuturesUnordered::new();
+            let mut stopped_by: Option<&str> = None;
+
+            loop {
+                // Start ready requests in file order while there is capacity
+                let ready: Vec<usize> = (0..count)
+                    .filter(|index| {
+                        !started[*index]
+                            && graph
+                                .dependencies(*index)
+                                .iter()
+                                .all(|d| results[*d].is_some())
+                    })
+                    .collect();
+                for index in ready {
+                    if stopped_by.is_some() || in_flight.len() >= max_concurrency {
+                        break;
                     }
+                    started[index] = true;
+
+                    // A request only sees the captures of its ancestors
+                    let ScopedRequest { request, scope } = planned[index];
+                    let mut scoped = scope.with_variables(&variables, &self.overrides);
+                    for ancestor in graph.ancestors(index) {
+                        scoped.extend(captured[ancestor].clone());
+                    }
+
+                    let session = &session;
+                    in_flight.push(async move {
+                        let interpolator = self.interpolator.for_request(index);
+                        let session = session.with_interpolator(&interpolator);
+                        let result = self
+                            .run_step(&session, request, &scope.collection, environment, &scoped)
+                            .await;
+                        if delay > 0 {
+                            tokio::time::sleep(Duration::from_millis(delay)).await;
+                        }
+                        (index, result)
+                    });
                 }
 
-                // Check if we should stop on failure
-                if stop_on_failure && result.status != "passed" {
+                let Some((index, step_results)) = in_flight.next().await else {
                     break;
+                };
+                for result in &step_results {
+                    captured[index].extend(self.capture_variables(result));
+                }
+                variables.extend(tokens.variables().await);
+                secrets::register(secret_variables(collection, &captured[index]));
+                secrets::register(secret_variables(collection, &variables));
+                if stop_on_failure && step_results.iter().any(|r| r.status == "failed") {
+                    stopped_by.get_or_insert(&planned[index].request.name);
                 }
+                results[index] = Some(step_results);
+            }
 
-                // Delay between requests if specified
-                if delay > 0 {
-                    tokio::time::sleep(Duration::from_millis(delay)).await;
+            // Requests that never started are reported as skipped
+            if let Some(failed) = stopped_by {
+                for (index, result) in results.iter_mut().enumerate() {
+                    if result.is_none() {
+                        *result = Some(vec![outcome_result(
+                            &planned[index].request.name,
+                            "skipped",
+                            stopped_reason(failed),
+                        )]);
+                    }
                 }
             }
-        } else {
-            // Parallel execution (not implemented yet)
-            return Err(Error::ValidationError(
-                "Parallel execution not yet implemented".to_string(),
-            ));
+
+            // Results and captures are reported in file order
+            for captures in captured {
+                variables.extend(captures);
+            }
+            request_results = results.into_iter().flatten().flatten().collect();
         }
 
-        // Calculate overall status
-        let status = if request_results.iter().all(|r| r.status == "passed") {
+        // Calculate overall status, skipped requests do not fail the collection
+        let status = if request_results
+            .iter()
+            .all(|r| r.status == "passed" || r.status == "skipped")
+        {
             "passed"
         } else {
             "failed"
@@ -317,6 +1171,20 @@ impl ApiCollectionRunner {
 
         let duration = start.elapsed().as_secs_f64();
 
+        // Mask secrets in everything the result exposes
+        secret_values.extend(secret_variables(collection, &variables));
+        let redactor = Redactor::new(secret_values);
+        for result in &mut request_results {
+            redactor.redact_result(result);
+        }
+        for (name, value) in variables.iter_mut() {
+            *value = if secret_names.contains(&name.as_str()) {
+                secrets::MASK.to_string()
+            } else {
+                redactor.redact(value)
+            };
+        }
+
         Ok(CollectionResult {
             name: collection.name.clone(),
             status: status.to_string(),
@@ -327,143 +1195,406 @@ impl ApiCollectionRunner {
         })
     }
 
-    /// Execute a single request from the collection
-    async fn execute_request(
+    /// Run a request with its conditions and loops, returning one result per iteration.
+    ///
+    /// An iteration that cannot be sent is reported as failed, so the run goes on.
+    async fn run_step(
         &self,
+        session: &Session<'_>,
         request: &CollectionRequest,
         collection: &ApiCollection,
+        environment: &str,
         variables: &HashMap<String, String>,
-    ) -> Result<TestResult> {
-        let start = Instant::now();
+    ) -> Vec<TestResult> {
+        match skip_reason(request, environment, variables) {
+            Ok(None) => {}
+            Ok(Some(reason)) => {
+                info!("Skipping '{}': {}", request.name, reason);
+                return vec![outcome_result(&request.name, "skipped", reason)];
+            }
+            Err(e) => return vec![outcome_result(&request.name, "failed", e.to_string())],
+        }
 
-        // Interpolate variables in URL
-        let url = self.interpolate_variables(&request.url, variables)?;
+        let Some(for_each) = &request.for_each else {
+            return self
+                .run_repeated(
+                    session,
+                    request,
+                    collection,
+                    environment,
+                    variables,
+                    &request.name,
+                )
+                .await
+                .unwrap_or_else(|e| vec![outcome_result(&request.name, "failed", e.to_string())]);
+        };
+        let items = match for_each_items(for_each, variables) {
+            Ok(items) => items,
+            Err(e) => return vec![outcome_result(&request.name, "failed", e.to_string())],
+        };
+        if items.is_empty() {
+            let reason = format!("'{}' has no items", for_each.items);
+            return vec![outcome_result(&request.name, "skipped", reason)];
+        }
 
-        // For simple requests, delegate to the ApiTestRunner
-        // This reuses validation logic and reduces code duplication
-        if request.is_simple_request() {
-            // Create a simplified API test config for the ApiTestRunner
-            let mut headers = HashMap::new();
-
-            // Add headers from collection defaults
-            if let Some(defaults) = &collection.defaults {
-                if let Some(default_headers) = &defaults.headers {
-                    for (key, value) in default_headers {
-                        let interpolated_value = self.interpolate_variables(value, variables)?;
-                        headers.insert(key.clone(), interpolated_value);
-                    }
+        // Each iteration sees the item and the captures of earlier iterations
+        let mut results = Vec::new();
+        let mut scoped = variables.clone();
+        for (index, item) in items.iter().enumerate() {
+            scoped.insert(for_each.variable.clone(), capture::to_variable(item));
+            scoped.insert(format!("{}_index", for_each.variable), index.to_string());
+            if let Value::Object(fields) = item {
+                for (field, value) in fields {
+                    scoped.insert(
+                        format!("{}.{}", for_each.variable, field),
+                        capture::to_variable(value),
+                    );
                 }
             }
 
-            // Add headers from request (overriding defaults)
-            if let Some(req_headers) = &request.headers {
-                for (key, value) in req_headers {
-                    let interpolated_value = self.interpolate_variables(value, variables)?;
-                    headers.insert(key.clone(), interpolated_value);
-                }
+            let label = format!("{} [{}/{}]", request.name, index + 1, items.len());
+            let iteration = self
+                .run_repeated(session, request, collection, environment, &scoped, &label)
+                .await
+                .unwrap_or_else(|e| vec![outcome_result(&label, "failed", e.to_string())]);
+            for result in &iteration {
+                scoped.extend(self.capture_variables(result));
             }
+            results.extend(iteration);
+        }
 
-            // Create a simplified test config
-            let test_config = serde_json::json!({
-                "name": request.name,
-                "description": request.description,
-                "url": url,
-                "method": request.method,
-                "headers": headers,
-                "expected_status": request.expected_status,
-                "expected_body": request.expected_body,
-                "timeout": collection.defaults.as_ref().and_then(|d| d.timeout).unwrap_or(30),
-                "retries": collection.defaults.as_ref().and_then(|d| d.retries).unwrap_or(3)
-            });
+        results
+    }
 
-            // Use the ApiTestRunner to execute the request
-            return self.api_runner.run(&test_config).await;
+    /// Send a request once, or until its `repeat_until` conditions hold
+    async fn run_repeated(
+        &self,
+        session: &Session<'_>,
+        request: &CollectionRequest,
+        collection: &ApiCollection,
+        environment: &str,
+        variables: &HashMap<String, String>,
+        label: &str,
+    ) -> Result<Vec<TestResult>> {
+        let Some(repeat) = &request.repeat_until else {
+            let mut result = self
+                .execute_request(session, request, collection, environment, variables)
+                .await?;
+            result.name = label.to_string();
+            return Ok(vec![result]);
+        };
+
+        let max_attempts = repeat.max_attempts.max(1);
+        let mut results = Vec::new();
+        for attempt in 1..=max_attempts {
+            let mut result = self
+                .execute_request(session, request, collection, environment, variables)
+                .await?;
+            result.name = format!("{} (attempt {})", label, attempt);
+
+            let body = result
+                .details
+                .as_ref()
+                .and_then(|details| details.get("response_body"))
+                .cloned()
+                .unwrap_or(Value::Null);
+            let checks = assertions::evaluate_all(&repeat.conditions, &body);
+            let met = checks.iter().all(|check| check.passed);
+            if let Some(details) = result.details.as_mut() {
+                details["repeat_until"] = json!(checks);
+            }
+
+            // A failed attempt ends the polling
+            if result.status != "passed" || met {
+                results.push(result);
+                break;
+            }
+            if attempt == max_attempts {
+                let failures = assertions::failure_messages(&checks).join("; ");
+                fail_result(
+                    &mut result,
+                    AssertionResult::check("repeat_until", json!(true), json!(false), false)
+                        .with_message(format!(
+                            "repeat_until conditions not met after {} attempts: {}",
+                            max_attempts, failures
+                        )),
+                );
+                results.push(result);
+                break;
+            }
+
+            results.push(result);
+            tokio::time::sleep(Duration::from_millis(repeat.interval_ms)).await;
         }
 
-        // Determine HTTP method
-        let method = Method::from_bytes(request.method.as_bytes())
-            .map_err(|e| Error::ValidationError(format!("Invalid HTTP method: {}", e)))?;
+        Ok(results)
+    }
 
-        // Build request with default timeout from collection or default value
-        let timeout = collection
-            .defaults
-            .as_ref()
-            .and_then(|d| d.timeout)
-            .unwrap_or(30);
+    /// Credentials of the collection authentication, with variables resolved
+    fn auth_secrets(
+        &self,
+        collection: &ApiCollection,
+        variables: &HashMap<String, String>,
+    ) -> Vec<String> {
+        let Some(auth) = &collection.auth else {
+            return Vec::new();
+        };
+        let resolve = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|value| self.interpolate_variables(value, variables).ok())
+        };
+        [
+            &auth.password,
+            &auth.token,
+            &auth.key_value,
+            &auth.client_secret,
+            &auth.refresh_token,
+            &auth.secret,
+            &auth.secret_access_key,
+            &auth.session_token,
+        ]
+        .into_iter()
+        .filter_map(resolve)
+        .collect()
+    }
 
-        let mut req_builder = self
-            .client
-            .request(method, &url)
-            .timeout(Duration::from_secs(timeout));
+    /// Values captured from a request result, by variable name
+    fn capture_variables(&self, result: &TestResult) -> HashMap<String, String> {
+        let mut captured = HashMap::new();
+        let Some(details) = &result.details else {
+            warn!("No details found in result");
+            return captured;
+        };
 
-        // Add headers from collection defaults
-        if let Some(defaults) = &collection.defaults {
-            if let Some(default_headers) = &defaults.headers {
-                for (key, value) in default_headers {
-                    let interpolated_value = self.interpolate_variables(value, variables)?;
-                    req_builder = req_builder.header(key, interpolated_value);
+        // Variables set by scripts win over captures
+        for key in ["captured", "script_variables"] {
+            if let Some(Value::Object(values)) = details.get(key) {
+                for (name, value) in values {
+                    if let Some(value) = value.as_str() {
+                        captured.insert(name.clone(), value.to_string());
+                    }
                 }
             }
         }
 
-        // Add headers from request (overriding defaults)
-        if let Some(headers) = &request.headers {
-            for (key, value) in headers {
-                let interpolated_value = self.interpolate_variables(value, variables)?;
-                req_builder = req_builder.header(key, interpolated_value);
+        captured
+    }
+
+    /// Execute a single request from the collection
+    async fn execute_request(
+        &self,
+        session: &Session<'_>,
+        request: &CollectionRequest,
+        collection: &ApiCollection,
+        environment: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<TestResult> {
+        let start = Instant::now();
+
+        // Pre-request scripts may set variables used by the request
+        let pre_scripts = [&collection.pre_request, &request.pre_request];
+        let post_scripts = [&collection.post_response, &request.post_response];
+        let has_scripts = pre_scripts.iter().chain(&post_scripts).any(|s| s.is_some());
+        let mut variables = Cow::Borrowed(variables);
+        let mut script_variables = HashMap::new();
+        let mut script_checks = Vec::new();
+        for script in pre_scripts.into_iter().flatten() {
+            // Dynamic variables are generated once, when the request is resolved after these scripts
+            let script_request = ResolvedRequest::new(request, collection, |value| {
+                Ok(interpolation::replace_variables(value, &variables))
+            })?;
+            let outcome = scripting::run(
+                ScriptPhase::PreRequest,
+                script,
+                &variables,
+                &script_request.script_value(request),
+                None,
+            );
+            script_checks.extend(outcome.checks);
+            variables.to_mut().extend(outcome.variables.clone());
+            script_variables.extend(outcome.variables);
+            if outcome.failed {
+                // The request is not sent when its pre-request script fails
+                let validation_issues = assertions::failure_messages(&script_checks);
+                return Ok(TestResult {
+                    name: request.name.clone(),
+                    status: "failed".to_string(),
+                    duration: start.elapsed().as_secs_f64(),
+                    details: Some(json!({
+                        "assertions": script_checks,
+                        "validation_issues": validation_issues,
+                        "script_variables": script_variables,
+                    })),
+                    timestamp: Utc::now().to_rfc3339(),
+                });
             }
         }
+        let variables = variables.as_ref();
 
-        // Add authentication if specified at collection level
-        if let Some(auth) = &collection.auth {
-            req_builder = self.add_authentication(req_builder, auth, variables)?;
-        }
+        // Interpolate variables in the URL, headers and body
+        let resolved = ResolvedRequest::new(request, collection, |value| {
+            session.interpolate(value, variables)
+        })?;
 
-        // Add request body if specified
-        if let Some(body) = &request.body {
-            // Interpolate variables in the body
-            let body_str = serde_json::to_string(body)?;
-            let interpolated_body_str = self.interpolate_variables(&body_str, variables)?;
-            let interpolated_body: Value = serde_json::from_str(&interpolated_body_str)?;
-            req_builder = req_builder.json(&interpolated_body);
+        // For simple requests, delegate to the ApiTestRunner
+        // This reuses validation logic and reduces code duplication.
+        // With a cookie jar or collection auth every request must go through the collection client.
+        if request.is_simple_request()
+            && session.jar.is_none()
+            && collection.auth.is_none()
+            && !has_scripts
+        {
+            // Create a simplified test config
+            let mut test_config = serde_json::json!({
+                "name": request.name,
+                "description": request.description.clone().unwrap_or_default(),
+                "environment": environment,
+                "url": resolved.url,
+                "method": request.method,
+                "headers": resolved.headers,
+                "body": resolved.body,
+                "body_type": request.body_type,
+                "content_type": request.content_type,
+                "expected_status": request.expected_status,
+                "expected_body": request.expected_body,
+                "assertions": request.assertions,
+                "tls": collection.tls,
+                "proxy": collection.proxy,
+                "timeout": collection.defaults.as_ref().and_then(|d| d.timeout).unwrap_or(30),
+                "retry": request.retry_policy(collection.defaults.as_ref())
+            });
+
+            if let (Some(config), Value::Object(limits)) = (
+                test_config.as_object_mut(),
+                serde_json::to_value(&request.timing_limits)?,
+            ) {
+                config.extend(limits);
+            }
+
+            // Use the ApiTestRunner to execute the request
+            return self.api_runner.run(&test_config).await;
         }
 
-        // Send the request
-        info!("Sending {} request to {}", request.method, url);
-        let response = req_builder.send().await?;
-
-        // Process response
-        let status = response.status();
-        let headers = response.headers().clone();
-        let response_body_text = response.text().await?;
-        let response_body: Value = serde_json::from_str(&response_body_text).unwrap_or_else(|_| {
+        // Send with the retry policy, building the request again for each attempt
+        let policy = request.retry_policy(collection.defaults.as_ref());
+        let outcome = retry::execute_with_retry(&policy, || {
+            self.send_request(session, request, collection, &resolved, variables)
+        })
+        .await;
+        let BufferedResponse {
+            status,
+            headers,
+            body: raw_body,
+            timings,
+        } = outcome.result?;
+        // A body that parses as JSON is checked as JSON whatever its Content-Type
+        let raw_body = raw_body.or_json();
+        let response_body: Value = raw_body.json().cloned().unwrap_or_else(|| {
             // If not valid JSON, return as string
-            Value::String(response_body_text.clone())
+            Value::String(raw_body.text())
         });
 
         // Validate response
         let duration = start.elapsed().as_secs_f64();
-        let validation_result =
-            self.validate_response(&status, &headers, &response_body, request, duration)?;
+        let mut checks = self.validate_response(
+            &status,
+            &headers,
+            &response_body,
+            &raw_body,
+            request,
+            duration,
+        )?;
+        checks.extend(request.timing_limits.check(&timings));
+        if let Some(schema) = &request.json_schema {
+            checks.push(match raw_body.json() {
+                Some(json) => session.schemas.check(schema, json)?,
+                None => AssertionResult::check("json_schema", json!("json"), json!("text"), false)
+                    .with_message("json_schema requires a JSON response".to_string()),
+            });
+        }
+        // Report whether the polling condition of the retry policy was reached
+        checks.extend(outcome.retry_until);
+
+        // Cookies known after the response: the jar contents or the cookies it set
+        let response_cookies = match session.jar {
+            Some(jar) => cookies::jar_cookies(jar, &resolved.url),
+            None => cookies::response_cookies(&headers),
+        };
+        if let Some(list) = &request.cookie_assertions {
+            checks.extend(cookies::check(list, &response_cookies));
+        }
+
+        // Required captures that match nothing fail the request
+        let captured = request.capture.as_ref().map(|captures| {
+            let response = CaptureResponse {
+                status: status.as_u16(),
+                headers: &headers,
+                body: &response_body,
+                text: &raw_body.text(),
+                cookies: &response_cookies,
+            };
+            let (captured, capture_checks) =
+                capture::evaluate(captures, &response, session.secret_names);
+            checks.extend(capture_checks);
+            captured
+        });
+
+        // Post-response scripts see the response and may add checks and variables
+        let mut variables = Cow::Borrowed(variables);
+        let script_request = resolved.script_value(request);
+        for script in post_scripts.into_iter().flatten() {
+            let script_response = json!({
+                "status": status.as_u16(),
+                "headers": headers
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+                    .collect::<HashMap<_, _>>(),
+                "body": response_body,
+                "text": raw_body.text(),
+                "time_ms": start.elapsed().as_millis() as u64,
+            });
+            let outcome = scripting::run(
+                ScriptPhase::PostResponse,
+                script,
+                &variables,
+                &script_request,
+                Some(&script_response),
+            );
+            variables.to_mut().extend(outcome.variables.clone());
+            script_variables.extend(outcome.variables);
+            script_checks.extend(outcome.checks);
+        }
+        checks.extend(script_checks);
 
         // Create response details
         let mut details = json!({
             "status_code": status.as_u16(),
             "response_time": duration,
             "response_body": response_body,
+            "timing": timings,
+            "attempts": outcome.attempts,
+            "cookies": response_cookies,
             "headers": headers
                 .iter()
                 .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
                 .collect::<std::collections::HashMap<_, _>>()
         });
 
-        // Add validation results if any
-        let has_validation_issues = if let Some(ref issues) = validation_result {
-            details["validation_issues"] = json!(issues);
-            true
-        } else {
-            false
-        };
+        if let Some(captured) = captured {
+            details["captured"] = json!(captured);
+        }
+        if has_scripts {
+            details["script_variables"] = json!(script_variables);
+        }
+
+        // Record every check and add validation issues if any
+        let validation_issues = assertions::failure_messages(&checks);
+        details["assertions"] = json!(checks);
+        let has_validation_issues = !validation_issues.is_empty();
+        if has_validation_issues {
+            details["validation_issues"] = json!(validation_issues);
+        }
 
         // Determine test status
         let test_status = if status.is_success() && !has_validation_issues {
@@ -481,116 +1612,122 @@ impl ApiCollectionRunner {
         })
     }
 
-    /// Add authentication to the request
-    fn add_authentication(
+    /// Build and send a request once, reading the whole response
+    async fn send_request(
         &self,
-        mut req_builder: reqwest::RequestBuilder,
-        auth: &CollectionAuth,
+        session: &Session<'_>,
+        request: &CollectionRequest,
+        collection: &ApiCollection,
+        resolved: &ResolvedRequest,
         variables: &HashMap<String, String>,
-    ) -> Result<reqwest::RequestBuilder> {
-        match auth.auth_type.as_str() {
-            "basic" => {
-                let username = auth.username.as_ref().ok_or_else(|| {
-                    Error::ValidationError("Username required for basic auth".to_string())
-                })?;
-                let password = auth.password.as_ref().ok_or_else(|| {
-                    Error::ValidationError("Password required for basic auth".to_string())
-                })?;
+    ) -> Result<BufferedResponse> {
+        // Determine HTTP method
+        let method = Method::from_bytes(request.method.as_bytes())
+            .map_err(|e| Error::ValidationError(format!("Invalid HTTP method: {}", e)))?;
 
-                let interpolated_username = self.interpolate_variables(username, variables)?;
-                let interpolated_password = self.interpolate_variables(password, variables)?;
+        // Build request with default timeout from collection or default value
+        let timeout = collection
+            .defaults
+            .as_ref()
+            .and_then(|d| d.timeout)
+            .unwrap_or(30);
 
+        let mut req_builder = session
+            .client
+            .request(method, &resolved.url)
+            .timeout(Duration::from_secs(timeout));
+
+        // Add the default and request headers
+        for (key, value) in &resolved.headers {
+            req_builder = req_builder.header(key, value);
+        }
+
+        // Add authentication if specified at collection level.
+        // OAuth2 tokens and signatures are added when sending.
+        let send_auth = match &collection.auth {
+            Some(auth) if auth.applies_on_send() => {
+                Some(auth.resolve(|value| session.interpolate(value, variables))?)
+            }
+            Some(auth) => {
                 req_builder =
-                    req_builder.basic_auth(interpolated_username, Some(interpolated_password));
+                    auth.apply(req_builder, |value| session.interpolate(value, variables))?;
+                None
             }
-            "bearer" => {
-                let token = auth.token.as_ref().ok_or_else(|| {
-                    Error::ValidationError("Token required for bearer auth".to_string())
-                })?;
+            None => None,
+        };
 
-                let interpolated_token = self.interpolate_variables(token, variables)?;
-                req_builder = req_builder.bearer_auth(interpolated_token);
+        // Add request body if specified
+        if let Some(body) = &resolved.body {
+            if let Some(auth) = &send_auth {
+                signing::check_body_type(auth, request.body_type)?;
             }
-            "api_key" => {
-                let key_name = auth.key_name.as_ref().ok_or_else(|| {
-                    Error::ValidationError("Key name required for API key auth".to_string())
-                })?;
-                let key_value = auth.key_value.as_ref().ok_or_else(|| {
-                    Error::ValidationError("Key value required for API key auth".to_string())
-                })?;
+            req_builder = request_body::apply_body(
+                req_builder,
+                request.body_type,
+                body,
+                request.content_type.as_deref(),
+            )?;
+        }
 
-                let interpolated_key_value = self.interpolate_variables(key_value, variables)?;
+        let mut timings = PhaseTimings::default();
 
-                // Determine if the key should be in header or query parameter
-                match auth.key_in.as_deref() {
-                    Some("query") => {
-                        // Add as query parameter
-                        req_builder = req_builder.query(&[(key_name, interpolated_key_value)]);
-                    }
-                    _ => {
-                        // Default to header
-                        req_builder = req_builder.header(key_name, interpolated_key_value);
-                    }
+        // Send the request
+        info!("Sending {} request to {}", request.method, resolved.url);
+        let sent = Instant::now();
+        let (response, setup) = timing::measure(async {
+            match &send_auth {
+                Some(auth) if auth.is_oauth2() => {
+                    session.tokens.send(session.client, auth, req_builder).await
                 }
+                Some(auth) => signing::send(session.client, auth, req_builder).await,
+                None => Ok(req_builder.send().await?),
             }
-            _ => {
-                return Err(Error::ValidationError(format!(
-                    "Unsupported authentication type: {}",
-                    auth.auth_type
-                )));
-            }
-        }
-
-        Ok(req_builder)
+        })
+        .await;
+        timings.record_headers(sent.elapsed(), setup);
+        let response = response?;
+        BufferedResponse::read(response, timings, None).await
     }
 
-    /// Interpolate variables in a string
+    /// Interpolate variables and dynamic variables in a string
     fn interpolate_variables(
         &self,
         input: &str,
         variables: &HashMap<String, String>,
     ) -> Result<String> {
-        let mut result = input.to_string();
-
-        // Find all variable references in the format {{variable_name}}
-        let re = regex::Regex::new(r"\{\{([^{}]+)\}\}").unwrap();
-
-        // Replace each variable reference with its value
-        for capture in re.captures_iter(input) {
-            let var_name = &capture[1];
-            let var_placeholder = &capture[0]; // The full {{variable_name}}
-
-            if let Some(value) = variables.get(var_name) {
-                result = result.replace(var_placeholder, value);
-            } else {
-                // Variable not found - could either error or leave as is
-                warn!("Variable '{}' not found during interpolation", var_name);
-            }
-        }
-
-        Ok(result)
+        // Unknown variables are left as is
+        self.interpolator.interpolate(input, variables, |var_name| {
+            warn!("Variable '{}' not found during interpolation", var_name)
+        })
     }
 
-    /// Validate response against expected values
+    /// Validate response against expected values, recording every check
     fn validate_response(
         &self,
         status: &reqwest::StatusCode,
         _headers: &reqwest::header::HeaderMap,
         body: &Value,
+        raw_body: &ResponseBody,
         request: &CollectionRequest,
         _duration: f64,
-    ) -> Result<Option<Vec<String>>> {
-        let mut validation_issues = Vec::new();
+    ) -> Result<Vec<AssertionResult>> {
+        let mut checks = Vec::new();
 
         // Validate status code if expected
         if let Some(expected_status) = request.expected_status {
-            if status.as_u16() != expected_status {
-                validation_issues.push(format!(
+            checks.push(
+                AssertionResult::check(
+                    "status",
+                    json!(expected_status),
+                    json!(status.as_u16()),
+                    status.as_u16() == expected_status,
+                )
+                .with_message(format!(
                     "Status code mismatch. Expected: {}, Got: {}",
                     expected_status,
                     status.as_u16()
-                ));
-            }
+                )),
+            );
         }
 
         // Validate body type if expected
@@ -604,39 +1741,145 @@ impl ApiCollectionRunner {
                 Value::Null => "null",
             };
 
-            if actual_type != expected_type {
-                validation_issues.push(format!(
+            checks.push(
+                AssertionResult::check(
+                    "body_type",
+                    json!(expected_type),
+                    json!(actual_type),
+                    actual_type == expected_type,
+                )
+                .with_message(format!(
                     "Body type mismatch. Expected: {}, Got: {}",
                     expected_type, actual_type
-                ));
-            }
+                )),
+            );
         }
 
         // Validate specific body fields if expected
-        if let Some(expected_body) = &request.expected_body {
-            if let Some(expected_obj) = expected_body.as_object() {
-                for (key, expected_value) in expected_obj {
-                    if let Some(actual_value) = body.get(key) {
-                        if actual_value != expected_value {
-                            validation_issues.push(format!(
-                                "Field '{}' mismatch. Expected: {:?}, Got: {:?}",
-                                key, expected_value, actual_value
-                            ));
-                        }
-                    } else {
-                        validation_issues.push(format!(
-                            "Expected field '{}' not found in response body",
-                            key
-                        ));
+        if let Some(expected_obj) = request.expected_body.as_ref().and_then(|b| b.as_object()) {
+            for (key, expected_value) in expected_obj {
+                let name = format!("body:{}", key);
+                let check = match body.get(key) {
+                    Some(actual_value) => AssertionResult::check(
+                        name,
+                        expected_value.clone(),
+                        actual_value.clone(),
+                        actual_value == expected_value,
+                    )
+                    .with_message(format!(
+                        "Field '{}' mismatch. Expected: {:?}, Got: {:?}",
+                        key, expected_value, actual_value
+                    )),
+                    None => {
+                        AssertionResult::check(name, expected_value.clone(), Value::Null, false)
+                            .with_message(format!(
+                                "Expected field '{}' not found in response body",
+                                key
+                            ))
                     }
-                }
+                };
+                checks.push(check);
             }
         }
 
-        if validation_issues.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(validation_issues))
+        // Evaluate JSONPath assertions if specified
+        if let Some(list) = &request.assertions {
+            checks.extend(assertions::evaluate_all_body(list, raw_body));
+        }
+
+        Ok(checks)
+    }
+}
+
+/// Why a request is skipped, if its `run_if` or `skip_if` condition says so
+fn skip_reason(
+    request: &CollectionRequest,
+    environment: &str,
+    variables: &HashMap<String, String>,
+) -> Result<Option<String>> {
+    if let Some(condition) = &request.run_if {
+        if !scripting::condition(condition, variables, environment)? {
+            return Ok(Some(format!("run_if '{}' is false", condition)));
+        }
+    }
+    if let Some(condition) = &request.skip_if {
+        if scripting::condition(condition, variables, environment)? {
+            return Ok(Some(format!("skip_if '{}' is true", condition)));
+        }
+    }
+    Ok(None)
+}
+
+/// The items of a `for_each` loop, from a variable holding a JSON array
+fn for_each_items(for_each: &ForEach, variables: &HashMap<String, String>) -> Result<Vec<Value>> {
+    let value = variables.get(&for_each.items).ok_or_else(|| {
+        Error::ValidationError(format!("for_each variable '{}' is not set", for_each.items))
+    })?;
+    match serde_json::from_str(value) {
+        Ok(Value::Array(items)) => Ok(items),
+        _ => Err(Error::ValidationError(format!(
+            "for_each variable '{}' is not a JSON array",
+            for_each.items
+        ))),
+    }
+}
+
+/// A result for a request that was not sent
+fn outcome_result(name: &str, status: &str, message: String) -> TestResult {
+    let details = match status {
+        "skipped" => json!({"skipped": message}),
+        _ => json!({"validation_issues": [message]}),
+    };
+    TestResult {
+        name: name.to_string(),
+        status: status.to_string(),
+        duration: 0.0,
+        details: Some(details),
+        timestamp: Utc::now().to_rfc3339(),
+    }
+}
+
+/// Why a request did not run after an earlier request failed
+fn stopped_reason(failed: &str) -> String {
+    format!("not run: '{}' failed and stop_on_failure is set", failed)
+}
+
+/// Fail a result with an extra check
+fn fail_result(result: &mut TestResult, check: AssertionResult) {
+    result.status = "failed".to_string();
+    let details = result.details.get_or_insert_with(|| json!({}));
+    if let Some(message) = &check.message {
+        match details["validation_issues"].as_array_mut() {
+            Some(issues) => issues.push(json!(message)),
+            None => details["validation_issues"] = json!([message]),
         }
     }
+    match details["assertions"].as_array_mut() {
+        Some(checks) => checks.push(json!(check)),
+        None => details["assertions"] = json!([check]),
+    }
+}
+
+/// Variables that are always secret, in addition to the collection's `secrets`
+const SECRET_VARIABLES: [&str; 2] = ["oauth2_access_token", "oauth2_refresh_token"];
+
+fn secret_names(collection: &ApiCollection) -> Vec<&str> {
+    collection
+        .secrets
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .chain(SECRET_VARIABLES)
+        .collect()
+}
+
+/// Values of the secret variables among `variables`
+fn secret_variables(
+    collection: &ApiCollection,
+    variables: &HashMap<String, String>,
+) -> Vec<String> {
+    secret_names(collection)
+        .into_iter()
+        .filter_map(|name| variables.get(name).cloned())
+        .collect()
 }
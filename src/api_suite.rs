@@ -0,0 +1,242 @@
+use crate::api::{ApiTestConfig, ApiTestRunner, RetryConfig};
+use crate::api_collection::CollectionAuth;
+use crate::client::{ClientOptions, ProxyConfig, TlsConfig};
+use crate::common::{self, TestResult, TestRunner};
+use crate::environment::Environment;
+use crate::error::Result;
+use futures::stream::{self, StreamExt};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A test in an API suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSuiteTest {
+    /// Test name
+    pub name: String,
+    /// Tags used to select tests
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Remaining API test fields (url, method, expected_status, assertions, ...)
+    #[serde(flatten)]
+    pub config: Map<String, Value>,
+}
+
+/// A file holding many API tests with shared settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSuiteConfig {
+    /// Suite name
+    pub name: String,
+    /// Suite description
+    pub description: Option<String>,
+    /// Base URL prepended to relative test URLs
+    pub base_url: Option<String>,
+    /// Headers sent with every test, test headers win
+    pub headers: Option<HashMap<String, String>>,
+    /// Authentication for tests that do not set their own
+    pub auth: Option<CollectionAuth>,
+    /// Default request timeout in seconds
+    pub timeout: Option<u64>,
+    /// Default retry policy
+    pub retry: Option<RetryConfig>,
+    /// Number of tests run at the same time
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// TLS settings for all tests
+    pub tls: Option<TlsConfig>,
+    /// Proxy settings for all tests
+    pub proxy: Option<ProxyConfig>,
+    /// Suite tests
+    pub tests: Vec<ApiSuiteTest>,
+    /// Directory of the suite file, used to resolve relative paths
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+fn default_concurrency() -> usize {
+    1
+}
+
+/// Selection of tests by tag and name
+#[derive(Debug, Clone, Default)]
+pub struct TestFilter {
+    /// Run only tests with at least one of these tags
+    pub tags: Vec<String>,
+    /// Skip tests with any of these tags
+    pub exclude_tags: Vec<String>,
+    /// Run only tests whose name contains this text (case-insensitive)
+    pub name: Option<String>,
+}
+
+impl TestFilter {
+    /// Whether a test with this name and these tags is selected
+    pub fn matches(&self, name: &str, tags: &[String]) -> bool {
+        if !self.tags.is_empty() && !self.tags.iter().any(|t| tags.contains(t)) {
+            return false;
+        }
+        if self.exclude_tags.iter().any(|t| tags.contains(t)) {
+            return false;
+        }
+        match &self.name {
+            Some(pattern) => name.to_lowercase().contains(&pattern.to_lowercase()),
+            None => true,
+        }
+    }
+}
+
+impl ApiSuiteConfig {
+    /// Whether a configuration file is an API suite rather than a single test
+    pub fn is_suite_file(path: &Path) -> Result<bool> {
+        let content = std::fs::read_to_string(path)?;
+        let value: Value = serde_json::from_str(&content)?;
+        Ok(value.get("tests").is_some_and(Value::is_array))
+    }
+
+    /// Load an API suite for an environment, resolving file paths relative to it
+    pub fn load(path: &Path, environment: &Environment) -> Result<Self> {
+        let mut suite: ApiSuiteConfig = common::load_config(path, environment)?;
+        suite.base_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Ok(suite)
+    }
+
+    /// Build the API test config of a suite test, applying the suite settings
+    pub fn test_config(&self, test: &ApiSuiteTest, environment: &str) -> Result<ApiTestConfig> {
+        let mut config = test.config.clone();
+        config.insert("name".to_string(), json!(test.name));
+        config
+            .entry("description")
+            .or_insert_with(|| json!(self.description.clone().unwrap_or_default()));
+        config
+            .entry("environment")
+            .or_insert_with(|| json!(environment));
+
+        // Relative URLs are resolved against the base URL
+        if let (Some(base_url), Some(Value::String(url))) = (&self.base_url, config.get_mut("url"))
+        {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                *url = format!(
+                    "{}/{}",
+                    base_url.trim_end_matches('/'),
+                    url.trim_start_matches('/')
+                );
+            }
+        }
+
+        // Suite headers are sent unless the test overrides them
+        if let Some(headers) = &self.headers {
+            let mut merged: Map<String, Value> =
+                headers.iter().map(|(k, v)| (k.clone(), json!(v))).collect();
+            if let Some(Value::Object(test_headers)) = config.get("headers") {
+                merged.extend(test_headers.clone());
+            }
+            config.insert("headers".to_string(), Value::Object(merged));
+        }
+
+        let defaults = [
+            ("auth", serde_json::to_value(&self.auth)?),
+            ("timeout", serde_json::to_value(self.timeout)?),
+            ("retry", serde_json::to_value(&self.retry)?),
+            ("tls", serde_json::to_value(&self.tls)?),
+            ("proxy", serde_json::to_value(&self.proxy)?),
+        ];
+        for (key, value) in defaults {
+            if !value.is_null() {
+                config.entry(key).or_insert(value);
+            }
+        }
+
+        let mut config: ApiTestConfig = serde_json::from_value(Value::Object(config))?;
+        config.resolve_paths(&self.base_dir);
+        Ok(config)
+    }
+}
+
+/// Runner for API suites
+pub struct ApiSuiteRunner {
+    api_runner: ApiTestRunner,
+    filter: TestFilter,
+    concurrency: Option<usize>,
+}
+
+impl ApiSuiteRunner {
+    /// Create a new API suite runner
+    pub fn new() -> Self {
+        Self {
+            api_runner: ApiTestRunner::new(),
+            filter: TestFilter::default(),
+            concurrency: None,
+        }
+    }
+
+    /// Override the TLS and proxy settings of every test
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.api_runner = self.api_runner.with_client_options(client_options);
+        self
+    }
+
+    /// Rewrite stored snapshots instead of comparing against them
+    pub fn with_update_snapshots(mut self, update_snapshots: bool) -> Self {
+        self.api_runner = self.api_runner.with_update_snapshots(update_snapshots);
+        self
+    }
+
+    /// Run only the tests selected by the filter
+    pub fn with_filter(mut self, filter: TestFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Override the concurrency of the suite
+    pub fn with_concurrency(mut self, concurrency: Option<usize>) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+}
+
+impl Default for ApiSuiteRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiSuiteRunner {
+    /// Run the selected tests of a suite, returning one result per test in suite order
+    pub async fn run_suite(
+        &self,
+        suite: &ApiSuiteConfig,
+        environment: &str,
+    ) -> Result<Vec<TestResult>> {
+        // Build every config first so that invalid tests fail before any request
+        let selected = suite
+            .tests
+            .iter()
+            .filter(|test| self.filter.matches(&test.name, &test.tags))
+            .map(|test| Ok((test, suite.test_config(test, environment)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let concurrency = self.concurrency.unwrap_or(suite.concurrency).max(1);
+        info!(
+            "Running suite: {} with {} of {} tests (concurrency {})",
+            suite.name,
+            selected.len(),
+            suite.tests.len(),
+            concurrency
+        );
+
+        stream::iter(selected)
+            .map(|(test, config)| async move {
+                let mut result = self.api_runner.run(&config).await?;
+                if let Some(details) = result.details.as_mut() {
+                    details["tags"] = json!(test.tags);
+                }
+                Ok(result)
+            })
+            .buffered(concurrency)
+            .collect::<Vec<Result<TestResult>>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+}
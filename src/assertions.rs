@@ -0,0 +1,322 @@
+use crate::response::ResponseBody;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Operators supported by response assertions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssertionOperator {
+    /// The path matches at least one value
+    Exists,
+    /// The path matches no value
+    NotExists,
+    /// The matched value equals the expected value
+    Equals,
+    /// The matched value differs from the expected value
+    NotEquals,
+    /// The matched string, array or object contains the expected value
+    Contains,
+    /// The matched string matches the expected regular expression
+    Matches,
+    /// The matched number is greater than the expected number
+    Gt,
+    /// The matched number is greater than or equal to the expected number
+    Gte,
+    /// The matched number is less than the expected number
+    Lt,
+    /// The matched number is less than or equal to the expected number
+    Lte,
+    /// The length of the matched string, array or object equals the expected number
+    Length,
+    /// The JSON type of the matched value equals the expected type name
+    TypeOf,
+    /// The matched value is one of the expected values
+    OneOf,
+    /// The raw body is exactly the expected number of bytes
+    Size,
+    /// The hex encoded SHA-256 digest of the raw body equals the expected digest
+    Sha256,
+}
+
+impl AssertionOperator {
+    /// Name of the operator as written in configuration files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssertionOperator::Exists => "exists",
+            AssertionOperator::NotExists => "not_exists",
+            AssertionOperator::Equals => "equals",
+            AssertionOperator::NotEquals => "not_equals",
+            AssertionOperator::Contains => "contains",
+            AssertionOperator::Matches => "matches",
+            AssertionOperator::Gt => "gt",
+            AssertionOperator::Gte => "gte",
+            AssertionOperator::Lt => "lt",
+            AssertionOperator::Lte => "lte",
+            AssertionOperator::Length => "length",
+            AssertionOperator::TypeOf => "type_of",
+            AssertionOperator::OneOf => "one_of",
+            AssertionOperator::Size => "size",
+            AssertionOperator::Sha256 => "sha256",
+        }
+    }
+}
+
+/// Compares the value selected by an assertion, given whether the path matched,
+/// the actual and the expected value
+type Compare = fn(&Assertion, bool, &Value, &Value) -> Result<bool, String>;
+
+/// A single assertion on a response body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assertion {
+    /// JSONPath (JSON bodies) or XPath (XML bodies) expression selecting the value to check.
+    /// Text bodies are always checked as a whole.
+    #[serde(default)]
+    pub path: String,
+    /// Operator to apply
+    pub operator: AssertionOperator,
+    /// Expected value (not needed for exists and not_exists)
+    #[serde(default)]
+    pub value: Option<Value>,
+    /// Namespace prefixes available to XPath expressions
+    #[serde(default)]
+    pub namespaces: Option<HashMap<String, String>>,
+}
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionResult {
+    /// Name of the check
+    pub name: String,
+    /// Expected value
+    pub expected: Value,
+    /// Actual value
+    pub actual: Value,
+    /// Whether the check passed
+    pub passed: bool,
+    /// Explanation of a failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl AssertionResult {
+    /// Build the result of a check comparing an expected and an actual value
+    pub fn check(name: impl Into<String>, expected: Value, actual: Value, passed: bool) -> Self {
+        let name = name.into();
+        let message = (!passed).then(|| {
+            format!(
+                "Check '{}' failed. Expected: {}, Got: {}",
+                name, expected, actual
+            )
+        });
+
+        Self {
+            name,
+            expected,
+            actual,
+            passed,
+            message,
+        }
+    }
+
+    /// Replace the failure message of a failed check
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        if !self.passed {
+            self.message = Some(message.into());
+        }
+        self
+    }
+}
+
+impl Assertion {
+    /// Evaluate the assertion against a JSON body
+    pub fn evaluate(&self, body: &Value) -> AssertionResult {
+        self.evaluate_body(&ResponseBody::from_json(body))
+    }
+
+    /// Evaluate the assertion against a response body of any type
+    pub fn evaluate_body(&self, body: &ResponseBody) -> AssertionResult {
+        let name = if self.path.is_empty() {
+            format!("body {}", self.operator.as_str())
+        } else {
+            format!("{} {}", self.path, self.operator.as_str())
+        };
+        let expected = self.value.clone().unwrap_or(Value::Null);
+
+        // Size and digest checks apply to the raw bytes of any body,
+        // the other operators compare the value selected by the path
+        let compare: Compare = match self.operator {
+            AssertionOperator::Size => {
+                let actual = json!(body.size());
+                return match expected.as_u64() {
+                    Some(size) => {
+                        AssertionResult::check(name, expected, actual, body.size() as u64 == size)
+                    }
+                    None => AssertionResult::check(name, expected, actual, false)
+                        .with_message("Expected size must be a non-negative integer"),
+                };
+            }
+            AssertionOperator::Sha256 => {
+                let digest = body.sha256();
+                let passed = expected
+                    .as_str()
+                    .is_some_and(|e| e.eq_ignore_ascii_case(&digest));
+                return AssertionResult::check(name, expected, json!(digest), passed);
+            }
+            AssertionOperator::Exists => |_, found, _, _| Ok(found),
+            AssertionOperator::NotExists => |_, found, _, _| Ok(!found),
+            AssertionOperator::Equals => |_, _, actual, expected| Ok(actual == expected),
+            AssertionOperator::NotEquals => |_, _, actual, expected| Ok(actual != expected),
+            AssertionOperator::Contains => |_, _, actual, expected| Ok(contains(actual, expected)),
+            AssertionOperator::Matches => |_, _, actual, expected| matches_regex(actual, expected),
+            AssertionOperator::Gt => {
+                |_, _, actual, expected| compare_numbers(actual, expected, |a, b| a > b)
+            }
+            AssertionOperator::Gte => {
+                |_, _, actual, expected| compare_numbers(actual, expected, |a, b| a >= b)
+            }
+            AssertionOperator::Lt => {
+                |_, _, actual, expected| compare_numbers(actual, expected, |a, b| a < b)
+            }
+            AssertionOperator::Lte => {
+                |_, _, actual, expected| compare_numbers(actual, expected, |a, b| a <= b)
+            }
+            AssertionOperator::Length => {
+                |assertion, _, actual, expected| match (length_of(actual), expected.as_u64()) {
+                    (Some(length), Some(expected_length)) => Ok(length as u64 == expected_length),
+                    (None, _) => Err(format!("Value at '{}' has no length", assertion.path)),
+                    (_, None) => Err("Expected length must be a non-negative integer".to_string()),
+                }
+            }
+            AssertionOperator::TypeOf => |_, _, actual, expected| match expected.as_str() {
+                Some(type_name) => Ok(type_matches(actual, type_name)),
+                None => Err("Expected type must be a string".to_string()),
+            },
+            AssertionOperator::OneOf => |_, _, actual, expected| match expected.as_array() {
+                Some(options) => Ok(options.contains(actual)),
+                None => Err("Expected value for one_of must be an array".to_string()),
+            },
+        };
+
+        let matches = match body.select(&self.path, self.namespaces.as_ref()) {
+            Ok(matches) => matches,
+            Err(message) => {
+                return AssertionResult::check(name, expected, Value::Null, false)
+                    .with_message(message);
+            }
+        };
+
+        // A single match is compared directly, several matches are compared as an array
+        let found = !matches.is_empty();
+        let actual = match matches.len() {
+            0 => Value::Null,
+            1 => matches[0].clone(),
+            _ => Value::Array(matches),
+        };
+
+        let outcome = match self.operator {
+            AssertionOperator::Exists | AssertionOperator::NotExists => {
+                compare(self, found, &actual, &expected)
+            }
+            _ if !found => Err(format!("Path '{}' matched no value", self.path)),
+            _ => compare(self, found, &actual, &expected),
+        };
+
+        match outcome {
+            Ok(passed) => AssertionResult::check(name, expected, actual, passed),
+            Err(message) => {
+                AssertionResult::check(name, expected, actual, false).with_message(message)
+            }
+        }
+    }
+}
+
+/// Evaluate a list of assertions against a JSON body
+pub fn evaluate_all(assertions: &[Assertion], body: &Value) -> Vec<AssertionResult> {
+    evaluate_all_body(assertions, &ResponseBody::from_json(body))
+}
+
+/// Evaluate a list of assertions against a response body of any type
+pub fn evaluate_all_body(assertions: &[Assertion], body: &ResponseBody) -> Vec<AssertionResult> {
+    assertions.iter().map(|a| a.evaluate_body(body)).collect()
+}
+
+/// Collect the failure messages of a list of assertion results
+pub fn failure_messages(results: &[AssertionResult]) -> Vec<String> {
+    results
+        .iter()
+        .filter(|r| !r.passed)
+        .map(|r| {
+            r.message
+                .clone()
+                .unwrap_or_else(|| format!("Assertion '{}' failed", r.name))
+        })
+        .collect()
+}
+
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match actual {
+        Value::String(s) => match expected {
+            Value::String(needle) => s.contains(needle.as_str()),
+            other => s.contains(&other.to_string()),
+        },
+        Value::Array(items) => items.contains(expected),
+        Value::Object(map) => expected.as_str().is_some_and(|key| map.contains_key(key)),
+        _ => false,
+    }
+}
+
+fn matches_regex(actual: &Value, expected: &Value) -> Result<bool, String> {
+    let pattern = expected
+        .as_str()
+        .ok_or_else(|| "Expected value for matches must be a regex string".to_string())?;
+    let re =
+        regex::Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+
+    match actual {
+        Value::String(s) => Ok(re.is_match(s)),
+        Value::Number(n) => Ok(re.is_match(&n.to_string())),
+        Value::Bool(b) => Ok(re.is_match(&b.to_string())),
+        _ => Ok(false),
+    }
+}
+
+fn compare_numbers(
+    actual: &Value,
+    expected: &Value,
+    compare: impl Fn(f64, f64) -> bool,
+) -> Result<bool, String> {
+    let expected = expected
+        .as_f64()
+        .ok_or_else(|| format!("Expected value must be a number, got {}", expected))?;
+    let actual = match actual {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| format!("Actual value is not a number: {}", actual))?;
+
+    Ok(compare(actual, expected))
+}
+
+fn length_of(value: &Value) -> Option<usize> {
+    match value {
+        Value::String(s) => Some(s.chars().count()),
+        Value::Array(items) => Some(items.len()),
+        Value::Object(map) => Some(map.len()),
+        _ => None,
+    }
+}
+
+fn type_matches(value: &Value, type_name: &str) -> bool {
+    match type_name {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
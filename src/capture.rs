@@ -0,0 +1,152 @@
+use crate::assertions::AssertionResult;
+use crate::secrets;
+use jsonpath_lib as jsonpath;
+use log::{info, warn};
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// A capture entry: a source, or a source with options
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Capture {
+    Source(String),
+    Options {
+        /// Where the value comes from
+        from: String,
+        /// Fail the request when nothing matches
+        #[serde(default)]
+        required: bool,
+    },
+}
+
+impl Capture {
+    /// The source, such as `$.id`, `header:Location` or `regex:<pattern>`
+    pub fn source(&self) -> &str {
+        match self {
+            Capture::Source(source) | Capture::Options { from: source, .. } => source,
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        matches!(self, Capture::Options { required: true, .. })
+    }
+}
+
+impl From<&str> for Capture {
+    fn from(source: &str) -> Self {
+        Capture::Source(source.to_string())
+    }
+}
+
+impl PartialEq<&str> for Capture {
+    fn eq(&self, other: &&str) -> bool {
+        self.source() == *other
+    }
+}
+
+/// The parts of a response that values can be captured from
+pub struct CaptureResponse<'a> {
+    pub status: u16,
+    pub headers: &'a HeaderMap,
+    /// Parsed JSON body, or the text
+    pub body: &'a Value,
+    /// Raw body text
+    pub text: &'a str,
+    pub cookies: &'a Map<String, Value>,
+}
+
+/// Evaluate every capture against a response.
+///
+/// Returns the captured values by variable name, and a failed check for each
+/// required capture that matched nothing. Values captured under one of the
+/// `secrets` names are registered as secrets before they are logged.
+pub fn evaluate(
+    captures: &HashMap<String, Capture>,
+    response: &CaptureResponse,
+    secrets: &[&str],
+) -> (HashMap<String, String>, Vec<AssertionResult>) {
+    let mut captured = HashMap::new();
+    let mut checks = Vec::new();
+
+    // Sorted so that checks come out in a stable order
+    let mut entries: Vec<_> = captures.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, capture) in entries {
+        let source = capture.source();
+        match select(source, response) {
+            Ok(Some(value)) => {
+                if secrets.contains(&name.as_str()) {
+                    secrets::register([value.clone()]);
+                }
+                info!("Captured variable '{}' with value: {}", name, value);
+                captured.insert(name.clone(), value);
+            }
+            result => {
+                let message = match result {
+                    Err(message) => message,
+                    _ => format!("'{}' matched no values in response", source),
+                };
+                if capture.is_required() {
+                    checks.push(
+                        AssertionResult::check(
+                            format!("capture {}", name),
+                            json!(source),
+                            Value::Null,
+                            false,
+                        )
+                        .with_message(format!("Required capture '{}': {}", name, message)),
+                    );
+                } else {
+                    warn!("{}", message);
+                }
+            }
+        }
+    }
+
+    (captured, checks)
+}
+
+/// The value of a single source, `None` when it matched nothing
+fn select(source: &str, response: &CaptureResponse) -> Result<Option<String>, String> {
+    if let Some(name) = source.strip_prefix("header:") {
+        return Ok(response
+            .headers
+            .get(name.trim())
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string));
+    }
+    if source == "status" {
+        return Ok(Some(response.status.to_string()));
+    }
+    if let Some(name) = source.strip_prefix("cookie:") {
+        return Ok(response.cookies.get(name).map(to_variable));
+    }
+    if let Some(pattern) = source.strip_prefix("regex:") {
+        let regex = Regex::new(pattern)
+            .map_err(|e| format!("Invalid capture regex '{}': {}", pattern, e))?;
+        // The first group if the pattern has one, otherwise the whole match
+        return Ok(regex.captures(response.text).and_then(|captures| {
+            captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|value| value.as_str().to_string())
+        }));
+    }
+
+    let path = source.strip_prefix("jsonpath:").unwrap_or(source);
+    let values = jsonpath::select(response.body, path)
+        .map_err(|_| format!("Failed to evaluate JSONPath '{}' on response", path))?;
+    Ok(values.first().map(|value| to_variable(value)))
+}
+
+/// Variables hold strings, other JSON values are stored serialized
+pub fn to_variable(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        other => other.to_string(),
+    }
+}
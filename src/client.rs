@@ -0,0 +1,166 @@
+use crate::error::{Error, Result};
+use reqwest::{Certificate, Client, ClientBuilder, Identity, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// TLS settings for outgoing requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM file with one or more CA certificates used to verify the server
+    pub ca_cert: Option<String>,
+    /// PEM file with the client certificate for mutual TLS
+    pub client_cert: Option<String>,
+    /// PEM file with the PKCS#8 private key of the client certificate
+    pub client_key: Option<String>,
+    /// Accept any server certificate and hostname (development only)
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+/// HTTP proxy settings for outgoing requests
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy URL used for HTTP and HTTPS requests
+    pub url: String,
+    /// Comma separated hosts, domains and IP ranges that bypass the proxy
+    pub no_proxy: Option<String>,
+    /// Username for proxy authentication
+    pub username: Option<String>,
+    /// Password for proxy authentication
+    pub password: Option<String>,
+}
+
+/// Connection settings shared by all HTTP runners
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientOptions {
+    /// TLS settings
+    pub tls: Option<TlsConfig>,
+    /// Proxy settings
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl ClientOptions {
+    /// Whether the options change anything compared to a default client
+    pub fn is_empty(&self) -> bool {
+        self.tls.is_none() && self.proxy.is_none()
+    }
+
+    /// Combine configuration file settings with overrides, overrides winning field by field
+    pub fn merge(&self, overrides: &ClientOptions) -> ClientOptions {
+        let tls = match (&self.tls, &overrides.tls) {
+            (Some(base), Some(over)) => Some(TlsConfig {
+                ca_cert: over.ca_cert.clone().or_else(|| base.ca_cert.clone()),
+                client_cert: over
+                    .client_cert
+                    .clone()
+                    .or_else(|| base.client_cert.clone()),
+                client_key: over.client_key.clone().or_else(|| base.client_key.clone()),
+                insecure_skip_verify: base.insecure_skip_verify || over.insecure_skip_verify,
+            }),
+            (base, over) => over.clone().or_else(|| base.clone()),
+        };
+
+        let proxy = match (&self.proxy, &overrides.proxy) {
+            (Some(base), Some(over)) => Some(ProxyConfig {
+                url: if over.url.is_empty() {
+                    base.url.clone()
+                } else {
+                    over.url.clone()
+                },
+                no_proxy: over.no_proxy.clone().or_else(|| base.no_proxy.clone()),
+                username: over.username.clone().or_else(|| base.username.clone()),
+                password: over.password.clone().or_else(|| base.password.clone()),
+            }),
+            // A bypass list without a proxy URL has nothing to apply to
+            (None, Some(over)) if over.url.is_empty() => None,
+            (base, over) => over.clone().or_else(|| base.clone()),
+        };
+
+        ClientOptions { tls, proxy }
+    }
+
+    /// Apply the TLS and proxy settings to a client builder
+    pub fn configure(&self, mut builder: ClientBuilder) -> Result<ClientBuilder> {
+        if let Some(tls) = &self.tls {
+            if let Some(ca_cert) = &tls.ca_cert {
+                let pem = read_pem(ca_cert, "CA certificate")?;
+                for certificate in Certificate::from_pem_bundle(&pem).map_err(|e| {
+                    Error::ConfigError(format!("Invalid CA certificate '{}': {}", ca_cert, e))
+                })? {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+
+            match (&tls.client_cert, &tls.client_key) {
+                (Some(cert), Some(key)) => {
+                    let cert_pem = read_pem(cert, "client certificate")?;
+                    let key_pem = read_pem(key, "client key")?;
+                    let identity = Identity::from_pkcs8_pem(&cert_pem, &key_pem).map_err(|e| {
+                        Error::ConfigError(format!("Invalid client certificate or key: {}", e))
+                    })?;
+                    builder = builder.identity(identity);
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(Error::ConfigError(
+                        "tls.client_cert and tls.client_key must be set together".to_string(),
+                    ))
+                }
+            }
+
+            if tls.insecure_skip_verify {
+                builder = builder
+                    .danger_accept_invalid_certs(true)
+                    .danger_accept_invalid_hostnames(true);
+            }
+        }
+
+        if let Some(proxy_config) = &self.proxy {
+            let mut proxy = Proxy::all(&proxy_config.url).map_err(|e| {
+                Error::ConfigError(format!("Invalid proxy URL '{}': {}", proxy_config.url, e))
+            })?;
+            if let Some(username) = &proxy_config.username {
+                proxy = proxy.basic_auth(username, proxy_config.password.as_deref().unwrap_or(""));
+            }
+            if let Some(no_proxy) = &proxy_config.no_proxy {
+                proxy = proxy.no_proxy(NoProxy::from_string(no_proxy));
+            }
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Pick the client for a run: the runner's default client when nothing is
+/// configured, otherwise a client built from `base` with the merged settings
+pub fn client_for(
+    default: &Client,
+    config: &ClientOptions,
+    overrides: &ClientOptions,
+    base: ClientBuilder,
+) -> Result<Client> {
+    let options = config.merge(overrides);
+    if options.is_empty() {
+        return Ok(default.clone());
+    }
+
+    Ok(options.configure(base)?.build()?)
+}
+
+/// Resolve certificate paths of a `tls` block relative to the configuration directory
+pub fn resolve_tls_paths(tls: &mut Value, base_dir: &Path) {
+    for key in ["ca_cert", "client_cert", "client_key"] {
+        if let Some(Value::String(path)) = tls.get_mut(key) {
+            if Path::new(path.as_str()).is_relative() {
+                *path = base_dir.join(path.as_str()).to_string_lossy().to_string();
+            }
+        }
+    }
+}
+
+fn read_pem(path: &str, what: &str) -> Result<Vec<u8>> {
+    std::fs::read(path)
+        .map_err(|e| Error::ConfigError(format!("Failed to read {} '{}': {}", what, path, e)))
+}
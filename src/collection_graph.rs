@@ -0,0 +1,191 @@
+use crate::api_collection::{ApiCollection, CollectionRequest};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+
+/// Dependency graph of the requests of a collection, built from `depends_on`
+#[derive(Debug, Clone)]
+pub struct DependencyGraph {
+    /// Request names, by request index
+    names: Vec<String>,
+    /// Indices of the requests each request depends on
+    dependencies: Vec<Vec<usize>>,
+    /// Request indices in topological order
+    order: Vec<usize>,
+}
+
+impl DependencyGraph {
+    /// Build and validate the graph of every request of a collection and its folders.
+    ///
+    /// Duplicate IDs, dependencies on unknown IDs and cycles are all reported
+    /// in a single validation error.
+    pub fn build(collection: &ApiCollection) -> Result<Self> {
+        let requests: Vec<&CollectionRequest> = collection
+            .scoped_requests()
+            .iter()
+            .map(|scoped| scoped.request)
+            .collect();
+        Self::for_requests(&collection.name, &requests)
+    }
+
+    /// Build and validate the graph of a list of requests, named `title` in errors
+    pub fn for_requests(title: &str, requests: &[&CollectionRequest]) -> Result<Self> {
+        let mut problems = Vec::new();
+
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        for (index, request) in requests.iter().enumerate() {
+            if let Some(id) = request.id.as_deref() {
+                if let Some(first) = ids.insert(id, index) {
+                    problems.push(format!(
+                        "Duplicate request ID '{}' used by '{}' and '{}'",
+                        id, requests[first].name, request.name
+                    ));
+                    ids.insert(id, first);
+                }
+            }
+        }
+
+        let dependencies: Vec<Vec<usize>> = requests
+            .iter()
+            .map(|request| {
+                request
+                    .depends_on
+                    .iter()
+                    .flatten()
+                    .filter_map(|dep_id| match ids.get(dep_id.as_str()) {
+                        Some(index) => Some(*index),
+                        None => {
+                            problems.push(format!(
+                                "Request '{}' depends on unknown request '{}'",
+                                request.name, dep_id
+                            ));
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut graph = Self {
+            names: requests.iter().map(|r| r.name.clone()).collect(),
+            dependencies,
+            order: Vec::new(),
+        };
+        match graph.topological_order() {
+            Ok(order) => graph.order = order,
+            Err(cycle) => problems.push(format!(
+                "Request dependencies form a cycle: {}",
+                cycle
+                    .iter()
+                    .map(|index| graph.names[*index].as_str())
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            )),
+        }
+
+        if !problems.is_empty() {
+            return Err(Error::ValidationError(format!(
+                "Invalid collection '{}':\n{}",
+                title,
+                problems.join("\n")
+            )));
+        }
+
+        Ok(graph)
+    }
+
+    /// Indices of the requests a request depends on
+    pub fn dependencies(&self, index: usize) -> &[usize] {
+        &self.dependencies[index]
+    }
+
+    /// Request indices in topological order, keeping file order where possible
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Transitive dependencies of a request, in file order
+    pub fn ancestors(&self, index: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.names.len()];
+        let mut stack = self.dependencies[index].clone();
+        while let Some(next) = stack.pop() {
+            if !std::mem::replace(&mut seen[next], true) {
+                stack.extend(&self.dependencies[next]);
+            }
+        }
+        (0..self.names.len()).filter(|i| seen[*i]).collect()
+    }
+
+    /// Kahn's algorithm, always picking the first ready request in file order.
+    /// Returns one cycle if the graph has any.
+    fn topological_order(&self) -> std::result::Result<Vec<usize>, Vec<usize>> {
+        let count = self.names.len();
+        let mut remaining: Vec<usize> = self.dependencies.iter().map(Vec::len).collect();
+        let mut done = vec![false; count];
+        let mut order = Vec::with_capacity(count);
+
+        while let Some(next) = (0..count).find(|i| !done[*i] && remaining[*i] == 0) {
+            done[next] = true;
+            order.push(next);
+            for (dependent, dependencies) in self.dependencies.iter().enumerate() {
+                remaining[dependent] -= dependencies.iter().filter(|d| **d == next).count();
+            }
+        }
+
+        if order.len() == count {
+            return Ok(order);
+        }
+
+        // Every request left has an unfinished dependency, so following them must loop
+        let mut path = Vec::new();
+        let mut current = (0..count).find(|i| !done[*i]).unwrap_or(0);
+        while !path.contains(&current) {
+            path.push(current);
+            current = self.dependencies[current]
+                .iter()
+                .copied()
+                .find(|d| !done[*d])
+                .unwrap_or(current);
+        }
+        let start = path.iter().position(|i| *i == current).unwrap_or(0);
+        let mut cycle = path[start..].to_vec();
+        cycle.push(current);
+        Err(cycle)
+    }
+
+    /// Render the graph in Graphviz DOT format
+    pub fn to_dot(&self, title: &str) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n    rankdir=LR;\n", escape(title));
+        for (index, name) in self.names.iter().enumerate() {
+            dot.push_str(&format!("    n{} [label=\"{}\"];\n", index, escape(name)));
+        }
+        for (index, dependencies) in self.dependencies.iter().enumerate() {
+            for dependency in dependencies {
+                dot.push_str(&format!("    n{} -> n{};\n", dependency, index));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the graph as a Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = "graph LR\n".to_string();
+        for (index, name) in self.names.iter().enumerate() {
+            mermaid.push_str(&format!(
+                "    n{}[\"{}\"]\n",
+                index,
+                name.replace('"', "#quot;")
+            ));
+        }
+        for (index, dependencies) in self.dependencies.iter().enumerate() {
+            for dependency in dependencies {
+                mermaid.push_str(&format!("    n{} --> n{}\n", dependency, index));
+            }
+        }
+        mermaid
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -1,9 +1,14 @@
+use crate::client::{self, ClientOptions, ProxyConfig, TlsConfig};
+use crate::environment::Environment;
 use crate::error::Result;
+use crate::interpolation;
 use async_trait::async_trait;
-use envsubst::substitute;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +20,20 @@ pub struct TestConfig {
     #[serde(default = "default_retries")]
     pub retries: u32,
     pub environment: String,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl TestConfig {
+    /// TLS and proxy settings of this configuration
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            tls: self.tls.clone(),
+            proxy: self.proxy.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -34,22 +53,81 @@ fn default_retries() -> u32 {
     3
 }
 
+/// A document converted from or to another tool's format, and the parts that could not be converted
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// The converted document
+    pub document: Value,
+    /// What was dropped or only partly converted
+    pub warnings: Vec<String>,
+}
+
 #[async_trait]
 pub trait TestRunner {
     async fn run(&self, config: &(impl serde::Serialize + Send + Sync)) -> Result<TestResult>;
 }
 
-pub fn load_config<T: DeserializeOwned>(path: &Path) -> Result<T> {
-    let content = std::fs::read_to_string(path)?;
-    let env_vars = std::env::vars().collect::<std::collections::HashMap<_, _>>();
-    let substituted = substitute(&content, &env_vars)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+/// Load a test configuration for an environment.
+///
+/// `${NAME}` placeholders are replaced with process environment variables,
+/// overridden by the environment file and command line variables. `{{name}}`
+/// placeholders are replaced with the variables the environment resolves from
+/// the configuration's own `variables` and `environments`.
+pub fn load_config<T: DeserializeOwned>(path: &Path, environment: &Environment) -> Result<T> {
+    load_typed_config(path, environment, determine_test_type(path))
+}
 
-    // Determine test type from file name or path
-    let test_type = determine_test_type(path);
+/// Load a configuration of a known test type, whatever its file name.
+///
+/// API collections keep their `{{name}}` placeholders and run in the
+/// environment selected when they are run: the collection runner resolves
+/// variables for each request, after folder variables and captures.
+pub fn load_typed_config<T: DeserializeOwned>(
+    path: &Path,
+    environment: &Environment,
+    test_type: Option<&str>,
+) -> Result<T> {
+    let content = std::fs::read_to_string(path)?;
+    let mut env_vars = std::env::vars().collect::<HashMap<_, _>>();
+    env_vars.extend(environment.variables());
+    let substituted = substitute_env(&content, &env_vars);
 
     // Parse JSON for validation
-    let json_value: Value = serde_json::from_str(&substituted)?;
+    let mut json_value: Value = serde_json::from_str(&substituted)?;
+
+    if test_type != Some("api_collection") {
+        // Resolve {{name}} variables
+        let variables: Option<HashMap<String, String>> = json_value
+            .get("variables")
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()?;
+        let environments: Option<HashMap<String, HashMap<String, String>>> = json_value
+            .get("environments")
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()?;
+        let resolved = environment.resolve(variables.as_ref(), environments.as_ref());
+        replace_variables(&mut json_value, &resolved);
+
+        // Configurations without an environment run in the selected one
+        if let Some(config) = json_value.as_object_mut() {
+            if !environment.name.is_empty() {
+                config
+                    .entry("environment")
+                    .or_insert_with(|| Value::String(environment.name.clone()));
+            }
+        }
+    }
+
+    // Resolve certificate paths relative to the configuration file
+    if let Some(tls) = json_value.get_mut("tls") {
+        client::resolve_tls_paths(tls, path.parent().unwrap_or(Path::new(".")));
+    }
+
+    // API suites are API configs with a list of tests
+    let test_type = match test_type {
+        Some("api") if json_value.get("tests").is_some() => Some("api_suite"),
+        test_type => test_type,
+    };
 
     // Validate against schema if we have a known test type
     if let Some(test_type) = test_type {
@@ -60,12 +138,42 @@ pub fn load_config<T: DeserializeOwned>(path: &Path) -> Result<T> {
     Ok(serde_json::from_value(json_value)?)
 }
 
+/// A `${NAME}` placeholder
+static ENV_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$\{([^${}]+)\}").unwrap());
+
+/// Replace `${NAME}` placeholders in a single pass, so values are inserted
+/// literally even when they contain `$`, `{` or `}`. Unknown names are left in place.
+fn substitute_env(content: &str, variables: &HashMap<String, String>) -> String {
+    ENV_PLACEHOLDER
+        .replace_all(content, |caps: &Captures| match variables.get(&caps[1]) {
+            Some(value) => value.clone(),
+            None => caps[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Replace `{{name}}` variables in every string of a configuration
+fn replace_variables(value: &mut Value, variables: &HashMap<String, String>) {
+    match value {
+        Value::String(s) => *s = interpolation::replace_variables(s, variables),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| replace_variables(item, variables)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|item| replace_variables(item, variables)),
+        _ => {}
+    }
+}
+
 /// Determine the test type from the file path
 fn determine_test_type(path: &Path) -> Option<&'static str> {
     let file_name = path.file_name()?.to_str()?;
 
     if file_name.contains("api_collection") {
         return Some("api_collection");
+    } else if file_name.contains("api_suite") {
+        return Some("api_suite");
     } else if file_name.contains("api") {
         return Some("api");
     } else if file_name.contains("performance_enhanced") {
@@ -85,7 +193,8 @@ fn determine_test_type(path: &Path) -> Option<&'static str> {
 }
 
 pub fn save_result(result: &TestResult, output_path: &Path) -> Result<()> {
-    let content = serde_json::to_string_pretty(result)?;
+    let result = crate::secrets::redact_results(std::slice::from_ref(result));
+    let content = serde_json::to_string_pretty(&result[0])?;
     std::fs::write(output_path, content)?;
     Ok(())
 }
@@ -0,0 +1,89 @@
+use crate::assertions::{Assertion, AssertionResult};
+use crate::error::{Error, Result};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// A cookie placed in the cookie jar before the first request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedCookie {
+    /// Cookie name
+    pub name: String,
+    /// Cookie value, may reference variables
+    pub value: String,
+    /// URL the cookie is sent to, may reference variables
+    pub url: String,
+    /// Cookie path (default: /)
+    pub path: Option<String>,
+}
+
+/// Add seed cookies to a jar, passing values and URLs through `resolve`
+pub fn seed(
+    jar: &Jar,
+    cookies: &[SeedCookie],
+    resolve: impl Fn(&str) -> Result<String>,
+) -> Result<()> {
+    for cookie in cookies {
+        let url = resolve(&cookie.url)?;
+        let url = Url::parse(&url).map_err(|e| {
+            Error::ConfigError(format!(
+                "Invalid URL '{}' for cookie '{}': {}",
+                url, cookie.name, e
+            ))
+        })?;
+        jar.add_cookie_str(
+            &format!(
+                "{}={}; Path={}",
+                cookie.name,
+                resolve(&cookie.value)?,
+                cookie.path.as_deref().unwrap_or("/")
+            ),
+            &url,
+        );
+    }
+    Ok(())
+}
+
+/// Cookies the jar sends to `url`, by name
+pub fn jar_cookies(jar: &Jar, url: &str) -> Map<String, Value> {
+    let header = Url::parse(url).ok().and_then(|url| jar.cookies(&url));
+    header
+        .as_ref()
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').filter_map(parse_pair).collect())
+        .unwrap_or_default()
+}
+
+/// Cookies set by a response, by name
+pub fn response_cookies(headers: &HeaderMap) -> Map<String, Value> {
+    headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next().and_then(parse_pair))
+        .collect()
+}
+
+fn parse_pair(pair: &str) -> Option<(String, Value)> {
+    let (name, value) = pair.trim().split_once('=')?;
+    Some((name.to_string(), Value::String(value.to_string())))
+}
+
+/// Evaluate assertions whose `path` is a cookie name against the cookies by name
+pub fn check(assertions: &[Assertion], cookies: &Map<String, Value>) -> Vec<AssertionResult> {
+    let cookies = Value::Object(cookies.clone());
+    assertions
+        .iter()
+        .map(|assertion| {
+            let by_name = Assertion {
+                path: format!("$['{}']", assertion.path.replace('\'', "\\'")),
+                ..assertion.clone()
+            };
+            let mut result = by_name.evaluate(&cookies);
+            result.name = format!("cookie:{} {}", assertion.path, assertion.operator.as_str());
+            result
+        })
+        .collect()
+}
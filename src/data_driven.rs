@@ -1,4 +1,6 @@
+use crate::api_collection::ApiCollection;
 use crate::error::{Error, Result};
+use crate::interpolation::{self, Interpolator};
 use log::warn;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -77,31 +79,42 @@ pub struct DataDrivenConfig {
 pub struct DataDrivenRunner {
     config: DataDrivenConfig,
     data_rows: Vec<HashMap<String, String>>,
+    interpolator: Interpolator,
 }
 
-/// Replace placeholders in a JSON value with data from a HashMap
-pub fn replace_placeholders(value: &mut Value, data: &HashMap<String, String>) {
+/// Replace placeholders and dynamic variables in a JSON value with data from a HashMap.
+///
+/// Placeholders without data are left for the test runner to resolve.
+pub fn replace_placeholders(
+    value: &mut Value,
+    data: &HashMap<String, String>,
+    interpolator: &Interpolator,
+) -> Result<()> {
+    replace_strings(value, &mut |s| interpolator.interpolate(s, data, |_| {}))
+}
+
+/// Replace every string of a JSON value
+fn replace_strings(
+    value: &mut Value,
+    replace: &mut impl FnMut(&str) -> Result<String>,
+) -> Result<()> {
     match value {
         Value::String(s) => {
-            let mut result = s.clone();
-            for (key, val) in data {
-                let placeholder = format!("{{{{{}}}}}", key);
-                result = result.replace(&placeholder, val);
-            }
-            *s = result;
+            *s = replace(s)?;
         }
         Value::Object(obj) => {
             for (_, v) in obj {
-                replace_placeholders(v, data);
+                replace_strings(v, replace)?;
             }
         }
         Value::Array(arr) => {
             for v in arr {
-                replace_placeholders(v, data);
+                replace_strings(v, replace)?;
             }
         }
         _ => {}
     }
+    Ok(())
 }
 
 impl DataDrivenRunner {
@@ -109,7 +122,17 @@ impl DataDrivenRunner {
     pub fn new(config: DataDrivenConfig) -> Result<Self> {
         let data_rows = Self::load_data(&config.data_source)?;
 
-        Ok(Self { config, data_rows })
+        Ok(Self {
+            config,
+            data_rows,
+            interpolator: Interpolator::new(),
+        })
+    }
+
+    /// Generate the same dynamic variable values on every run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.interpolator = Interpolator::seeded(seed);
+        self
     }
 
     /// Load data from the specified data source
@@ -128,10 +151,10 @@ impl DataDrivenRunner {
         })?;
 
         let file = File::open(file_path).map_err(|e| {
-            Error::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to open CSV file: {}", e),
-            ))
+            Error::IoError(std::io::Error::other(format!(
+                "Failed to open CSV file: {}",
+                e
+            )))
         })?;
 
         let reader = BufReader::new(file);
@@ -164,10 +187,10 @@ impl DataDrivenRunner {
 
         // Reopen the file to start reading from the beginning
         let file = File::open(file_path).map_err(|e| {
-            Error::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to reopen CSV file: {}", e),
-            ))
+            Error::IoError(std::io::Error::other(format!(
+                "Failed to reopen CSV file: {}",
+                e
+            )))
         })?;
 
         // Create a new CSV reader directly from the file
@@ -202,10 +225,10 @@ impl DataDrivenRunner {
         })?;
 
         let file = File::open(file_path).map_err(|e| {
-            Error::IoError(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to open JSON file: {}", e),
-            ))
+            Error::IoError(std::io::Error::other(format!(
+                "Failed to open JSON file: {}",
+                e
+            )))
         })?;
 
         let json_value: Value = serde_json::from_reader(file)
@@ -321,7 +344,7 @@ impl DataDrivenRunner {
         let mut config_json = serde_json::to_value(config)?;
 
         // Apply replacements
-        Self::replace_placeholders(&mut config_json, row);
+        replace_placeholders(&mut config_json, row, &self.interpolator)?;
 
         // Convert back to config
         let new_config = serde_json::from_value(config_json)?;
@@ -329,28 +352,22 @@ impl DataDrivenRunner {
         Ok(new_config)
     }
 
-    /// Replace placeholders in a JSON value
-    fn replace_placeholders(value: &mut Value, data: &HashMap<String, String>) {
-        match value {
-            Value::String(s) => {
-                let mut result = s.clone();
-                for (key, val) in data {
-                    let placeholder = format!("{{{{{}}}}}", key);
-                    result = result.replace(&placeholder, val);
-                }
-                *s = result;
-            }
-            Value::Object(obj) => {
-                for (_, v) in obj {
-                    Self::replace_placeholders(v, data);
-                }
-            }
-            Value::Array(arr) => {
-                for v in arr {
-                    Self::replace_placeholders(v, data);
-                }
-            }
-            _ => {}
-        }
+    /// Apply replacements from a data row to a collection, keeping the
+    /// directory its body files and schema references are relative to.
+    ///
+    /// Dynamic variables are left in place for the collection runner, which
+    /// generates them for every request it sends.
+    pub fn apply_collection_replacements(
+        &self,
+        collection: &ApiCollection,
+        row: &HashMap<String, String>,
+    ) -> Result<ApiCollection> {
+        let mut collection_json = serde_json::to_value(collection)?;
+        replace_strings(&mut collection_json, &mut |s| {
+            Ok(interpolation::replace_variables(s, row))
+        })?;
+        let mut updated: ApiCollection = serde_json::from_value(collection_json)?;
+        updated.base_dir = collection.base_dir.clone();
+        Ok(updated)
     }
 }
@@ -0,0 +1,161 @@
+use crate::error::{Error, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The environment a run uses: its name, variables from an environment file
+/// and variables given on the command line.
+///
+/// Variables are resolved from lowest to highest precedence: configuration
+/// `variables`, the configuration's environment in `environments`, the
+/// environment file, then the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    /// Name of the environment, used to pick from `environments` sections
+    pub name: String,
+    file: HashMap<String, String>,
+    overrides: HashMap<String, String>,
+}
+
+impl Environment {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Load variables from a JSON, YAML or `.env` file.
+    ///
+    /// JSON and YAML files hold either variables, or environments by name
+    /// that each hold variables, like a collection's `environments`.
+    pub fn with_file(mut self, path: &Path) -> Result<Self> {
+        self.file = load_file(path, &self.name)?;
+        Ok(self)
+    }
+
+    /// Set variables given on the command line, overriding every other source
+    pub fn with_overrides(mut self, overrides: HashMap<String, String>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Variables from the environment file and the command line
+    pub fn variables(&self) -> HashMap<String, String> {
+        let mut variables = self.file.clone();
+        variables.extend(self.overrides.clone());
+        variables
+    }
+
+    /// Resolve the variables of a configuration with its own `variables` and `environments`
+    pub fn resolve(
+        &self,
+        variables: Option<&HashMap<String, String>>,
+        environments: Option<&HashMap<String, HashMap<String, String>>>,
+    ) -> HashMap<String, String> {
+        let mut resolved = variables.cloned().unwrap_or_default();
+        if let Some(selected) = environments.and_then(|e| e.get(&self.name)) {
+            resolved.extend(selected.clone());
+        }
+        resolved.extend(self.variables());
+        resolved
+    }
+}
+
+/// Parse a `NAME=VALUE` command line variable
+pub fn parse_override(input: &str) -> std::result::Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected NAME=VALUE, got '{}'", input)),
+    }
+}
+
+/// Load the variables of an environment file, picking `environment` if it holds named environments
+pub fn load_file(path: &Path, environment: &str) -> Result<HashMap<String, String>> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    let document: Value = match extension.as_str() {
+        "json" => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        "yaml" | "yml" => serde_yaml::from_str(&std::fs::read_to_string(path)?).map_err(|e| {
+            Error::ConfigError(format!("Invalid YAML in {}: {}", path.display(), e))
+        })?,
+        _ if extension == "env" || file_name.starts_with(".env") => {
+            let mut variables = HashMap::new();
+            let entries = dotenvy::from_path_iter(path).map_err(|e| {
+                Error::ConfigError(format!("Cannot read {}: {}", path.display(), e))
+            })?;
+            for entry in entries {
+                let (name, value) = entry.map_err(|e| {
+                    Error::ConfigError(format!("Invalid .env file {}: {}", path.display(), e))
+                })?;
+                variables.insert(name, value);
+            }
+            return Ok(variables);
+        }
+        _ => {
+            return Err(Error::ConfigError(format!(
+                "Unsupported environment file: {}. Use a .json, .yaml, .yml or .env file.",
+                path.display()
+            )))
+        }
+    };
+
+    let invalid = |reason: &str| {
+        Error::ConfigError(format!(
+            "Invalid environment file {}: {}",
+            path.display(),
+            reason
+        ))
+    };
+    let entries = match document {
+        Value::Object(entries) => entries,
+        Value::Null => return Ok(HashMap::new()),
+        _ => return Err(invalid("expected an object of variables or environments")),
+    };
+
+    // Named environments when every value is an object
+    let named = !entries.is_empty() && entries.values().all(Value::is_object);
+    let entries = if named {
+        match entries.get(environment) {
+            Some(Value::Object(selected)) => selected.clone(),
+            _ => {
+                let mut names: Vec<&String> = entries.keys().collect();
+                names.sort();
+                return Err(invalid(&format!(
+                    "no environment '{}' (found {})",
+                    environment,
+                    names
+                        .iter()
+                        .map(|n| n.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+        }
+    } else {
+        entries
+    };
+
+    entries
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(s) => s,
+                Value::Null => String::new(),
+                Value::Bool(_) | Value::Number(_) => value.to_string(),
+                _ => return Err(invalid(&format!("'{}' must be a scalar value", name))),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
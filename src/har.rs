@@ -0,0 +1,425 @@
+use crate::api_collection::ApiCollection;
+use crate::common::Conversion;
+use crate::error::{Error, Result};
+use crate::performance_enhanced::EnhancedPerformanceConfig;
+use crate::request_body::BodyType;
+use base64::Engine as _;
+use regex::Regex;
+use reqwest::Url;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// File extensions of static assets
+const STATIC_EXTENSIONS: &[&str] = &[
+    "js", "mjs", "css", "map", "png", "jpg", "jpeg", "gif", "svg", "ico", "webp", "avif", "bmp",
+    "woff", "woff2", "ttf", "otf", "eot", "mp3", "mp4", "webm", "ogg", "wav",
+];
+/// Browser resource types of static assets
+const STATIC_RESOURCE_TYPES: &[&str] = &["image", "stylesheet", "script", "font", "media"];
+/// Response content types of static assets
+const STATIC_MIME_PREFIXES: &[&str] = &[
+    "image/",
+    "font/",
+    "audio/",
+    "video/",
+    "text/css",
+    "text/javascript",
+    "application/javascript",
+    "application/x-javascript",
+];
+/// Request headers the browser adds on its own, or that only make sense in the captured session
+const DROPPED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "cookie",
+    "connection",
+    "accept-encoding",
+    "origin",
+    "referer",
+    "user-agent",
+    "pragma",
+    "cache-control",
+    "priority",
+    "upgrade-insecure-requests",
+];
+
+/// Rules deciding which HAR entries are imported
+#[derive(Debug, Clone, Default)]
+pub struct HarFilter {
+    /// Hosts to keep, where `*.example.com` also matches subdomains.
+    /// Defaults to the host of the first entry.
+    pub hosts: Vec<String>,
+    /// Keep images, scripts, stylesheets, fonts and media
+    pub include_static: bool,
+    /// Regular expressions; entries whose URL matches one are dropped
+    pub exclude: Vec<String>,
+}
+
+/// Options for importing a HAR file
+#[derive(Debug, Clone, Default)]
+pub struct HarImportOptions {
+    /// Name of the generated configuration, defaults to the title of the first page
+    pub name: Option<String>,
+    /// Which entries are imported
+    pub filter: HarFilter,
+    /// Add body type and top-level field assertions from the captured JSON responses
+    pub body_assertions: bool,
+}
+
+/// A request kept from the HAR file
+struct Entry {
+    method: String,
+    url: Url,
+    headers: BTreeMap<String, String>,
+    body: Option<(BodyType, Value, Option<String>)>,
+    status: u16,
+    response: Option<Value>,
+}
+
+impl Entry {
+    /// Requests with the same key are the same step
+    fn key(&self) -> (String, String, String) {
+        let body = self
+            .body
+            .as_ref()
+            .map(|(_, body, _)| body.to_string())
+            .unwrap_or_default();
+        (self.method.clone(), self.url.to_string(), body)
+    }
+
+    fn name(&self) -> String {
+        format!("{} {}", self.method, self.url.path())
+    }
+}
+
+/// Convert a HAR file into a collection with one request per distinct request
+pub fn import_collection(har: &Value, options: &HarImportOptions) -> Result<Conversion> {
+    let (entries, warnings) = entries(har, &options.filter)?;
+    let Some(first) = entries.first() else {
+        return Err(Error::ValidationError(
+            "No requests left in the HAR file after filtering".to_string(),
+        ));
+    };
+    let base_url = first.url.origin().ascii_serialization();
+
+    let mut seen = Vec::new();
+    let mut requests = Vec::new();
+    for entry in &entries {
+        let key = entry.key();
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+
+        let url = entry.url.to_string();
+        let url = match url.strip_prefix(&base_url) {
+            Some(rest) => format!("{{{{base_url}}}}{}", rest),
+            None => url,
+        };
+        let mut request = json!({"name": entry.name(), "url": url, "method": entry.method});
+        if !entry.headers.is_empty() {
+            request["headers"] = json!(entry.headers);
+        }
+        if let Some((body_type, body, content_type)) = &entry.body {
+            request["body"] = body.clone();
+            if *body_type != BodyType::Json {
+                request["body_type"] = json!(body_type);
+            }
+            if let Some(content_type) = content_type {
+                request["content_type"] = json!(content_type);
+            }
+        }
+        if entry.status > 0 {
+            request["expected_status"] = json!(entry.status);
+        }
+        if options.body_assertions {
+            add_body_assertions(&mut request, entry.response.as_ref());
+        }
+        requests.push(request);
+    }
+
+    let document = json!({
+        "name": name(har, options),
+        "description": format!("Imported from a HAR file with {} requests", entries.len()),
+        "variables": {"base_url": base_url},
+        "requests": requests
+    });
+
+    // Make sure the result loads as a collection
+    serde_json::from_value::<ApiCollection>(document.clone())?;
+
+    Ok(Conversion { document, warnings })
+}
+
+/// Convert a HAR file into an enhanced performance test, weighting each scenario by how often it was requested
+pub fn import_performance(har: &Value, options: &HarImportOptions) -> Result<Conversion> {
+    let (entries, mut warnings) = entries(har, &options.filter)?;
+    if entries.is_empty() {
+        return Err(Error::ValidationError(
+            "No requests left in the HAR file after filtering".to_string(),
+        ));
+    }
+
+    // Scenarios keep the order in which requests were first seen
+    let mut scenarios: Vec<(String, String, Value)> = Vec::new();
+    let mut weights: HashMap<(String, String), u32> = HashMap::new();
+    for entry in &entries {
+        let key = (entry.method.clone(), entry.url.to_string());
+        let weight = weights.entry(key.clone()).or_insert(0);
+        *weight += 1;
+        if *weight > 1 {
+            continue;
+        }
+
+        let mut scenario = json!({
+            "name": entry.name(),
+            "target_url": key.1,
+            "method": entry.method,
+            "tags": {"source": "har"}
+        });
+        if !entry.headers.is_empty() {
+            scenario["headers"] = json!(entry.headers);
+        }
+        match &entry.body {
+            Some((BodyType::Json, body, _)) => scenario["body"] = body.clone(),
+            Some(_) => warnings.push(format!(
+                "The body of '{}' is not JSON and was not imported",
+                entry.name()
+            )),
+            None => {}
+        }
+        scenarios.push((key.0, key.1, scenario));
+    }
+
+    let scenarios: Vec<Value> = scenarios
+        .into_iter()
+        .map(|(method, url, mut scenario)| {
+            scenario["weight"] = json!(weights[&(method, url)]);
+            scenario
+        })
+        .collect();
+
+    let document = json!({
+        "name": name(har, options),
+        "description": format!("Imported from a HAR file with {} requests", entries.len()),
+        "timeout": 30,
+        "retries": 0,
+        "environment": "production",
+        "load_profile": {
+            "type": "ramping_vus",
+            "initial": 1,
+            "stages": [
+                {"duration_secs": 30, "target": 10},
+                {"duration_secs": 60, "target": 10},
+                {"duration_secs": 10, "target": 0}
+            ]
+        },
+        "scenarios": scenarios,
+        "success_threshold": 95.0
+    });
+
+    // Make sure the result loads as a performance test
+    serde_json::from_value::<EnhancedPerformanceConfig>(document.clone())?;
+
+    Ok(Conversion { document, warnings })
+}
+
+fn name(har: &Value, options: &HarImportOptions) -> String {
+    options
+        .name
+        .clone()
+        .or_else(|| har["log"]["pages"][0]["title"].as_str().map(str::to_string))
+        .unwrap_or_else(|| "Imported HAR".to_string())
+}
+
+/// The entries of a HAR file that pass the filter, and what was skipped
+fn entries(har: &Value, filter: &HarFilter) -> Result<(Vec<Entry>, Vec<String>)> {
+    let list = har["log"]["entries"].as_array().ok_or_else(|| {
+        Error::ValidationError("Not a HAR file: missing 'log.entries'".to_string())
+    })?;
+    let exclude = filter
+        .exclude
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| {
+                Error::ConfigError(format!("Invalid exclude pattern '{}': {}", pattern, e))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut hosts = filter.hosts.clone();
+    let mut static_assets = 0;
+    let mut excluded = 0;
+    let mut other_hosts = Vec::new();
+    let mut entries = Vec::new();
+
+    for har_entry in list {
+        let request = &har_entry["request"];
+        let Some(url) = request["url"].as_str().and_then(|url| Url::parse(url).ok()) else {
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            continue;
+        }
+        let host = url.host_str().unwrap_or("").to_string();
+        if hosts.is_empty() {
+            hosts.push(host.clone());
+        }
+
+        if !filter.include_static && is_static(har_entry, &url) {
+            static_assets += 1;
+            continue;
+        }
+        if !hosts.iter().any(|pattern| host_matches(pattern, &host)) {
+            if !other_hosts.contains(&host) {
+                other_hosts.push(host);
+            }
+            continue;
+        }
+        if exclude.iter().any(|pattern| pattern.is_match(url.as_str())) {
+            excluded += 1;
+            continue;
+        }
+
+        let headers = request["headers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|header| {
+                let name = header["name"].as_str()?;
+                let lower = name.to_ascii_lowercase();
+                let dropped = lower.starts_with(':')
+                    || lower.starts_with("sec-")
+                    || DROPPED_HEADERS.contains(&lower.as_str());
+                (!dropped).then(|| (lower, header["value"].as_str().unwrap_or("").to_string()))
+            })
+            .collect();
+
+        let response = &har_entry["response"];
+        entries.push(Entry {
+            method: request["method"].as_str().unwrap_or("GET").to_uppercase(),
+            url,
+            headers,
+            body: body(&request["postData"]),
+            status: response["status"].as_u64().unwrap_or(0) as u16,
+            response: response_json(&response["content"]),
+        });
+    }
+
+    let mut warnings = Vec::new();
+    if static_assets > 0 {
+        warnings.push(format!("Skipped {} static asset request(s)", static_assets));
+    }
+    if !other_hosts.is_empty() {
+        warnings.push(format!(
+            "Skipped requests to other hosts: {}",
+            other_hosts.join(", ")
+        ));
+    }
+    if excluded > 0 {
+        warnings.push(format!(
+            "Skipped {} request(s) matching an exclude pattern",
+            excluded
+        ));
+    }
+    Ok((entries, warnings))
+}
+
+fn is_static(entry: &Value, url: &Url) -> bool {
+    if let Some(resource_type) = entry["_resourceType"].as_str() {
+        if STATIC_RESOURCE_TYPES.contains(&resource_type) {
+            return true;
+        }
+    }
+    let extension = url
+        .path()
+        .rsplit('/')
+        .next()
+        .and_then(|file| file.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    if extension.is_some_and(|extension| STATIC_EXTENSIONS.contains(&extension.as_str())) {
+        return true;
+    }
+    let mime = entry["response"]["content"]["mimeType"]
+        .as_str()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    STATIC_MIME_PREFIXES
+        .iter()
+        .any(|prefix| mime.starts_with(prefix))
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+        None => pattern.eq_ignore_ascii_case(host),
+    }
+}
+
+/// Request body, body type and content type of a HAR `postData`
+fn body(post_data: &Value) -> Option<(BodyType, Value, Option<String>)> {
+    let mime = post_data["mimeType"].as_str().unwrap_or("");
+    let text = post_data["text"].as_str().unwrap_or("");
+
+    if mime.contains("json") {
+        if let Ok(json) = serde_json::from_str(text) {
+            return Some((BodyType::Json, json, None));
+        }
+    }
+    if mime.starts_with("application/x-www-form-urlencoded") {
+        let fields: Map<String, Value> = match post_data["params"].as_array() {
+            Some(params) => params
+                .iter()
+                .filter_map(|p| Some((p["name"].as_str()?.to_string(), p["value"].clone())))
+                .collect(),
+            None => url::form_urlencoded::parse(text.as_bytes())
+                .map(|(key, value)| (key.into_owned(), json!(value)))
+                .collect(),
+        };
+        return Some((BodyType::Form, Value::Object(fields), None));
+    }
+    if text.is_empty() {
+        return None;
+    }
+    let content_type = (!mime.is_empty()).then(|| mime.to_string());
+    Some((BodyType::Raw, json!(text), content_type))
+}
+
+/// The captured response body, if it is JSON
+fn response_json(content: &Value) -> Option<Value> {
+    let text = content["text"].as_str()?;
+    if content["encoding"].as_str() == Some("base64") {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .ok()?;
+        return serde_json::from_slice(&bytes).ok();
+    }
+    serde_json::from_str(text).ok()
+}
+
+/// Expect the captured body type and the presence of its top-level fields
+fn add_body_assertions(request: &mut Value, response: Option<&Value>) {
+    match response {
+        Some(Value::Object(fields)) => {
+            request["expected_body_type"] = json!("object");
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            let assertions: Vec<Value> = names
+                .into_iter()
+                .map(|name| {
+                    let path = if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                        format!("$.{}", name)
+                    } else {
+                        format!("$['{}']", name.replace('\'', "\\'"))
+                    };
+                    json!({"path": path, "operator": "exists"})
+                })
+                .collect();
+            if !assertions.is_empty() {
+                request["assertions"] = json!(assertions);
+            }
+        }
+        Some(Value::Array(_)) => request["expected_body_type"] = json!("array"),
+        _ => {}
+    }
+}
@@ -0,0 +1,225 @@
+use crate::error::{Error, Result};
+use base64::Engine as _;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A `{{name}}` placeholder
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{([^{}$][^{}]*)\}\}").unwrap());
+
+/// A `{{$name}}` or `{{$name(arguments)}}` dynamic variable
+static DYNAMIC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\$([A-Za-z][A-Za-z0-9]*)(?:\(([^{}]*)\))?\}\}").unwrap());
+
+const FIRST_NAMES: [&str; 12] = [
+    "Ada", "Alan", "Grace", "Linus", "Margaret", "Dennis", "Barbara", "Ken", "Radia", "Tim",
+    "Frances", "John",
+];
+
+const LAST_NAMES: [&str; 12] = [
+    "Lovelace",
+    "Turing",
+    "Hopper",
+    "Torvalds",
+    "Hamilton",
+    "Ritchie",
+    "Liskov",
+    "Thompson",
+    "Perlman",
+    "Berners-Lee",
+    "Allen",
+    "McCarthy",
+];
+
+const ALPHANUMERIC: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Resolves `{{name}}` variables and `{{$name}}` dynamic variables in text.
+///
+/// Every `{{$name}}` occurrence gets a fresh value. A seeded interpolator
+/// produces the same random values on every run.
+#[derive(Debug)]
+pub struct Interpolator {
+    seed: Option<u64>,
+    rng: Mutex<StdRng>,
+}
+
+impl Interpolator {
+    pub fn new() -> Self {
+        Self {
+            seed: None,
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// An interpolator whose random values repeat for the same seed
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    /// An interpolator for the request at `index` of a run.
+    ///
+    /// With a seed, each request gets its own seed derived from it, so its
+    /// values do not depend on the order in which concurrent requests run.
+    pub fn for_request(&self, index: usize) -> Self {
+        match self.seed {
+            Some(seed) => {
+                Self::seeded(seed ^ (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            }
+            None => Self::new(),
+        }
+    }
+
+    /// Replace variables and dynamic variables in `input`.
+    ///
+    /// Variables are replaced first, so they can be used in dynamic variable
+    /// arguments such as `{{$base64({{user}}:{{password}})}}`. Unknown names are
+    /// left in place and passed to `on_missing`.
+    pub fn interpolate(
+        &self,
+        input: &str,
+        variables: &HashMap<String, String>,
+        mut on_missing: impl FnMut(&str),
+    ) -> Result<String> {
+        if !input.contains("{{") {
+            return Ok(input.to_string());
+        }
+
+        let replaced = PLACEHOLDER.replace_all(input, |captures: &Captures| {
+            match variables.get(&captures[1]) {
+                Some(value) => value.clone(),
+                None => {
+                    on_missing(&captures[1]);
+                    captures[0].to_string()
+                }
+            }
+        });
+
+        let mut error = None;
+        let generated = DYNAMIC.replace_all(&replaced, |captures: &Captures| {
+            let arguments = captures.get(2).map(|m| m.as_str());
+            match self.generate(&captures[1], arguments) {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    on_missing(&format!("${}", &captures[1]));
+                    captures[0].to_string()
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                    captures[0].to_string()
+                }
+            }
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(generated.into_owned()),
+        }
+    }
+
+    /// The value of a dynamic variable, `None` for unknown names
+    pub fn generate(&self, name: &str, arguments: Option<&str>) -> Result<Option<String>> {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        let value = match name {
+            "uuid" | "guid" => {
+                let mut bytes: [u8; 16] = rng.gen();
+                bytes[6] = (bytes[6] & 0x0f) | 0x40;
+                bytes[8] = (bytes[8] & 0x3f) | 0x80;
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                format!(
+                    "{}-{}-{}-{}-{}",
+                    &hex[0..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..32]
+                )
+            }
+            "timestamp" => Utc::now().timestamp().to_string(),
+            "timestampMs" => Utc::now().timestamp_millis().to_string(),
+            "isoDate" | "isoTimestamp" => {
+                Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            }
+            "randomInt" => {
+                let (min, max) = match arguments.map(str::trim).filter(|a| !a.is_empty()) {
+                    Some(arguments) => parse_range(arguments)?,
+                    None => (0, 1000),
+                };
+                rng.gen_range(min..=max).to_string()
+            }
+            "randomBoolean" => rng.gen_bool(0.5).to_string(),
+            "randomString" => {
+                let length = match arguments.map(str::trim).filter(|a| !a.is_empty()) {
+                    Some(length) => length.parse::<usize>().map_err(|_| {
+                        Error::ValidationError(format!(
+                            "$randomString expects a length, got '{}'",
+                            length
+                        ))
+                    })?,
+                    None => 16,
+                };
+                (0..length)
+                    .map(|_| ALPHANUMERIC[rng.gen_range(0..ALPHANUMERIC.len())] as char)
+                    .collect()
+            }
+            "randomFirstName" => FIRST_NAMES[rng.gen_range(0..FIRST_NAMES.len())].to_string(),
+            "randomLastName" => LAST_NAMES[rng.gen_range(0..LAST_NAMES.len())].to_string(),
+            "randomEmail" => {
+                let first = FIRST_NAMES[rng.gen_range(0..FIRST_NAMES.len())];
+                let last = LAST_NAMES[rng.gen_range(0..LAST_NAMES.len())];
+                format!(
+                    "{}.{}.{}@example.com",
+                    first.to_lowercase(),
+                    last.to_lowercase().replace('-', ""),
+                    rng.gen_range(1000..10000)
+                )
+            }
+            "base64" => base64::engine::general_purpose::STANDARD.encode(arguments.unwrap_or("")),
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+}
+
+/// Replace the `{{name}}` variables in `input`, leaving unknown names and dynamic variables in place
+pub fn replace_variables(input: &str, variables: &HashMap<String, String>) -> String {
+    if !input.contains("{{") {
+        return input.to_string();
+    }
+    PLACEHOLDER
+        .replace_all(input, |captures: &Captures| {
+            variables
+                .get(&captures[1])
+                .cloned()
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+impl Default for Interpolator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse `min,max` arguments of `$randomInt`
+fn parse_range(arguments: &str) -> Result<(i64, i64)> {
+    let invalid = || {
+        Error::ValidationError(format!(
+            "$randomInt expects 'min,max' arguments, got '{}'",
+            arguments
+        ))
+    };
+    let (min, max) = arguments.split_once(',').ok_or_else(invalid)?;
+    let min: i64 = min.trim().parse().map_err(|_| invalid())?;
+    let max: i64 = max.trim().parse().map_err(|_| invalid())?;
+    if min > max {
+        return Err(invalid());
+    }
+    Ok((min, max))
+}
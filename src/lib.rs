@@ -1,15 +1,38 @@
+// The config schemas built with serde_json::json! exceed the default macro recursion limit
+#![recursion_limit = "256"]
+
 pub mod ai;
 pub mod ai_compat;
 pub mod api;
 pub mod api_collection;
+pub mod api_suite;
+pub mod assertions;
+pub mod capture;
+pub mod client;
+pub mod collection_graph;
 pub mod common;
+pub mod cookies;
 pub mod data_driven;
+pub mod environment;
 pub mod error;
+pub mod har;
+pub mod interpolation;
+pub mod oauth2;
 pub mod performance;
 pub mod performance_enhanced;
+pub mod postman;
 pub mod reporting;
+pub mod request_body;
+pub mod response;
+pub mod response_schema;
+pub mod retry;
 pub mod schema;
+pub mod scripting;
+pub mod secrets;
 pub mod security;
+pub mod signing;
+pub mod snapshot;
+pub mod timing;
 pub mod web;
 
 pub use error::Error;
@@ -0,0 +1,266 @@
+use crate::api_collection::CollectionAuth;
+use crate::error::{Error, Result};
+use crate::secrets;
+use log::info;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Tokens are refreshed this long before they expire
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Token endpoint response (RFC 6749 section 5.1)
+#[derive(Debug, Clone, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default = "default_token_type")]
+    token_type: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+fn default_token_type() -> String {
+    "Bearer".to_string()
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    response: TokenResponse,
+    expires_at: Option<Instant>,
+}
+
+impl Token {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| Instant::now() + EXPIRY_MARGIN >= expires_at)
+    }
+}
+
+/// OAuth2 access tokens, fetched on first use and shared for the lifetime of the cache.
+///
+/// Tokens are keyed by their resolved auth settings, so one cache can serve
+/// requests with different credentials.
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    tokens: Mutex<HashMap<String, Token>>,
+    latest: Mutex<Option<TokenResponse>>,
+}
+
+impl TokenCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A valid access token, fetching or refreshing it if needed
+    pub async fn access_token(&self, client: &Client, auth: &CollectionAuth) -> Result<String> {
+        let key = cache_key(auth)?;
+        let mut tokens = self.tokens.lock().await;
+        if let Some(token) = tokens.get(&key).filter(|token| !token.is_expired()) {
+            return Ok(token.response.access_token.clone());
+        }
+
+        let previous = tokens.remove(&key);
+        let token = self.renew(client, auth, previous).await?;
+        let access_token = token.response.access_token.clone();
+        tokens.insert(key, token);
+        Ok(access_token)
+    }
+
+    /// Replace a token the server rejected, unless another request already did
+    pub async fn refresh(
+        &self,
+        client: &Client,
+        auth: &CollectionAuth,
+        rejected: &str,
+    ) -> Result<String> {
+        let key = cache_key(auth)?;
+        let mut tokens = self.tokens.lock().await;
+        if let Some(token) = tokens
+            .get(&key)
+            .filter(|token| token.response.access_token != rejected && !token.is_expired())
+        {
+            return Ok(token.response.access_token.clone());
+        }
+
+        let previous = tokens.remove(&key);
+        let token = self.renew(client, auth, previous).await?;
+        let access_token = token.response.access_token.clone();
+        tokens.insert(key, token);
+        Ok(access_token)
+    }
+
+    /// Send a request with a bearer token, refreshing the token and sending again once on 401
+    pub async fn send(
+        &self,
+        client: &Client,
+        auth: &CollectionAuth,
+        request: RequestBuilder,
+    ) -> Result<Response> {
+        let token = self.access_token(client, auth).await?;
+        let retry = request.try_clone();
+        let response = request.bearer_auth(&token).send().await?;
+        match retry {
+            Some(retry) if response.status() == StatusCode::UNAUTHORIZED => {
+                info!("Access token rejected, fetching a new one");
+                let token = self.refresh(client, auth, &token).await?;
+                Ok(retry.bearer_auth(token).send().await?)
+            }
+            _ => Ok(response),
+        }
+    }
+
+    /// The most recently issued token as variables
+    pub async fn variables(&self) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        if let Some(token) = self.latest.lock().await.as_ref() {
+            variables.insert(
+                "oauth2_access_token".to_string(),
+                token.access_token.clone(),
+            );
+            variables.insert("oauth2_token_type".to_string(), token.token_type.clone());
+            if let Some(refresh_token) = &token.refresh_token {
+                variables.insert("oauth2_refresh_token".to_string(), refresh_token.clone());
+            }
+        }
+        variables
+    }
+
+    /// Use the refresh token of the previous token if there is one, otherwise the configured grant
+    async fn renew(
+        &self,
+        client: &Client,
+        auth: &CollectionAuth,
+        previous: Option<Token>,
+    ) -> Result<Token> {
+        let refresh_token = previous.and_then(|token| token.response.refresh_token);
+        let token = match refresh_token {
+            Some(refresh_token) => match request_token(client, auth, Some(&refresh_token)).await {
+                Ok(token) => token,
+                Err(e) => {
+                    info!(
+                        "Refreshing the access token failed ({}), requesting a new one",
+                        e
+                    );
+                    request_token(client, auth, None).await?
+                }
+            },
+            None => request_token(client, auth, None).await?,
+        };
+        // Registered before the tokens can reach a log line
+        secrets::register(
+            std::iter::once(token.response.access_token.clone())
+                .chain(token.response.refresh_token.clone()),
+        );
+        *self.latest.lock().await = Some(token.response.clone());
+        Ok(token)
+    }
+}
+
+fn cache_key(auth: &CollectionAuth) -> Result<String> {
+    Ok(serde_json::to_string(auth)?)
+}
+
+/// Request a token with the configured grant, or with a refresh token from an earlier response
+async fn request_token(
+    client: &Client,
+    auth: &CollectionAuth,
+    refresh_token: Option<&str>,
+) -> Result<Token> {
+    let token_url = required(&auth.token_url, "token_url")?;
+    let grant_type = match refresh_token {
+        Some(_) => "refresh_token",
+        None => auth.grant_type.as_deref().unwrap_or("client_credentials"),
+    };
+
+    let mut form: Vec<(&str, String)> = vec![("grant_type", grant_type.to_string())];
+    match grant_type {
+        "client_credentials" => {}
+        "password" => {
+            form.push(("username", required(&auth.username, "username")?));
+            form.push(("password", required(&auth.password, "password")?));
+        }
+        "refresh_token" => {
+            let refresh_token = match refresh_token {
+                Some(refresh_token) => refresh_token.to_string(),
+                None => required(&auth.refresh_token, "refresh_token")?,
+            };
+            form.push(("refresh_token", refresh_token));
+        }
+        "authorization_code" => {
+            form.push(("code", required(&auth.code, "code")?));
+            if let Some(code_verifier) = &auth.code_verifier {
+                form.push(("code_verifier", code_verifier.clone()));
+            }
+            if let Some(redirect_uri) = &auth.redirect_uri {
+                form.push(("redirect_uri", redirect_uri.clone()));
+            }
+        }
+        other => {
+            return Err(Error::ValidationError(format!(
+                "Unsupported OAuth2 grant type '{}'",
+                other
+            )))
+        }
+    }
+    if let Some(scope) = &auth.scope {
+        form.push(("scope", scope.clone()));
+    }
+    if let Some(audience) = &auth.audience {
+        form.push(("audience", audience.clone()));
+    }
+
+    let client_id = required(&auth.client_id, "client_id")?;
+    let mut request = client.post(&token_url);
+    match auth.client_auth.as_deref().unwrap_or("body") {
+        "basic" => request = request.basic_auth(client_id, auth.client_secret.clone()),
+        "body" => {
+            form.push(("client_id", client_id));
+            if let Some(client_secret) = &auth.client_secret {
+                form.push(("client_secret", client_secret.clone()));
+            }
+        }
+        other => {
+            return Err(Error::ValidationError(format!(
+                "Unsupported OAuth2 client_auth '{}', expected body or basic",
+                other
+            )))
+        }
+    }
+
+    info!(
+        "Requesting OAuth2 token ({}) from {}",
+        grant_type, token_url
+    );
+    let response = request.form(&form).send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::AuthError(format!(
+            "Token request to {} failed with status {}: {}",
+            token_url, status, text
+        )));
+    }
+
+    let mut response: TokenResponse = serde_json::from_str(&text).map_err(|e| {
+        Error::AuthError(format!("Invalid token response from {}: {}", token_url, e))
+    })?;
+    // Servers may omit the refresh token when refreshing, the old one stays valid
+    if response.refresh_token.is_none() {
+        response.refresh_token = refresh_token.map(str::to_string);
+    }
+
+    Ok(Token {
+        expires_at: response
+            .expires_in
+            .map(|seconds| Instant::now() + Duration::from_secs(seconds)),
+        response,
+    })
+}
+
+fn required(value: &Option<String>, name: &str) -> Result<String> {
+    value
+        .clone()
+        .ok_or_else(|| Error::ValidationError(format!("'{}' is required for oauth2 auth", name)))
+}
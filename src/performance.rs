@@ -1,3 +1,4 @@
+use crate::client::{self, ClientOptions};
 use crate::common::{TestConfig, TestResult, TestRunner};
 use crate::error::{Error, Result};
 use async_trait::async_trait;
@@ -37,6 +38,7 @@ struct RequestResult {
 
 pub struct PerformanceTestRunner {
     client: Client,
+    client_options: ClientOptions,
     concurrent_users: u32,
     duration_secs: u64,
 }
@@ -45,12 +47,23 @@ impl PerformanceTestRunner {
     pub fn new(concurrent_users: u32, duration_secs: u64) -> Self {
         Self {
             client: Client::new(),
+            client_options: ClientOptions::default(),
             concurrent_users,
             duration_secs,
         }
     }
 
-    async fn run_load_test(&self, config: &PerformanceTestConfig) -> Vec<RequestResult> {
+    /// Override the TLS and proxy settings of every test config
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.client_options = client_options;
+        self
+    }
+
+    async fn run_load_test(
+        &self,
+        client: &Client,
+        config: &PerformanceTestConfig,
+    ) -> Vec<RequestResult> {
         let mut results = Vec::new();
         let start = Instant::now();
         let end = start + Duration::from_secs(self.duration_secs);
@@ -59,7 +72,7 @@ impl PerformanceTestRunner {
             let mut handles = Vec::new();
 
             for _ in 0..self.concurrent_users {
-                let client = self.client.clone();
+                let client = client.clone();
                 let target_url = config.target_url.clone();
                 let method = config.method.clone();
                 let headers = config.headers.clone();
@@ -113,7 +126,13 @@ impl TestRunner for PerformanceTestRunner {
             serde_json::from_value::<PerformanceTestConfig>(serde_json::to_value(config)?)?;
         let start = Instant::now();
 
-        let results = self.run_load_test(&config).await;
+        let client = client::client_for(
+            &self.client,
+            &config.base.client_options(),
+            &self.client_options,
+            Client::builder(),
+        )?;
+        let results = self.run_load_test(&client, &config).await;
         let duration = start.elapsed().as_secs_f64();
 
         let (success_count, error_count) =
@@ -1,3 +1,4 @@
+use crate::client::{self, ClientOptions};
 use crate::common::{TestConfig, TestResult, TestRunner};
 use crate::error::{Error, Result};
 use async_trait::async_trait;
@@ -326,6 +327,7 @@ fn percentile(sorted_values: &[f64], p: f64) -> f64 {
 /// Enhanced performance test runner
 pub struct EnhancedPerformanceRunner {
     client: Client,
+    client_options: ClientOptions,
 }
 
 impl EnhancedPerformanceRunner {
@@ -335,8 +337,15 @@ impl EnhancedPerformanceRunner {
                 .timeout(Duration::from_secs(30))
                 .build()
                 .unwrap_or_else(|_| Client::new()),
+            client_options: ClientOptions::default(),
         }
     }
+
+    /// Override the TLS and proxy settings of every test config
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.client_options = client_options;
+        self
+    }
 }
 
 impl Default for EnhancedPerformanceRunner {
@@ -726,8 +735,17 @@ impl TestRunner for EnhancedPerformanceRunner {
 
         info!("Starting performance test: {}", config.base.name);
 
-        // Run the load test
-        let metrics_collector = self.run_load_test(&config).await?;
+        // Run the load test with the TLS and proxy settings of this config
+        let runner = Self {
+            client: client::client_for(
+                &self.client,
+                &config.base.client_options(),
+                &self.client_options,
+                Client::builder().timeout(Duration::from_secs(30)),
+            )?,
+            client_options: self.client_options.clone(),
+        };
+        let metrics_collector = runner.run_load_test(&config).await?;
 
         // Calculate overall duration
         let duration = start.elapsed().as_secs_f64();
@@ -0,0 +1,840 @@
+use crate::api_collection::{ApiCollection, CollectionAuth, CollectionRequest};
+use crate::assertions::AssertionOperator;
+use crate::collection_graph::DependencyGraph;
+use crate::common::Conversion;
+use crate::error::{Error, Result};
+use crate::request_body::{BodyType, MultipartPart};
+use base64::Engine as _;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Schema URL of the Postman collection format written by `export`
+pub const COLLECTION_SCHEMA: &str =
+    "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// JavaScript property path, such as `.data.items[0].id`
+const JS_PATH: &str = r"((?:\.[A-Za-z_$][\w$]*|\[\d+\])*)";
+/// Expression holding the parsed response body in Postman tests
+const JS_BODY: &str = r"(?:pm\.response\.json\(\)|jsonData|responseJson|response|data|body)";
+
+static STATUS_TEST: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"pm\.response\.to\.have\.status\((\d{3})\)|pm\.expect\(pm\.response\.code\)\.to\.(?:eql|equal)\((\d{3})\)|responseCode\.code\s*===?\s*(\d{3})",
+    )
+    .unwrap()
+});
+static CAPTURE_TEST: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"pm\.(?:environment|collectionVariables|globals|variables)\.set\(\s*["']([^"']+)["']\s*,\s*{}{}\s*\)"#,
+        JS_BODY, JS_PATH
+    ))
+    .unwrap()
+});
+static EQUALS_TEST: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"pm\.expect\(\s*{}{}\s*\)\.to\.(?:eql|equal|be\.equal)\(\s*("[^"]*"|'[^']*'|-?\d+(?:\.\d+)?|true|false|null)\s*\)"#,
+        JS_BODY, JS_PATH
+    ))
+    .unwrap()
+});
+/// Lines of a test script that carry no check of their own
+static BOILERPLATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?://.*|\}\)?\)?;?|pm\.test\(.*(?:function\s*\(\)|=>)\s*\{|(?:var|let|const)\s+\w+\s*=\s*pm\.response\.json\(\);?)?\s*$",
+    )
+    .unwrap()
+});
+
+/// Convert a Postman v2.1 collection, and optionally Postman environments, into a collection
+pub fn import(collection: &Value, environments: &[Value]) -> Result<Conversion> {
+    let info = collection.get("info").ok_or_else(|| {
+        Error::ValidationError("Not a Postman collection: missing 'info'".to_string())
+    })?;
+    if let Some(schema) = info.get("schema").and_then(Value::as_str) {
+        if !schema.contains("v2.1") && !schema.contains("v2.0") {
+            return Err(Error::ValidationError(format!(
+                "Unsupported Postman collection schema '{}', expected v2.1",
+                schema
+            )));
+        }
+    }
+
+    let mut importer = Importer::default();
+    let name = info["name"].as_str().unwrap_or("Imported collection");
+    let mut output = Map::new();
+    output.insert("name".to_string(), json!(name));
+    if let Some(description) = description(info) {
+        output.insert("description".to_string(), json!(description));
+    }
+
+    let variables = key_values(collection.get("variable"));
+    if !variables.is_empty() {
+        output.insert("variables".to_string(), json!(variables));
+    }
+
+    if let Some(auth) = collection
+        .get("auth")
+        .and_then(|auth| importer.auth(auth, name))
+    {
+        output.insert("auth".to_string(), serde_json::to_value(auth)?);
+    }
+    importer.events(collection, name, None);
+
+    let mut requests = Vec::new();
+    importer.items(collection, "", None, &mut requests);
+    output.insert("requests".to_string(), Value::Array(requests));
+
+    let mut environment_map = Map::new();
+    for environment in environments {
+        let name = environment["name"].as_str().ok_or_else(|| {
+            Error::ValidationError("Not a Postman environment: missing 'name'".to_string())
+        })?;
+        environment_map.insert(
+            name.to_string(),
+            json!(key_values(environment.get("values"))),
+        );
+    }
+    if !environment_map.is_empty() {
+        output.insert("environments".to_string(), Value::Object(environment_map));
+    }
+
+    // Make sure the result loads as a collection
+    let document = Value::Object(output);
+    serde_json::from_value::<ApiCollection>(document.clone())?;
+
+    Ok(Conversion {
+        document,
+        warnings: importer.warnings,
+    })
+}
+
+/// Convert a collection into a Postman v2.1 collection
+pub fn export(collection: &ApiCollection) -> Result<Conversion> {
+    let mut warnings = Vec::new();
+    let mut output = Map::new();
+
+    let mut info = json!({"name": collection.name, "schema": COLLECTION_SCHEMA});
+    if let Some(description) = &collection.description {
+        info["description"] = json!(description);
+    }
+    if let Some(version) = &collection.version {
+        info["version"] = json!(version);
+    }
+    output.insert("info".to_string(), info);
+
+    // Postman runs requests in file order, so dependencies go first
+    let graph = DependencyGraph::build(collection)?;
+    let default_headers = collection
+        .defaults
+        .as_ref()
+        .and_then(|defaults| defaults.headers.clone())
+        .unwrap_or_default();
+    let items: Vec<Value> = graph
+        .order()
+        .iter()
+        .map(|index| {
+            export_request(
+                &collection.requests[*index],
+                &default_headers,
+                &mut warnings,
+            )
+        })
+        .collect();
+    output.insert("item".to_string(), Value::Array(items));
+
+    if let Some(auth) = &collection.auth {
+        match export_auth(auth) {
+            Some(auth) => {
+                output.insert("auth".to_string(), auth);
+            }
+            None => warnings.push(format!(
+                "Authentication type '{}' has no Postman equivalent and was dropped",
+                auth.auth_type
+            )),
+        }
+    }
+
+    if let Some(variables) = &collection.variables {
+        output.insert("variable".to_string(), postman_values(variables, false));
+    }
+    if collection.pre_request.is_some() || collection.post_response.is_some() {
+        warnings.push("Collection scripts were not exported".to_string());
+    }
+
+    Ok(Conversion {
+        document: Value::Object(output),
+        warnings,
+    })
+}
+
+/// Convert collection environment variables into a Postman environment
+pub fn export_environment(name: &str, variables: &HashMap<String, String>) -> Value {
+    json!({
+        "name": name,
+        "values": postman_values(variables, true),
+        "_postman_variable_scope": "environment"
+    })
+}
+
+#[derive(Default)]
+struct Importer {
+    warnings: Vec<String>,
+}
+
+impl Importer {
+    /// Flatten the items of a collection or folder, naming requests after their folders.
+    ///
+    /// `override_auth` is the nearest folder or request authentication, which
+    /// replaces the collection authentication for the requests below it.
+    fn items(
+        &mut self,
+        parent: &Value,
+        prefix: &str,
+        override_auth: Option<&Value>,
+        requests: &mut Vec<Value>,
+    ) {
+        for item in parent["item"].as_array().into_iter().flatten() {
+            let name = item["name"].as_str().unwrap_or("Unnamed request");
+            let name = if prefix.is_empty() {
+                name.to_string()
+            } else {
+                format!("{} / {}", prefix, name)
+            };
+            let auth = item
+                .get("auth")
+                .or_else(|| item["request"].get("auth"))
+                .or(override_auth);
+
+            if item.get("item").is_some() {
+                self.events(item, &name, None);
+                self.items(item, &name, auth, requests);
+            } else if let Some(request) = item.get("request") {
+                let mut output = self.request(request, &name);
+                if let Some(auth) = auth {
+                    self.request_auth(auth, &name, &mut output);
+                }
+                self.events(item, &name, Some(&mut output));
+                requests.push(Value::Object(output));
+            }
+        }
+    }
+
+    fn request(&mut self, request: &Value, name: &str) -> Map<String, Value> {
+        let mut output = Map::new();
+        output.insert("name".to_string(), json!(name));
+
+        // A request may be just its URL
+        if let Some(url) = request.as_str() {
+            output.insert("url".to_string(), json!(url));
+            output.insert("method".to_string(), json!("GET"));
+            return output;
+        }
+
+        if let Some(description) = description(request) {
+            output.insert("description".to_string(), json!(description));
+        }
+        output.insert("url".to_string(), json!(url(&request["url"])));
+        output.insert(
+            "method".to_string(),
+            json!(request["method"].as_str().unwrap_or("GET").to_uppercase()),
+        );
+
+        let headers = key_values(request.get("header"));
+        let content_type = headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.clone());
+        if !headers.is_empty() {
+            output.insert("headers".to_string(), json!(headers));
+        }
+
+        if let Some(body) = request.get("body") {
+            self.body(body, name, content_type, &mut output);
+        }
+        output
+    }
+
+    fn body(
+        &mut self,
+        body: &Value,
+        name: &str,
+        content_type: Option<String>,
+        output: &mut Map<String, Value>,
+    ) {
+        if body["disabled"].as_bool() == Some(true) {
+            return;
+        }
+        let (body_type, value) = match body["mode"].as_str().unwrap_or("") {
+            "raw" => {
+                let raw = body["raw"].as_str().unwrap_or("");
+                let language = body["options"]["raw"]["language"].as_str();
+                let is_json = language == Some("json")
+                    || content_type.as_deref().is_some_and(|t| t.contains("json"));
+                match serde_json::from_str::<Value>(raw) {
+                    Ok(json) if is_json => (BodyType::Json, json),
+                    _ if raw.is_empty() => return,
+                    _ => {
+                        if content_type.is_none() {
+                            let content_type = match language {
+                                Some("json") => "application/json",
+                                Some("xml") => "application/xml",
+                                Some("html") => "text/html",
+                                Some("javascript") => "application/javascript",
+                                _ => "text/plain",
+                            };
+                            output.insert("content_type".to_string(), json!(content_type));
+                        }
+                        (BodyType::Raw, json!(raw))
+                    }
+                }
+            }
+            "urlencoded" => (BodyType::Form, json!(key_values(body.get("urlencoded")))),
+            "formdata" => {
+                let parts: Vec<MultipartPart> = enabled(body.get("formdata"))
+                    .map(|part| {
+                        let file = (part["type"].as_str() == Some("file"))
+                            .then(|| match &part["src"] {
+                                Value::Array(sources) => sources.first().and_then(Value::as_str),
+                                source => source.as_str(),
+                            })
+                            .flatten();
+                        MultipartPart {
+                            name: part["key"].as_str().unwrap_or("").to_string(),
+                            value: file
+                                .is_none()
+                                .then(|| part["value"].as_str().unwrap_or("").to_string()),
+                            file: file.map(str::to_string),
+                            filename: None,
+                            content_type: part["contentType"].as_str().map(str::to_string),
+                        }
+                    })
+                    .collect();
+                (BodyType::Multipart, json!(parts))
+            }
+            "file" => match body["file"]["src"].as_str() {
+                Some(src) => (BodyType::BinaryFile, json!(src)),
+                None => {
+                    self.warnings
+                        .push(format!("'{}' sends a file body without a path", name));
+                    return;
+                }
+            },
+            "graphql" => {
+                let graphql = &body["graphql"];
+                let variables = graphql["variables"]
+                    .as_str()
+                    .and_then(|v| serde_json::from_str::<Value>(v).ok())
+                    .unwrap_or_else(|| graphql["variables"].clone());
+                (
+                    BodyType::Json,
+                    json!({"query": graphql["query"], "variables": variables}),
+                )
+            }
+            "" => return,
+            mode => {
+                self.warnings.push(format!(
+                    "'{}' uses the '{}' body mode, which was not imported",
+                    name, mode
+                ));
+                return;
+            }
+        };
+        if body_type != BodyType::Json {
+            output.insert("body_type".to_string(), json!(body_type));
+        }
+        output.insert("body".to_string(), value);
+    }
+
+    /// Collection level authentication
+    fn auth(&mut self, auth: &Value, name: &str) -> Option<CollectionAuth> {
+        let auth_type = auth["type"].as_str().unwrap_or("noauth");
+        let params = |kind: &str| key_values(auth.get(kind));
+        let empty = CollectionAuth::default();
+        match auth_type {
+            "noauth" => None,
+            "basic" => {
+                let params = params("basic");
+                Some(CollectionAuth {
+                    auth_type: "basic".to_string(),
+                    username: params.get("username").cloned(),
+                    password: params.get("password").cloned(),
+                    ..empty
+                })
+            }
+            "bearer" => Some(CollectionAuth {
+                auth_type: "bearer".to_string(),
+                token: params("bearer").get("token").cloned(),
+                ..empty
+            }),
+            "apikey" => {
+                let params = params("apikey");
+                Some(CollectionAuth {
+                    auth_type: "api_key".to_string(),
+                    key_name: params.get("key").cloned(),
+                    key_value: params.get("value").cloned(),
+                    key_in: params.get("in").cloned(),
+                    ..empty
+                })
+            }
+            "awsv4" => {
+                let params = params("awsv4");
+                Some(CollectionAuth {
+                    auth_type: "aws_sigv4".to_string(),
+                    access_key_id: params.get("accessKey").cloned(),
+                    secret_access_key: params.get("secretKey").cloned(),
+                    session_token: params.get("sessionToken").cloned(),
+                    region: params.get("region").cloned(),
+                    service: params.get("service").cloned(),
+                    ..empty
+                })
+            }
+            other => {
+                self.warnings.push(format!(
+                    "'{}' uses '{}' authentication, which was not imported",
+                    name, other
+                ));
+                None
+            }
+        }
+    }
+
+    /// Request or folder authentication differing from the collection, sent as headers
+    fn request_auth(&mut self, auth: &Value, name: &str, output: &mut Map<String, Value>) {
+        let Some(auth) = self.auth(auth, name) else {
+            if auth["type"].as_str() == Some("noauth") {
+                self.warnings.push(format!(
+                    "'{}' disables authentication, but the collection authentication will be sent",
+                    name
+                ));
+            }
+            return;
+        };
+
+        let header = match auth.auth_type.as_str() {
+            "bearer" => auth
+                .token
+                .map(|token| ("Authorization".to_string(), format!("Bearer {}", token))),
+            "api_key" if auth.key_in.as_deref() == Some("query") => {
+                if let (Some(key), Some(value), Some(Value::String(url))) =
+                    (auth.key_name, auth.key_value, output.get_mut("url"))
+                {
+                    let separator = if url.contains('?') { '&' } else { '?' };
+                    url.push_str(&format!("{}{}={}", separator, key, value));
+                }
+                None
+            }
+            "api_key" => auth.key_name.zip(auth.key_value),
+            "basic" => {
+                let username = auth.username.unwrap_or_default();
+                let password = auth.password.unwrap_or_default();
+                if username.contains("{{") || password.contains("{{") {
+                    self.warnings.push(format!(
+                        "'{}' uses basic authentication with variables, which was not imported",
+                        name
+                    ));
+                    None
+                } else {
+                    let credentials = base64::engine::general_purpose::STANDARD
+                        .encode(format!("{}:{}", username, password));
+                    Some((
+                        "Authorization".to_string(),
+                        format!("Basic {}", credentials),
+                    ))
+                }
+            }
+            _ => {
+                self.warnings.push(format!(
+                    "'{}' overrides the collection authentication with '{}', which was not imported",
+                    name, auth.auth_type
+                ));
+                None
+            }
+        };
+
+        if let Some((key, value)) = header {
+            let headers = output
+                .entry("headers")
+                .or_insert_with(|| Value::Object(Map::new()));
+            headers[key] = json!(value);
+        }
+    }
+
+    /// Map test scripts to checks and captures, and report what is left over
+    fn events(&mut self, item: &Value, name: &str, mut output: Option<&mut Map<String, Value>>) {
+        for event in item["event"].as_array().into_iter().flatten() {
+            let lines: Vec<&str> = match &event["script"]["exec"] {
+                Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+                Value::String(script) => script.lines().collect(),
+                _ => Vec::new(),
+            };
+            if lines.iter().all(|line| line.trim().is_empty()) {
+                continue;
+            }
+
+            let listen = event["listen"].as_str().unwrap_or("");
+            let Some(output) = output.as_deref_mut().filter(|_| listen == "test") else {
+                self.warnings.push(format!(
+                    "The {} script of '{}' was not imported",
+                    listen, name
+                ));
+                continue;
+            };
+
+            let mut skipped = 0;
+            for line in lines {
+                if !map_test_line(line, output) && !BOILERPLATE.is_match(line) {
+                    skipped += 1;
+                }
+            }
+            if skipped > 0 {
+                self.warnings.push(format!(
+                    "{} line(s) of the test script of '{}' were not imported",
+                    skipped, name
+                ));
+            }
+        }
+    }
+}
+
+/// Map one line of a Postman test script; returns false if nothing was recognised
+fn map_test_line(line: &str, output: &mut Map<String, Value>) -> bool {
+    let mut mapped = false;
+
+    if let Some(status) = STATUS_TEST
+        .captures(line)
+        .and_then(|c| c.iter().skip(1).flatten().next())
+    {
+        output.insert(
+            "expected_status".to_string(),
+            json!(status.as_str().parse::<u16>().unwrap_or(200)),
+        );
+        mapped = true;
+    }
+
+    for capture in CAPTURE_TEST.captures_iter(line) {
+        let captures = output
+            .entry("capture")
+            .or_insert_with(|| Value::Object(Map::new()));
+        captures[&capture[1]] = json!(format!("${}", &capture[2]));
+        mapped = true;
+    }
+
+    for capture in EQUALS_TEST.captures_iter(line) {
+        let literal = &capture[2];
+        let value = match literal.strip_prefix('\'') {
+            Some(single) => json!(single.trim_end_matches('\'')),
+            None => serde_json::from_str(literal).unwrap_or(Value::Null),
+        };
+        let assertions = output
+            .entry("assertions")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(assertions) = assertions {
+            assertions.push(json!({
+                "path": format!("${}", &capture[1]),
+                "operator": "equals",
+                "value": value
+            }));
+        }
+        mapped = true;
+    }
+
+    mapped
+}
+
+fn export_request(
+    request: &CollectionRequest,
+    default_headers: &HashMap<String, String>,
+    warnings: &mut Vec<String>,
+) -> Value {
+    let mut headers = default_headers.clone();
+    headers.extend(request.headers.clone().unwrap_or_default());
+    let mut headers: Vec<(String, String)> = headers.into_iter().collect();
+    headers.sort();
+    if let Some(content_type) = &request.content_type {
+        headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+        headers.push(("Content-Type".to_string(), content_type.clone()));
+    }
+
+    let mut output = json!({
+        "name": request.name,
+        "request": {
+            "method": request.method,
+            "header": headers
+                .iter()
+                .map(|(key, value)| json!({"key": key, "value": value}))
+                .collect::<Vec<_>>(),
+            "url": {"raw": request.url}
+        }
+    });
+    if let Some(description) = &request.description {
+        output["request"]["description"] = json!(description);
+    }
+    if let Some(body) = &request.body {
+        output["request"]["body"] = export_body(request.body_type, body);
+    }
+
+    let mut script = Vec::new();
+    if let Some(status) = request.expected_status {
+        script.push(format!(
+            "pm.test(\"Status code is {}\", function () {{ pm.response.to.have.status({}); }});",
+            status, status
+        ));
+    }
+    for assertion in request.assertions.iter().flatten() {
+        match (js_path(&assertion.path), &assertion.operator, &assertion.value) {
+            (Some(path), AssertionOperator::Equals, Some(value)) => script.push(format!(
+                "pm.test(\"{} equals {}\", function () {{ pm.expect(pm.response.json(){}).to.eql({}); }});",
+                assertion.path.replace('"', "\\\""),
+                value.to_string().replace('"', "\\\""),
+                path,
+                value
+            )),
+            _ => warnings.push(format!(
+                "Assertion '{} {}' of '{}' was not exported",
+                assertion.path,
+                assertion.operator.as_str(),
+                request.name
+            )),
+        }
+    }
+    let mut captures: Vec<_> = request.capture.iter().flatten().collect();
+    captures.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, capture) in captures {
+        let source = capture.source();
+        match js_path(source.strip_prefix("jsonpath:").unwrap_or(source)) {
+            Some(path) => script.push(format!(
+                "pm.collectionVariables.set(\"{}\", pm.response.json(){});",
+                name, path
+            )),
+            None => warnings.push(format!(
+                "Capture '{}' of '{}' was not exported",
+                name, request.name
+            )),
+        }
+    }
+    if !script.is_empty() {
+        output["event"] = json!([{
+            "listen": "test",
+            "script": {"type": "text/javascript", "exec": script}
+        }]);
+    }
+
+    if request.pre_request.is_some() || request.post_response.is_some() {
+        warnings.push(format!("Scripts of '{}' were not exported", request.name));
+    }
+    if request.run_if.is_some()
+        || request.skip_if.is_some()
+        || request.repeat_until.is_some()
+        || request.for_each.is_some()
+    {
+        warnings.push(format!(
+            "Conditions and loops of '{}' were not exported",
+            request.name
+        ));
+    }
+    if request.cookie_assertions.is_some() || request.expected_body.is_some() {
+        warnings.push(format!(
+            "Body and cookie expectations of '{}' were not exported",
+            request.name
+        ));
+    }
+    output
+}
+
+fn export_body(body_type: BodyType, body: &Value) -> Value {
+    match body_type {
+        BodyType::Json => json!({
+            "mode": "raw",
+            "raw": serde_json::to_string_pretty(body).unwrap_or_default(),
+            "options": {"raw": {"language": "json"}}
+        }),
+        BodyType::Raw => json!({
+            "mode": "raw",
+            "raw": body.as_str().map(str::to_string).unwrap_or_else(|| body.to_string())
+        }),
+        BodyType::Form => json!({
+            "mode": "urlencoded",
+            "urlencoded": body
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(key, value)| json!({
+                    "key": key,
+                    "value": value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())
+                }))
+                .collect::<Vec<_>>()
+        }),
+        BodyType::Multipart => {
+            let parts: Vec<MultipartPart> =
+                serde_json::from_value(body.clone()).unwrap_or_default();
+            json!({
+                "mode": "formdata",
+                "formdata": parts
+                    .iter()
+                    .map(|part| match &part.file {
+                        Some(file) => json!({"key": part.name, "type": "file", "src": file}),
+                        None => json!({"key": part.name, "type": "text", "value": part.value}),
+                    })
+                    .collect::<Vec<_>>()
+            })
+        }
+        BodyType::BinaryFile => json!({"mode": "file", "file": {"src": body}}),
+    }
+}
+
+fn export_auth(auth: &CollectionAuth) -> Option<Value> {
+    let param = |key: &str, value: &Option<String>| json!({"key": key, "value": value.clone().unwrap_or_default(), "type": "string"});
+    match auth.auth_type.as_str() {
+        "basic" => Some(json!({
+            "type": "basic",
+            "basic": [param("username", &auth.username), param("password", &auth.password)]
+        })),
+        "bearer" => Some(json!({"type": "bearer", "bearer": [param("token", &auth.token)]})),
+        "api_key" => Some(json!({
+            "type": "apikey",
+            "apikey": [
+                param("key", &auth.key_name),
+                param("value", &auth.key_value),
+                param("in", &Some(auth.key_in.clone().unwrap_or_else(|| "header".to_string())))
+            ]
+        })),
+        "aws_sigv4" => Some(json!({
+            "type": "awsv4",
+            "awsv4": [
+                param("accessKey", &auth.access_key_id),
+                param("secretKey", &auth.secret_access_key),
+                param("sessionToken", &auth.session_token),
+                param("region", &auth.region),
+                param("service", &auth.service)
+            ]
+        })),
+        _ => None,
+    }
+}
+
+/// JavaScript property access for a simple JSONPath such as `$.data.items[0].id`
+fn js_path(path: &str) -> Option<&str> {
+    static SIMPLE: Lazy<Regex> = Lazy::new(|| Regex::new(&format!("^\\${}$", JS_PATH)).unwrap());
+    SIMPLE.is_match(path).then(|| &path[1..])
+}
+
+/// URL of a Postman request, with path variables filled in
+fn url(url: &Value) -> String {
+    let Some(parts) = url.as_object() else {
+        return url.as_str().unwrap_or("").to_string();
+    };
+
+    let mut raw = match parts.get("raw").and_then(Value::as_str) {
+        Some(raw) => raw.to_string(),
+        None => {
+            let join = |key: &str, separator: &str| match parts.get(key) {
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(separator),
+                Some(Value::String(item)) => item.clone(),
+                _ => String::new(),
+            };
+            let mut raw = join("host", ".");
+            if let Some(protocol) = parts.get("protocol").and_then(Value::as_str) {
+                raw = format!("{}://{}", protocol, raw);
+            }
+            if let Some(port) = parts.get("port").and_then(Value::as_str) {
+                raw = format!("{}:{}", raw, port);
+            }
+            let path = join("path", "/");
+            if !path.is_empty() {
+                raw = format!("{}/{}", raw, path);
+            }
+            let query: Vec<String> = enabled(parts.get("query"))
+                .map(|q| {
+                    format!(
+                        "{}={}",
+                        q["key"].as_str().unwrap_or(""),
+                        q["value"].as_str().unwrap_or("")
+                    )
+                })
+                .collect();
+            if !query.is_empty() {
+                raw = format!("{}?{}", raw, query.join("&"));
+            }
+            raw
+        }
+    };
+
+    for variable in parts
+        .get("variable")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let Some(key) = variable["key"].as_str() else {
+            continue;
+        };
+        let value = match variable["value"].as_str() {
+            Some(value) if !value.is_empty() => value.to_string(),
+            _ => format!("{{{{{}}}}}", key),
+        };
+        let pattern = Regex::new(&format!(r":{}\b", regex::escape(key))).unwrap();
+        raw = pattern
+            .replace_all(&raw, regex::NoExpand(&value))
+            .into_owned();
+    }
+    raw
+}
+
+fn description(value: &Value) -> Option<String> {
+    match &value["description"] {
+        Value::String(text) if !text.is_empty() => Some(text.clone()),
+        Value::Object(description) => description
+            .get("content")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Entries of a Postman key/value list that are not disabled
+fn enabled(list: Option<&Value>) -> impl Iterator<Item = &Value> {
+    list.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|entry| {
+            entry["disabled"].as_bool() != Some(true) && entry["enabled"].as_bool() != Some(false)
+        })
+}
+
+/// A Postman key/value list as a map of strings
+fn key_values(list: Option<&Value>) -> HashMap<String, String> {
+    enabled(list)
+        .filter_map(|entry| {
+            let key = entry["key"].as_str()?;
+            let value = match &entry["value"] {
+                Value::String(value) => value.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+fn postman_values(variables: &HashMap<String, String>, enabled: bool) -> Value {
+    let mut variables: Vec<_> = variables.iter().collect();
+    variables.sort();
+    Value::Array(
+        variables
+            .into_iter()
+            .map(|(key, value)| {
+                let mut entry = json!({"key": key, "value": value, "type": "default"});
+                if enabled {
+                    entry["enabled"] = json!(true);
+                }
+                entry
+            })
+            .collect(),
+    )
+}
@@ -1,5 +1,6 @@
 use crate::common::TestResult;
 use crate::error::{Error, Result};
+use crate::secrets;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
@@ -44,11 +45,13 @@ impl ReportGenerator {
     }
 
     pub fn generate(&self, results: &[TestResult]) -> Result<()> {
+        // Reports never contain secret values, whatever the format
+        let results = secrets::redact_results(results);
         match self.format {
-            ReportFormat::Json => self.generate_json(results),
-            ReportFormat::Xml => self.generate_xml(results),
-            ReportFormat::Html => self.generate_html(results),
-            ReportFormat::Csv => self.generate_csv(results),
+            ReportFormat::Json => self.generate_json(&results),
+            ReportFormat::Xml => self.generate_xml(&results),
+            ReportFormat::Html => self.generate_html(&results),
+            ReportFormat::Csv => self.generate_csv(&results),
         }
     }
 
@@ -98,7 +101,28 @@ impl ReportGenerator {
 
                 match result.status.as_str() {
                     "failed" => {
-                        xml.push_str("      <failure>\n");
+                        let failed_checks = failed_assertions(result);
+                        if failed_checks.is_empty() {
+                            xml.push_str("      <failure>\n");
+                        } else {
+                            xml.push_str(&format!(
+                                "      <failure message=\"{} of {} checks failed\">\n",
+                                failed_checks.len(),
+                                assertions_of(result).len()
+                            ));
+                            for check in &failed_checks {
+                                xml.push_str(&format!(
+                                    "        [{}] expected: {}, actual: {}{}\n",
+                                    escape_xml(check["name"].as_str().unwrap_or("")),
+                                    escape_xml(&check["expected"].to_string()),
+                                    escape_xml(&check["actual"].to_string()),
+                                    check["message"]
+                                        .as_str()
+                                        .map(|m| format!(" - {}", escape_xml(m)))
+                                        .unwrap_or_default()
+                                ));
+                            }
+                        }
                         if let Some(details) = &result.details {
                             xml.push_str(&format!("        {}\n", serde_json::to_string(details)?));
                         }
@@ -148,6 +172,12 @@ impl ReportGenerator {
         html.push_str(
             "    .details { margin-top: 10px; font-family: monospace; white-space: pre-wrap; }\n",
         );
+        html.push_str("    .assertions { border-collapse: collapse; margin-top: 10px; }\n");
+        html.push_str(
+            "    .assertions th, .assertions td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n",
+        );
+        html.push_str("    .check-passed { color: #3c763d; }\n");
+        html.push_str("    .check-failed { color: #a94442; font-weight: bold; }\n");
         html.push_str("  </style>\n");
         html.push_str("</head>\n<body>\n");
         html.push_str("  <h1>QitOps Test Report</h1>\n");
@@ -185,6 +215,26 @@ impl ReportGenerator {
             html.push_str(&format!("    <p>Duration: {:.2}s</p>\n", result.duration));
             html.push_str(&format!("    <p>Timestamp: {}</p>\n", result.timestamp));
 
+            let checks = assertions_of(result);
+            if !checks.is_empty() {
+                html.push_str("    <table class=\"assertions\">\n");
+                html.push_str(
+                    "      <tr><th>Check</th><th>Expected</th><th>Actual</th><th>Result</th></tr>\n",
+                );
+                for check in checks {
+                    let passed = check["passed"].as_bool().unwrap_or(false);
+                    html.push_str(&format!(
+                        "      <tr><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>\n",
+                        escape_xml(check["name"].as_str().unwrap_or("")),
+                        escape_xml(&check["expected"].to_string()),
+                        escape_xml(&check["actual"].to_string()),
+                        if passed { "check-passed" } else { "check-failed" },
+                        if passed { "pass" } else { "fail" }
+                    ));
+                }
+                html.push_str("    </table>\n");
+            }
+
             if let Some(details) = &result.details {
                 html.push_str("    <div class=\"details\">\n");
                 html.push_str(&format!(
@@ -221,3 +271,30 @@ impl ReportGenerator {
         Ok(())
     }
 }
+
+/// Get the structured checks recorded in a test result
+fn assertions_of(result: &TestResult) -> &[serde_json::Value] {
+    result
+        .details
+        .as_ref()
+        .and_then(|d| d.get("assertions"))
+        .and_then(|a| a.as_array())
+        .map(|a| a.as_slice())
+        .unwrap_or(&[])
+}
+
+/// Get the checks of a test result that did not pass
+fn failed_assertions(result: &TestResult) -> Vec<&serde_json::Value> {
+    assertions_of(result)
+        .iter()
+        .filter(|check| !check["passed"].as_bool().unwrap_or(false))
+        .collect()
+}
+
+/// Escape text for inclusion in XML and HTML output
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
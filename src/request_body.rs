@@ -0,0 +1,195 @@
+use crate::error::{Error, Result};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::multipart::{Form, Part};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+/// How the request body is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyType {
+    /// JSON document (default)
+    #[default]
+    Json,
+    /// application/x-www-form-urlencoded fields from a flat object
+    Form,
+    /// multipart/form-data from a list of parts
+    Multipart,
+    /// Raw string sent with an explicit content type
+    Raw,
+    /// Contents of a file sent as the body
+    BinaryFile,
+}
+
+/// A single part of a multipart body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultipartPart {
+    /// Form field name
+    pub name: String,
+    /// Text value of the part
+    pub value: Option<String>,
+    /// Path of a file to upload, relative to the configuration file
+    pub file: Option<String>,
+    /// File name sent for file parts (defaults to the name of the file)
+    pub filename: Option<String>,
+    /// Content type of the part
+    pub content_type: Option<String>,
+}
+
+/// Attach a body to a request according to its body type
+pub fn apply_body(
+    request: RequestBuilder,
+    body_type: BodyType,
+    body: &Value,
+    content_type: Option<&str>,
+) -> Result<RequestBuilder> {
+    match body_type {
+        BodyType::Json => Ok(request.json(body)),
+        BodyType::Form => {
+            let fields = body.as_object().ok_or_else(|| {
+                Error::ValidationError("Form body must be an object of fields".to_string())
+            })?;
+            let fields: Vec<(String, String)> = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value_to_field(value)))
+                .collect();
+            Ok(request.form(&fields))
+        }
+        BodyType::Multipart => Ok(request.multipart(multipart_form(body)?)),
+        BodyType::Raw => {
+            let raw = match body {
+                Value::String(s) => s.clone(),
+                other => serde_json::to_string(other)?,
+            };
+            with_content_type(request.body(raw), content_type, "text/plain")
+        }
+        BodyType::BinaryFile => {
+            let path = body.as_str().ok_or_else(|| {
+                Error::ValidationError("Binary file body must be a file path".to_string())
+            })?;
+            let bytes = std::fs::read(path).map_err(|e| {
+                Error::ValidationError(format!("Failed to read body file '{}': {}", path, e))
+            })?;
+            with_content_type(
+                request.body(bytes),
+                content_type,
+                "application/octet-stream",
+            )
+        }
+    }
+}
+
+/// Set the Content-Type of a request.
+///
+/// An explicit content type always wins; otherwise a Content-Type already set
+/// through the request headers is kept and the default is only used as a fallback.
+fn with_content_type(
+    request: RequestBuilder,
+    content_type: Option<&str>,
+    default: &str,
+) -> Result<RequestBuilder> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+    let headers = request.headers_mut();
+    match content_type {
+        Some(content_type) => {
+            headers.insert(CONTENT_TYPE, header_value(content_type)?);
+        }
+        None if !headers.contains_key(CONTENT_TYPE) => {
+            headers.insert(CONTENT_TYPE, header_value(default)?);
+        }
+        None => {}
+    }
+    Ok(RequestBuilder::from_parts(client, request))
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value)
+        .map_err(|e| Error::ValidationError(format!("Invalid content type '{}': {}", value, e)))
+}
+
+/// Rewrite file paths in a body so they are relative to the configuration directory
+pub fn resolve_paths(body_type: BodyType, body: &mut Value, base_dir: &Path) {
+    let resolve = |path: &mut String| {
+        if Path::new(path.as_str()).is_relative() {
+            *path = base_dir.join(path.as_str()).to_string_lossy().to_string();
+        }
+    };
+
+    match body_type {
+        BodyType::BinaryFile => {
+            if let Value::String(path) = body {
+                resolve(path);
+            }
+        }
+        BodyType::Multipart => {
+            if let Some(parts) = body.as_array_mut() {
+                for part in parts {
+                    if let Some(Value::String(path)) = part.get_mut("file") {
+                        resolve(path);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Build a multipart form from a list of parts
+fn multipart_form(body: &Value) -> Result<Form> {
+    let parts: Vec<MultipartPart> = serde_json::from_value(body.clone()).map_err(|e| {
+        Error::ValidationError(format!("Multipart body must be a list of parts: {}", e))
+    })?;
+
+    let mut form = Form::new();
+    for part in parts {
+        let mut form_part = match (&part.file, &part.value) {
+            (Some(path), _) => {
+                let bytes = std::fs::read(path).map_err(|e| {
+                    Error::ValidationError(format!(
+                        "Failed to read multipart file '{}': {}",
+                        path, e
+                    ))
+                })?;
+                let filename = part.filename.clone().unwrap_or_else(|| {
+                    Path::new(path)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| path.clone())
+                });
+                Part::bytes(bytes).file_name(filename)
+            }
+            (None, Some(value)) => Part::text(value.clone()),
+            (None, None) => {
+                return Err(Error::ValidationError(format!(
+                    "Multipart part '{}' needs a value or a file",
+                    part.name
+                )))
+            }
+        };
+
+        if let Some(content_type) = &part.content_type {
+            form_part = form_part.mime_str(content_type).map_err(|e| {
+                Error::ValidationError(format!(
+                    "Invalid content type '{}' for part '{}': {}",
+                    content_type, part.name, e
+                ))
+            })?;
+        }
+
+        form = form.part(part.name, form_part);
+    }
+
+    Ok(form)
+}
+
+/// Convert a JSON value to a form field value
+fn value_to_field(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,274 @@
+use crate::error::Result;
+use crate::timing::PhaseTimings;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Type of a response body
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseType {
+    /// JSON document, assertions use JSONPath
+    Json,
+    /// XML document, assertions use XPath
+    Xml,
+    /// Plain text, HTML or any other textual content
+    Text,
+    /// Binary content, checked by size and digest
+    Binary,
+}
+
+impl ResponseType {
+    /// Determine the response type from a Content-Type header
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_lowercase();
+
+        if mime.is_empty() {
+            None
+        } else if mime.ends_with("json") {
+            Some(ResponseType::Json)
+        } else if mime.ends_with("xml") {
+            Some(ResponseType::Xml)
+        } else if mime.starts_with("text/")
+            || mime.contains("javascript")
+            || mime.contains("x-www-form-urlencoded")
+            || mime.contains("yaml")
+        {
+            Some(ResponseType::Text)
+        } else {
+            Some(ResponseType::Binary)
+        }
+    }
+
+    /// Guess the response type from the content when no Content-Type is available
+    fn sniff(bytes: &[u8]) -> Self {
+        if serde_json::from_slice::<Value>(bytes).is_ok() {
+            ResponseType::Json
+        } else if std::str::from_utf8(bytes).is_ok() {
+            ResponseType::Text
+        } else {
+            ResponseType::Binary
+        }
+    }
+
+    /// Name of the response type as written in configuration files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseType::Json => "json",
+            ResponseType::Xml => "xml",
+            ResponseType::Text => "text",
+            ResponseType::Binary => "binary",
+        }
+    }
+}
+
+/// A buffered response body together with its detected type
+#[derive(Debug, Clone)]
+pub struct ResponseBody {
+    /// Type used to interpret the body
+    pub response_type: ResponseType,
+    /// Raw body bytes
+    pub bytes: Vec<u8>,
+    /// Parsed JSON document (only for JSON bodies that parse)
+    json: Option<Value>,
+}
+
+impl ResponseBody {
+    /// Create a response body, preferring an explicit type over the Content-Type header
+    pub fn new(
+        bytes: Vec<u8>,
+        content_type: Option<&str>,
+        override_type: Option<ResponseType>,
+    ) -> Self {
+        let response_type = override_type
+            .or_else(|| content_type.and_then(ResponseType::from_content_type))
+            .unwrap_or_else(|| ResponseType::sniff(&bytes));
+        let json = match response_type {
+            ResponseType::Json => serde_json::from_slice(&bytes).ok(),
+            _ => None,
+        };
+
+        Self {
+            response_type,
+            bytes,
+            json,
+        }
+    }
+
+    /// Wrap an already parsed JSON document
+    pub fn from_json(value: &Value) -> Self {
+        Self {
+            response_type: ResponseType::Json,
+            bytes: serde_json::to_vec(value).unwrap_or_default(),
+            json: Some(value.clone()),
+        }
+    }
+
+    /// Treat the body as JSON if it parses as JSON, whatever its Content-Type
+    pub fn or_json(mut self) -> Self {
+        if self.json.is_none() {
+            if let Ok(json) = serde_json::from_slice(&self.bytes) {
+                self.response_type = ResponseType::Json;
+                self.json = Some(json);
+            }
+        }
+        self
+    }
+
+    /// Parsed JSON document, if the body is valid JSON
+    pub fn json(&self) -> Option<&Value> {
+        self.json.as_ref()
+    }
+
+    /// Body as text, replacing invalid UTF-8 sequences
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+
+    /// Size of the body in bytes
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Hex encoded SHA-256 digest of the body
+    pub fn sha256(&self) -> String {
+        hex::encode(Sha256::digest(&self.bytes))
+    }
+
+    /// Representation of the body for test result details
+    pub fn to_details(&self) -> Value {
+        match (self.response_type, &self.json) {
+            (ResponseType::Json, Some(json)) => json.clone(),
+            (ResponseType::Binary, _) => json!({
+                "size": self.size(),
+                "sha256": self.sha256()
+            }),
+            _ => Value::String(self.text()),
+        }
+    }
+
+    /// Select values from the body with a path expression.
+    ///
+    /// JSON bodies use JSONPath, XML bodies use XPath and text bodies always
+    /// select the whole text. Binary bodies have no addressable values.
+    pub fn select(
+        &self,
+        path: &str,
+        namespaces: Option<&HashMap<String, String>>,
+    ) -> std::result::Result<Vec<Value>, String> {
+        match self.response_type {
+            ResponseType::Json => {
+                let json = self
+                    .json
+                    .as_ref()
+                    .ok_or_else(|| "Response is not valid JSON".to_string())?;
+                let path = if path.is_empty() { "$" } else { path };
+                jsonpath_lib::select(json, path)
+                    .map(|matches| matches.into_iter().cloned().collect())
+                    .map_err(|e| format!("Invalid JSONPath '{}': {}", path, e))
+            }
+            ResponseType::Xml => {
+                let path = if path.is_empty() { "/" } else { path };
+                select_xpath(&self.text(), path, namespaces)
+            }
+            ResponseType::Text => Ok(vec![Value::String(self.text())]),
+            ResponseType::Binary => {
+                Err("Binary responses only support the size and sha256 operators".to_string())
+            }
+        }
+    }
+}
+
+/// A response that has been read completely, with the timings of its request
+#[derive(Debug, Clone)]
+pub struct BufferedResponse {
+    /// HTTP status
+    pub status: StatusCode,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body
+    pub body: ResponseBody,
+    /// Timing breakdown of the request
+    pub timings: PhaseTimings,
+}
+
+impl BufferedResponse {
+    /// Read the whole response body, recording the download time
+    pub async fn read(
+        response: reqwest::Response,
+        mut timings: PhaseTimings,
+        response_type: Option<ResponseType>,
+    ) -> Result<Self> {
+        let status = response.status();
+        let headers = response.headers().clone();
+
+        let download_start = Instant::now();
+        let bytes = response.bytes().await?.to_vec();
+        timings.record_download(download_start.elapsed());
+
+        let content_type = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        let body = ResponseBody::new(bytes, content_type, response_type);
+
+        Ok(Self {
+            status,
+            headers,
+            body,
+            timings,
+        })
+    }
+
+    /// Response headers as a map of strings
+    pub fn header_map(&self) -> HashMap<String, String> {
+        self.headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+            .collect()
+    }
+}
+
+/// Evaluate an XPath expression against an XML document
+fn select_xpath(
+    xml: &str,
+    path: &str,
+    namespaces: Option<&HashMap<String, String>>,
+) -> std::result::Result<Vec<Value>, String> {
+    let package = sxd_document::parser::parse(xml)
+        .map_err(|e| format!("Response is not valid XML: {:?}", e))?;
+    let document = package.as_document();
+
+    let xpath = sxd_xpath::Factory::new()
+        .build(path)
+        .map_err(|e| format!("Invalid XPath '{}': {}", path, e))?
+        .ok_or_else(|| format!("Invalid XPath '{}': empty expression", path))?;
+
+    let mut context = sxd_xpath::Context::new();
+    if let Some(namespaces) = namespaces {
+        for (prefix, uri) in namespaces {
+            context.set_namespace(prefix, uri);
+        }
+    }
+
+    let value = xpath
+        .evaluate(&context, document.root())
+        .map_err(|e| format!("XPath evaluation failed for '{}': {}", path, e))?;
+
+    Ok(match value {
+        sxd_xpath::Value::Boolean(b) => vec![Value::Bool(b)],
+        sxd_xpath::Value::Number(n) => vec![json!(n)],
+        sxd_xpath::Value::String(s) => vec![Value::String(s)],
+        sxd_xpath::Value::Nodeset(nodes) => nodes
+            .document_order()
+            .iter()
+            .map(|node| Value::String(node.string_value()))
+            .collect(),
+    })
+}
@@ -0,0 +1,136 @@
+use crate::assertions::AssertionResult;
+use crate::error::{Error, Result};
+use jsonschema::{JSONSchema, SchemaResolver, SchemaResolverError};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Response JSON Schemas compiled once and shared for the lifetime of the cache.
+///
+/// Schemas can reference the shared `schemas` of a collection as
+/// `#/schemas/<name>`, and schema files by paths relative to the collection.
+pub struct SchemaCache {
+    base_url: Option<Url>,
+    shared: Option<Value>,
+    resolver: FileResolver,
+    compiled: Mutex<HashMap<String, Arc<JSONSchema>>>,
+}
+
+impl SchemaCache {
+    /// A cache resolving `$ref` file paths relative to `base_dir`
+    pub fn new(shared: Option<&HashMap<String, Value>>, base_dir: &Path) -> Self {
+        let base_url = std::path::absolute(base_dir)
+            .ok()
+            .and_then(|dir| Url::from_directory_path(dir).ok());
+        Self {
+            base_url,
+            shared: shared.map(|schemas| json!(schemas)),
+            resolver: FileResolver::default(),
+            compiled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The compiled form of a schema, compiling it on first use
+    pub fn compile(&self, schema: &Value) -> Result<Arc<JSONSchema>> {
+        let key = schema.to_string();
+        if let Some(compiled) = self.lock().get(&key) {
+            return Ok(compiled.clone());
+        }
+
+        // The shared schemas are part of every root schema, so `#/schemas/<name>` resolves
+        let mut root = schema.clone();
+        if let Value::Object(map) = &mut root {
+            if let Some(base_url) = &self.base_url {
+                map.entry("$id").or_insert_with(|| json!(base_url.as_str()));
+            }
+            if let Some(shared) = &self.shared {
+                map.entry("schemas").or_insert_with(|| shared.clone());
+            }
+        }
+        let compiled = JSONSchema::options()
+            .with_resolver(self.resolver.clone())
+            .compile(&root)
+            .map(Arc::new)
+            .map_err(|e| Error::ValidationError(format!("Invalid JSON Schema: {}", e)))?;
+        self.lock().insert(key, compiled.clone());
+        Ok(compiled)
+    }
+
+    /// Check a response body against a schema
+    pub fn check(&self, schema: &Value, body: &Value) -> Result<AssertionResult> {
+        let compiled = self.compile(schema)?;
+        Ok(check(&compiled, body))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<JSONSchema>>> {
+        self.compiled.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The `json_schema` check of a response body
+pub fn check(schema: &JSONSchema, body: &Value) -> AssertionResult {
+    let error_messages: Vec<String> = match schema.validate(body) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.map(|e| format!("{}", e)).collect(),
+    };
+    AssertionResult::check(
+        "json_schema",
+        json!("valid"),
+        json!(error_messages),
+        error_messages.is_empty(),
+    )
+    .with_message(format!(
+        "JSON Schema validation failed:\n{}",
+        error_messages.join("\n")
+    ))
+}
+
+/// Loads `$ref` schema files, each read once for all clones of the resolver
+#[derive(Clone, Default)]
+struct FileResolver {
+    documents: Arc<Mutex<HashMap<Url, Arc<Value>>>>,
+}
+
+impl FileResolver {
+    fn load(&self, url: &Url) -> Result<Arc<Value>> {
+        let mut documents = self.documents.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(document) = documents.get(url) {
+            return Ok(document.clone());
+        }
+        let path = url
+            .to_file_path()
+            .map_err(|_| Error::ValidationError(format!("Invalid schema file URL: {}", url)))?;
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            Error::ValidationError(format!("Cannot read schema {}: {}", path.display(), e))
+        })?;
+        let document: Value = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| {
+                Error::ValidationError(format!("Invalid YAML in {}: {}", path.display(), e))
+            })?,
+            _ => serde_json::from_str(&content)?,
+        };
+        let document = Arc::new(document);
+        documents.insert(url.clone(), document.clone());
+        Ok(document)
+    }
+}
+
+impl SchemaResolver for FileResolver {
+    fn resolve(
+        &self,
+        _root_schema: &Value,
+        url: &Url,
+        original_reference: &str,
+    ) -> std::result::Result<Arc<Value>, SchemaResolverError> {
+        if url.scheme() != "file" {
+            return Err(Error::ValidationError(format!(
+                "Unsupported schema reference '{}': only files relative to the collection are supported",
+                original_reference
+            ))
+            .into());
+        }
+        Ok(self.load(url)?)
+    }
+}
@@ -0,0 +1,261 @@
+use crate::assertions::{self, Assertion, AssertionResult};
+use crate::error::{Error, Result};
+use crate::response::BufferedResponse;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_initial_delay")]
+    pub initial_delay_ms: u64,
+    #[serde(default = "default_max_delay")]
+    pub max_delay_ms: u64,
+    #[serde(default = "default_retry_status_codes")]
+    pub retry_status_codes: Vec<u16>,
+    #[serde(default)]
+    pub retry_on_timeout: bool,
+    #[serde(default)]
+    pub retry_on_connection_error: bool,
+    /// Wait for the delay given by a Retry-After header on retryable statuses
+    #[serde(default = "default_respect_retry_after")]
+    pub respect_retry_after: bool,
+    /// Upper bound for a Retry-After delay
+    #[serde(default = "default_max_retry_after")]
+    pub max_retry_after_ms: u64,
+    /// Keep retrying until every assertion passes (for polling asynchronous jobs)
+    pub retry_until: Option<Vec<Assertion>>,
+}
+
+impl Default for RetryConfig {
+    /// The policy of an empty `retry` block
+    fn default() -> Self {
+        Self {
+            max_retries: default_max_retries(),
+            initial_delay_ms: default_initial_delay(),
+            max_delay_ms: default_max_delay(),
+            retry_status_codes: default_retry_status_codes(),
+            retry_on_timeout: false,
+            retry_on_connection_error: false,
+            respect_retry_after: default_respect_retry_after(),
+            max_retry_after_ms: default_max_retry_after(),
+            retry_until: None,
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_initial_delay() -> u64 {
+    100
+}
+
+fn default_max_delay() -> u64 {
+    5000
+}
+
+fn default_retry_status_codes() -> Vec<u16> {
+    vec![408, 429, 500, 502, 503, 504]
+}
+
+fn default_respect_retry_after() -> bool {
+    true
+}
+
+fn default_max_retry_after() -> u64 {
+    60000
+}
+
+/// Record of a single request attempt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttemptRecord {
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// HTTP status, if a response was received
+    pub status: Option<u16>,
+    /// Time spent on the attempt in milliseconds
+    pub latency_ms: f64,
+    /// Delay before the next attempt in milliseconds (0 for the last attempt)
+    pub delay_ms: u64,
+    /// Why the attempt was retried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_reason: Option<String>,
+    /// Error of a failed attempt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of a request executed with retries
+#[derive(Debug)]
+pub struct RetryOutcome {
+    /// Last response, or the error that ended the attempts
+    pub result: Result<BufferedResponse>,
+    /// History of every attempt
+    pub attempts: Vec<AttemptRecord>,
+    /// Check for the `retry_until` condition, if one is configured
+    pub retry_until: Option<AssertionResult>,
+}
+
+impl RetryConfig {
+    /// The default policy with the given number of retries
+    pub fn with_retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff delay before the given retry (1-based), with jitter
+    fn backoff_delay(&self, retry: u32) -> Duration {
+        let exponential = self
+            .initial_delay_ms
+            .saturating_mul(2u64.saturating_pow(retry.saturating_sub(1)));
+        let capped = exponential.min(self.max_delay_ms);
+        // Jitter keeps clients from retrying in lockstep
+        let jittered = capped / 2 + rand::thread_rng().gen_range(0..=capped - capped / 2);
+        Duration::from_millis(jittered)
+    }
+
+    /// Delay requested by a Retry-After header, capped by `max_retry_after_ms`
+    fn retry_after_delay(&self, response: &BufferedResponse) -> Option<Duration> {
+        if !self.respect_retry_after {
+            return None;
+        }
+        let value = response.headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+        let millis = match value.parse::<u64>() {
+            Ok(seconds) => seconds.saturating_mul(1000),
+            Err(_) => {
+                let date = DateTime::parse_from_rfc2822(value).ok()?;
+                (date.with_timezone(&Utc) - Utc::now())
+                    .num_milliseconds()
+                    .max(0) as u64
+            }
+        };
+        Some(Duration::from_millis(millis.min(self.max_retry_after_ms)))
+    }
+
+    /// Whether an error should be retried
+    fn should_retry_error(&self, error: &Error) -> bool {
+        match error {
+            Error::RequestError(e) => {
+                (e.is_timeout() && self.retry_on_timeout)
+                    || (e.is_connect() && self.retry_on_connection_error)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Execute a request with the retry policy.
+///
+/// `send` performs one attempt. Retryable statuses, timeouts and connection
+/// errors are retried with exponential backoff, honouring Retry-After, and a
+/// `retry_until` condition keeps polling until it holds or attempts run out.
+pub async fn execute_with_retry<F, Fut>(config: &RetryConfig, mut send: F) -> RetryOutcome
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<BufferedResponse>>,
+{
+    let max_attempts = config.max_retries + 1;
+    let mut attempts = Vec::new();
+    let mut attempt = 1;
+
+    loop {
+        info!("Attempt {} of {}", attempt, max_attempts);
+        let start = Instant::now();
+        let result = send().await;
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let is_last = attempt >= max_attempts;
+
+        // Decide whether to retry, and after how long
+        let (retry_reason, delay, until_checks) = match &result {
+            Ok(response) => {
+                let status = response.status.as_u16();
+                if config.retry_status_codes.contains(&status) {
+                    let delay = config
+                        .retry_after_delay(response)
+                        .unwrap_or_else(|| config.backoff_delay(attempt));
+                    (Some(format!("status {}", status)), delay, None)
+                } else if let Some(conditions) = &config.retry_until {
+                    let checks = assertions::evaluate_all_body(conditions, &response.body);
+                    let reason = checks
+                        .iter()
+                        .any(|c| !c.passed)
+                        .then(|| "retry_until condition not met".to_string());
+                    (reason, config.backoff_delay(attempt), Some(checks))
+                } else {
+                    (None, Duration::ZERO, None)
+                }
+            }
+            Err(e) if config.should_retry_error(e) => {
+                (Some(e.to_string()), config.backoff_delay(attempt), None)
+            }
+            Err(_) => (None, Duration::ZERO, None),
+        };
+
+        let retrying = retry_reason.is_some() && !is_last;
+        attempts.push(AttemptRecord {
+            attempt,
+            status: result.as_ref().ok().map(|r| r.status.as_u16()),
+            latency_ms,
+            delay_ms: if retrying {
+                delay.as_millis() as u64
+            } else {
+                0
+            },
+            retry_reason: if retrying { retry_reason.clone() } else { None },
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+
+        if !retrying {
+            if let Some(reason) = &retry_reason {
+                warn!("Giving up after {} attempts: {}", attempt, reason);
+            }
+            let retry_until = config
+                .retry_until
+                .as_ref()
+                .map(|_| until_check(until_checks.as_deref(), attempt));
+            return RetryOutcome {
+                result,
+                attempts,
+                retry_until,
+            };
+        }
+
+        warn!(
+            "Attempt {} failed ({}), retrying in {}ms",
+            attempt,
+            retry_reason.unwrap_or_default(),
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Summarize the final evaluation of the `retry_until` condition as a check
+fn until_check(checks: Option<&[AssertionResult]>, attempts: u32) -> AssertionResult {
+    let failures = checks.map(assertions::failure_messages).unwrap_or_default();
+    let passed = checks.is_some() && failures.is_empty();
+    let message = if checks.is_none() {
+        "retry_until condition was not evaluated because no usable response was received"
+            .to_string()
+    } else {
+        format!(
+            "retry_until condition not met after {} attempts: {}",
+            attempts,
+            failures.join("; ")
+        )
+    };
+
+    AssertionResult::check("retry_until", json!(true), json!(passed), passed).with_message(message)
+}
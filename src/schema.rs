@@ -4,38 +4,212 @@ use once_cell::sync::Lazy;
 use serde_json::Value;
 use std::collections::HashMap;
 
+/// Schema for a list of JSONPath response assertions
+fn assertions_schema() -> Value {
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "required": ["operator"],
+            "properties": {
+                "path": {"type": "string"},
+                "operator": {"type": "string", "enum": ["exists", "not_exists", "equals", "not_equals", "contains", "matches", "gt", "gte", "lt", "lte", "length", "type_of", "one_of", "size", "sha256"]},
+                "value": {},
+                "namespaces": {"type": "object", "additionalProperties": {"type": "string"}}
+            }
+        }
+    })
+}
+
+/// Schema for the TLS settings shared by all HTTP runners
+fn tls_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "ca_cert": {"type": "string"},
+            "client_cert": {"type": "string"},
+            "client_key": {"type": "string"},
+            "insecure_skip_verify": {"type": "boolean"}
+        }
+    })
+}
+
+/// Schema for the proxy settings shared by all HTTP runners
+fn proxy_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "required": ["url"],
+        "properties": {
+            "url": {"type": "string"},
+            "no_proxy": {"type": "string"},
+            "username": {"type": "string"},
+            "password": {"type": "string"}
+        }
+    })
+}
+
+/// Schema for snapshot (golden response) settings
+fn snapshot_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "name": {"type": "string"},
+            "dir": {"type": "string"},
+            "headers": {"type": "array", "items": {"type": "string"}},
+            "ignore_paths": {"type": "array", "items": {"type": "string"}}
+        }
+    })
+}
+
+/// Schema for request authentication
+fn auth_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "type": {"type": "string", "enum": ["basic", "bearer", "api_key", "oauth2", "hmac", "aws_sigv4"]},
+            "username": {"type": "string"},
+            "password": {"type": "string"},
+            "token": {"type": "string"},
+            "key_name": {"type": "string"},
+            "key_value": {"type": "string"},
+            "key_in": {"type": "string", "enum": ["header", "query"]},
+            "grant_type": {
+                "type": "string",
+                "enum": ["client_credentials", "password", "refresh_token", "authorization_code"]
+            },
+            "token_url": {"type": "string"},
+            "client_id": {"type": "string"},
+            "client_secret": {"type": "string"},
+            "client_auth": {"type": "string", "enum": ["body", "basic"]},
+            "scope": {"type": "string"},
+            "audience": {"type": "string"},
+            "refresh_token": {"type": "string"},
+            "code": {"type": "string"},
+            "code_verifier": {"type": "string"},
+            "redirect_uri": {"type": "string"},
+            "secret": {"type": "string"},
+            "canonical_template": {"type": "string"},
+            "signature_header": {"type": "string"},
+            "signature_prefix": {"type": "string"},
+            "signature_encoding": {"type": "string", "enum": ["hex", "base64"]},
+            "timestamp_header": {"type": "string"},
+            "access_key_id": {"type": "string"},
+            "secret_access_key": {"type": "string"},
+            "session_token": {"type": "string"},
+            "region": {"type": "string"},
+            "service": {"type": "string"}
+        },
+        "required": ["type"],
+        "allOf": [
+            {
+                "if": {"properties": {"type": {"const": "oauth2"}}},
+                "then": {"required": ["token_url", "client_id"]}
+            },
+            {
+                "if": {"properties": {"type": {"const": "hmac"}}},
+                "then": {"required": ["secret"]}
+            },
+            {
+                "if": {"properties": {"type": {"const": "aws_sigv4"}}},
+                "then": {"required": ["access_key_id", "secret_access_key", "region", "service"]}
+            }
+        ]
+    })
+}
+
+fn retry_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "max_retries": {"type": "integer", "minimum": 0},
+            "initial_delay_ms": {"type": "integer", "minimum": 1},
+            "max_delay_ms": {"type": "integer", "minimum": 1},
+            "retry_status_codes": {
+                "type": "array",
+                "items": {"type": "integer", "minimum": 100, "maximum": 599}
+            },
+            "retry_on_timeout": {"type": "boolean"},
+            "retry_on_connection_error": {"type": "boolean"},
+            "respect_retry_after": {"type": "boolean"},
+            "max_retry_after_ms": {"type": "integer", "minimum": 0},
+            "retry_until": assertions_schema()
+        }
+    })
+}
+
+/// Properties of an API test, shared by single tests and suite tests
+fn api_test_properties() -> Value {
+    serde_json::json!({
+        "name": {"type": "string"},
+        "description": {"type": "string"},
+        "timeout": {"type": "integer", "minimum": 1},
+        "retries": {"type": "integer", "minimum": 0},
+        "environment": {"type": "string"},
+        "tls": tls_schema(),
+        "proxy": proxy_schema(),
+        "url": {"type": "string", "format": "uri"},
+        "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"]},
+        "headers": {"type": "object"},
+        "body": {},
+        "body_type": {"type": "string", "enum": ["json", "form", "multipart", "raw", "binary_file"]},
+        "content_type": {"type": "string"},
+        "expected_status": {"type": "integer", "minimum": 100, "maximum": 599},
+        "expected_body": {},
+        "max_response_time": {"type": "integer", "minimum": 1},
+        "max_dns_ms": {"type": "integer", "minimum": 1},
+        "max_connect_ms": {"type": "integer", "minimum": 1},
+        "max_ttfb_ms": {"type": "integer", "minimum": 1},
+        "max_download_ms": {"type": "integer", "minimum": 1},
+        "expected_headers": {"type": "object"},
+        "json_schema": {},
+        "assertions": assertions_schema(),
+        "auth": auth_schema(),
+        "response_type": {"type": "string", "enum": ["json", "xml", "text", "binary"]},
+        "snapshot": snapshot_schema(),
+        "retry": retry_schema()
+    })
+}
+
 // Define schemas for each test type
 static API_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
     let schema = serde_json::json!({
         "type": "object",
         "required": ["name", "url", "method"],
+        "properties": api_test_properties()
+    });
+
+    JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .expect("Invalid API test schema")
+});
+
+static API_SUITE_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
+    // Suite tests may use URLs relative to base_url
+    let mut test_properties = api_test_properties();
+    test_properties["url"] = serde_json::json!({"type": "string"});
+    test_properties["tags"] = serde_json::json!({"type": "array", "items": {"type": "string"}});
+
+    let schema = serde_json::json!({
+        "type": "object",
+        "required": ["name", "tests"],
         "properties": {
             "name": {"type": "string"},
             "description": {"type": "string"},
+            "base_url": {"type": "string", "format": "uri"},
+            "headers": {"type": "object", "additionalProperties": {"type": "string"}},
+            "auth": auth_schema(),
             "timeout": {"type": "integer", "minimum": 1},
-            "retries": {"type": "integer", "minimum": 0},
-            "environment": {"type": "string"},
-            "url": {"type": "string", "format": "uri"},
-            "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"]},
-            "headers": {"type": "object"},
-            "body": {},
-            "expected_status": {"type": "integer", "minimum": 100, "maximum": 599},
-            "expected_body": {},
-            "max_response_time": {"type": "integer", "minimum": 1},
-            "expected_headers": {"type": "object"},
-            "json_schema": {},
-            "retry": {
-                "type": "object",
-                "properties": {
-                    "max_retries": {"type": "integer", "minimum": 0},
-                    "initial_delay_ms": {"type": "integer", "minimum": 1},
-                    "max_delay_ms": {"type": "integer", "minimum": 1},
-                    "retry_status_codes": {
-                        "type": "array",
-                        "items": {"type": "integer", "minimum": 100, "maximum": 599}
-                    },
-                    "retry_on_timeout": {"type": "boolean"},
-                    "retry_on_connection_error": {"type": "boolean"}
+            "retry": retry_schema(),
+            "concurrency": {"type": "integer", "minimum": 1},
+            "tls": tls_schema(),
+            "proxy": proxy_schema(),
+            "tests": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["name", "url", "method"],
+                    "properties": test_properties
                 }
             }
         }
@@ -44,7 +218,7 @@ static API_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
     JSONSchema::options()
         .with_draft(Draft::Draft7)
         .compile(&schema)
-        .expect("Invalid API test schema")
+        .expect("Invalid API suite schema")
 });
 
 static PERFORMANCE_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
@@ -57,6 +231,8 @@ static PERFORMANCE_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
             "timeout": {"type": "integer", "minimum": 1},
             "retries": {"type": "integer", "minimum": 0},
             "environment": {"type": "string"},
+            "tls": tls_schema(),
+            "proxy": proxy_schema(),
             "target_url": {"type": "string", "format": "uri"},
             "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"]},
             "headers": {"type": "object"},
@@ -85,6 +261,8 @@ static SECURITY_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
             "timeout": {"type": "integer", "minimum": 1},
             "retries": {"type": "integer", "minimum": 0},
             "environment": {"type": "string"},
+            "tls": tls_schema(),
+            "proxy": proxy_schema(),
             "target_url": {"type": "string", "format": "uri"},
             "headers": {"type": "object"},
             "auth": {
@@ -123,6 +301,8 @@ static WEB_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
             "timeout": {"type": "integer", "minimum": 1},
             "retries": {"type": "integer", "minimum": 0},
             "environment": {"type": "string"},
+            "tls": tls_schema(),
+            "proxy": proxy_schema(),
             "target_url": {"type": "string", "format": "uri"},
             "viewport": {
                 "type": "object",
@@ -177,65 +357,145 @@ static WEB_TEST_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
 static API_COLLECTION_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
     let schema = serde_json::json!({
         "type": "object",
-        "required": ["name", "requests"],
+        "required": ["name"],
+        "definitions": {
+            "defaults": {
+                "type": "object",
+                "properties": {
+                    "headers": {"type": "object"},
+                    "timeout": {"type": "integer", "minimum": 1},
+                    "retries": {"type": "integer", "minimum": 0},
+                    "retry": retry_schema()
+                }
+            },
+            "request": {
+                "type": "object",
+                "required": ["name", "url", "method"],
+                "properties": {
+                    "name": {"type": "string"},
+                    "description": {"type": "string"},
+                    "id": {"type": "string"},
+                    "url": {"type": "string"},
+                    "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"]},
+                    "headers": {"type": "object"},
+                    "body": {},
+                    "body_type": {"type": "string", "enum": ["json", "form", "multipart", "raw", "binary_file"]},
+                    "content_type": {"type": "string"},
+                    "expected_status": {"type": "integer", "minimum": 100, "maximum": 599},
+                    "expected_body": {},
+                    "expected_body_type": {"type": "string", "enum": ["object", "array", "string", "number", "boolean", "null"]},
+                    "assertions": assertions_schema(),
+                    "json_schema": {"type": ["object", "boolean"]},
+                    "cookie_assertions": assertions_schema(),
+                    "max_dns_ms": {"type": "integer", "minimum": 1},
+        "max_connect_ms": {"type": "integer", "minimum": 1},
+        "max_ttfb_ms": {"type": "integer", "minimum": 1},
+                    "max_download_ms": {"type": "integer", "minimum": 1},
+                    "depends_on": {
+                        "type": "array",
+                        "items": {"type": "string"}
+                    },
+                    "capture": {
+                        "type": "object",
+                        "additionalProperties": {
+                            "oneOf": [
+                                {"type": "string"},
+                                {
+                                    "type": "object",
+                                    "required": ["from"],
+                                    "properties": {
+                                        "from": {"type": "string"},
+                                        "required": {"type": "boolean"}
+                                    }
+                                }
+                            ]
+                        }
+                    },
+                    "pre_request": {"type": "string"},
+                    "post_response": {"type": "string"},
+                    "run_if": {"type": "string"},
+                    "skip_if": {"type": "string"},
+                    "repeat_until": {
+                        "type": "object",
+                        "required": ["conditions"],
+                        "properties": {
+                            "conditions": assertions_schema(),
+                            "interval_ms": {"type": "integer", "minimum": 0},
+                            "max_attempts": {"type": "integer", "minimum": 1}
+                        }
+                    },
+                    "for_each": {
+                        "type": "object",
+                        "required": ["items"],
+                        "properties": {
+                            "items": {"type": "string"},
+                            "as": {"type": "string"}
+                        }
+                    },
+                    "retry": retry_schema(),
+                    "tags": {"type": "array", "items": {"type": "string"}}
+                }
+            },
+            "folder": {
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": {"type": "string", "pattern": "^[^/]+$"},
+                    "description": {"type": "string"},
+                    "auth": auth_schema(),
+                    "defaults": {"$ref": "#/definitions/defaults"},
+                    "variables": {"type": "object"},
+                    "requests": {"type": "array", "items": {"$ref": "#/definitions/request"}},
+                    "folders": {"type": "array", "items": {"$ref": "#/definitions/folder"}}
+                }
+            }
+        },
         "properties": {
             "name": {"type": "string"},
             "description": {"type": "string"},
             "version": {"type": "string"},
             "variables": {"type": "object"},
-            "auth": {
-                "type": "object",
-                "properties": {
-                    "type": {"type": "string", "enum": ["basic", "bearer", "api_key"]},
-                    "username": {"type": "string"},
-                    "password": {"type": "string"},
-                    "token": {"type": "string"},
-                    "key_name": {"type": "string"},
-                    "key_value": {"type": "string"}
-                },
-                "required": ["type"]
-            },
-            "defaults": {
+            "auth": auth_schema(),
+            "defaults": {"$ref": "#/definitions/defaults"},
+            "requests": {"type": "array", "items": {"$ref": "#/definitions/request"}},
+            "folders": {"type": "array", "items": {"$ref": "#/definitions/folder"}},
+            "environments": {"type": "object"},
+            "run_options": {
                 "type": "object",
                 "properties": {
-                    "headers": {"type": "object"},
-                    "timeout": {"type": "integer", "minimum": 1},
-                    "retries": {"type": "integer", "minimum": 0}
+                    "sequential": {"type": "boolean"},
+                    "stop_on_failure": {"type": "boolean"},
+                    "delay_between_requests_ms": {"type": "integer", "minimum": 0},
+                    "cookies": {"type": "boolean"},
+                    "max_concurrency": {"type": "integer", "minimum": 1}
                 }
             },
-            "requests": {
+            "cookies": {
                 "type": "array",
                 "items": {
                     "type": "object",
-                    "required": ["name", "url", "method"],
+                    "required": ["name", "value", "url"],
                     "properties": {
                         "name": {"type": "string"},
-                        "description": {"type": "string"},
-                        "id": {"type": "string"},
+                        "value": {"type": "string"},
                         "url": {"type": "string"},
-                        "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"]},
-                        "headers": {"type": "object"},
-                        "body": {},
-                        "expected_status": {"type": "integer", "minimum": 100, "maximum": 599},
-                        "expected_body": {},
-                        "expected_body_type": {"type": "string", "enum": ["object", "array", "string", "number", "boolean", "null"]},
-                        "depends_on": {
-                            "type": "array",
-                            "items": {"type": "string"}
-                        },
-                        "capture": {"type": "object"}
+                        "path": {"type": "string"}
                     }
                 }
             },
-            "environments": {"type": "object"},
-            "run_options": {
+            "pre_request": {"type": "string"},
+            "post_response": {"type": "string"},
+            "import_env": {
                 "type": "object",
                 "properties": {
-                    "sequential": {"type": "boolean"},
-                    "stop_on_failure": {"type": "boolean"},
-                    "delay_between_requests_ms": {"type": "integer", "minimum": 0}
+                    "allow": {"type": "array", "items": {"type": "string"}},
+                    "prefix": {"type": "string"}
                 }
-            }
+            },
+            "secrets": {"type": "array", "items": {"type": "string"}},
+            "schemas": {"type": "object", "additionalProperties": {"type": ["object", "boolean"]}},
+            "tls": tls_schema(),
+            "proxy": proxy_schema()
         }
     });
 
@@ -255,6 +515,8 @@ static ENHANCED_PERFORMANCE_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
             "timeout": {"type": "integer", "minimum": 1},
             "retries": {"type": "integer", "minimum": 0},
             "environment": {"type": "string"},
+            "tls": tls_schema(),
+            "proxy": proxy_schema(),
             "load_profile": {
                 "type": "object",
                 "required": ["type"],
@@ -356,6 +618,7 @@ static DATA_DRIVEN_SCHEMA: Lazy<JSONSchema> = Lazy::new(|| {
 static SCHEMAS: Lazy<HashMap<&'static str, &'static JSONSchema>> = Lazy::new(|| {
     let mut map = HashMap::new();
     map.insert("api", &*API_TEST_SCHEMA);
+    map.insert("api_suite", &*API_SUITE_SCHEMA);
     map.insert("performance", &*PERFORMANCE_TEST_SCHEMA);
     map.insert("performance_enhanced", &*ENHANCED_PERFORMANCE_SCHEMA);
     map.insert("security", &*SECURITY_TEST_SCHEMA);
@@ -368,7 +631,7 @@ static SCHEMAS: Lazy<HashMap<&'static str, &'static JSONSchema>> = Lazy::new(||
 /// Validate a test configuration against its schema
 pub fn validate_config(config: &Value, test_type: &str) -> Result<()> {
     let schema = SCHEMAS.get(test_type).ok_or_else(|| {
-        Error::ValidationError(format!("Unknown test type: {}. Supported types are: api, performance, performance_enhanced, security, web, api_collection, data_driven", test_type))
+        Error::ValidationError(format!("Unknown test type: {}. Supported types are: api, api_suite, performance, performance_enhanced, security, web, api_collection, data_driven", test_type))
     })?;
 
     let validation = schema.validate(config);
@@ -437,6 +700,8 @@ pub fn determine_test_type(path: &std::path::Path) -> Result<String> {
 
     if file_name.contains("api_collection") {
         return Ok("api_collection".to_string());
+    } else if file_name.contains("api_suite") {
+        return Ok("api_suite".to_string());
     } else if file_name.contains("api") {
         return Ok("api".to_string());
     } else if file_name.contains("performance_enhanced") {
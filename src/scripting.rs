@@ -0,0 +1,185 @@
+use crate::assertions::AssertionResult;
+use crate::error::{Error, Result};
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use log::{debug, info};
+use rhai::{Dynamic, Engine, Map, Scope};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Upper bound on the operations a single script may run
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// When a script runs relative to its request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptPhase {
+    PreRequest,
+    PostResponse,
+}
+
+impl ScriptPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptPhase::PreRequest => "pre_request",
+            ScriptPhase::PostResponse => "post_response",
+        }
+    }
+}
+
+/// What a script changed and checked
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOutcome {
+    /// Variables the script added or changed
+    pub variables: HashMap<String, String>,
+    /// Checks added with `assert`, plus a failed check if the script raised an error
+    pub checks: Vec<AssertionResult>,
+    /// Whether the script raised an error
+    pub failed: bool,
+}
+
+/// Run a script in a sandboxed engine.
+///
+/// The script sees `vars` (writable), `request` and, after the response,
+/// `response`. Errors are reported as a failed check rather than returned.
+pub fn run(
+    phase: ScriptPhase,
+    script: &str,
+    variables: &HashMap<String, String>,
+    request: &Value,
+    response: Option<&Value>,
+) -> ScriptOutcome {
+    let checks = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(&checks);
+
+    let mut scope = Scope::new();
+    let vars: Map = variables
+        .iter()
+        .map(|(name, value)| (name.into(), Dynamic::from(value.clone())))
+        .collect();
+    scope.push("vars", vars);
+    scope.push_constant_dynamic("request", to_dynamic(request));
+    if let Some(response) = response {
+        scope.push_constant_dynamic("response", to_dynamic(response));
+    }
+
+    let result = engine.run_with_scope(&mut scope, script);
+    drop(engine);
+    let mut outcome = ScriptOutcome {
+        checks: Rc::try_unwrap(checks)
+            .map(RefCell::into_inner)
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let name = format!("{} script", phase.as_str());
+    match result {
+        Ok(()) => {
+            let vars = scope.get_value::<Map>("vars").unwrap_or_default();
+            for (name, value) in vars {
+                let value = to_variable(value);
+                if variables.get(name.as_str()) != Some(&value) {
+                    outcome.variables.insert(name.to_string(), value);
+                }
+            }
+        }
+        Err(error) => {
+            outcome.failed = true;
+            outcome.checks.push(
+                AssertionResult::check(name.clone(), json!("completed"), json!("error"), false)
+                    .with_message(format!("{} failed: {}", name, error)),
+            );
+        }
+    }
+
+    outcome
+}
+
+/// Evaluate a `run_if` or `skip_if` expression with `vars` and `environment` in scope
+pub fn condition(
+    expression: &str,
+    variables: &HashMap<String, String>,
+    environment: &str,
+) -> Result<bool> {
+    let engine = engine(&Rc::new(RefCell::new(Vec::new())));
+    let mut scope = Scope::new();
+    let vars: Map = variables
+        .iter()
+        .map(|(name, value)| (name.into(), Dynamic::from(value.clone())))
+        .collect();
+    scope.push_constant("vars", vars);
+    scope.push_constant("environment", environment.to_string());
+    engine
+        .eval_expression_with_scope::<bool>(&mut scope, expression)
+        .map_err(|e| Error::ValidationError(format!("Condition '{}' failed: {}", expression, e)))
+}
+
+/// A sandboxed engine with limits, logging and the helper functions scripts can call
+fn engine(checks: &Rc<RefCell<Vec<AssertionResult>>>) -> Engine {
+    let mut engine = Engine::new();
+    // Scripts cannot load modules from disk or evaluate code built at run time
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(10 * 1024 * 1024);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+    engine.on_print(|text| info!("[script] {}", text));
+    engine.on_debug(|text, _, _| debug!("[script] {}", text));
+
+    let list = checks.clone();
+    engine.register_fn("assert", move |name: &str, passed: bool| {
+        list.borrow_mut().push(AssertionResult::check(
+            name,
+            json!(true),
+            json!(passed),
+            passed,
+        ));
+    });
+    let list = checks.clone();
+    engine.register_fn("assert", move |name: &str, passed: bool, message: &str| {
+        list.borrow_mut().push(
+            AssertionResult::check(name, json!(true), json!(passed), passed).with_message(message),
+        );
+    });
+
+    engine.register_fn("sha256", |text: &str| {
+        hex::encode(Sha256::digest(text.as_bytes()))
+    });
+    engine.register_fn("hmac_sha256", |key: &str, text: &str| {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(text.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    });
+    engine.register_fn("base64_encode", |text: &str| {
+        base64::engine::general_purpose::STANDARD.encode(text.as_bytes())
+    });
+    engine.register_fn("to_json", |value: Dynamic| {
+        rhai::serde::from_dynamic::<Value>(&value)
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    });
+
+    engine
+}
+
+fn to_dynamic(value: &Value) -> Dynamic {
+    rhai::serde::to_dynamic(value).unwrap_or(Dynamic::UNIT)
+}
+
+/// Variables are strings; other values are stored as JSON
+fn to_variable(value: Dynamic) -> String {
+    if value.is_string() {
+        return value.into_string().unwrap_or_default();
+    }
+    match rhai::serde::from_dynamic::<Value>(&value) {
+        Ok(Value::String(text)) => text,
+        Ok(value) => value.to_string(),
+        Err(_) => value.to_string(),
+    }
+}
@@ -0,0 +1,117 @@
+use crate::common::TestResult;
+use log::warn;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+/// Replacement for secret values
+pub const MASK: &str = "********";
+
+/// Values shorter than this are not masked inside text, they would hide unrelated text
+const MIN_SECRET_LEN: usize = 4;
+
+/// Secret values known to the process, masked in log lines and reports
+static REGISTERED: Lazy<RwLock<Redactor>> = Lazy::new(Default::default);
+
+/// Masks a set of secret values wherever they appear in text
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    /// Longest first, so a secret containing another is masked whole
+    values: Vec<String>,
+    /// Non-empty values too short to mask
+    short: HashSet<String>,
+}
+
+impl Redactor {
+    pub fn new(values: impl IntoIterator<Item = String>) -> Self {
+        let mut redactor = Self::default();
+        redactor.extend(values);
+        redactor
+    }
+
+    /// Add secret values, returning how many were newly found too short to mask
+    pub fn extend(&mut self, values: impl IntoIterator<Item = String>) -> usize {
+        let mut short = 0;
+        for value in values {
+            if value.len() >= MIN_SECRET_LEN {
+                if !self.values.contains(&value) {
+                    self.values.push(value);
+                }
+            } else if !value.is_empty() && self.short.insert(value) {
+                short += 1;
+            }
+        }
+        self.values
+            .sort_by_key(|value| std::cmp::Reverse(value.len()));
+        short
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for value in &self.values {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), MASK);
+            }
+        }
+        text
+    }
+
+    /// Mask secrets in every string of a JSON value
+    pub fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            Value::Object(fields) => fields
+                .values_mut()
+                .for_each(|field| self.redact_json(field)),
+            _ => {}
+        }
+    }
+
+    pub fn redact_result(&self, result: &mut TestResult) {
+        result.name = self.redact(&result.name);
+        if let Some(details) = result.details.as_mut() {
+            self.redact_json(details);
+        }
+    }
+}
+
+/// Mask these values in log lines and reports for the rest of the process
+pub fn register(values: impl IntoIterator<Item = String>) {
+    let short = match REGISTERED.write() {
+        Ok(mut registered) => registered.extend(values),
+        Err(_) => return,
+    };
+    if short > 0 {
+        warn!(
+            "{} secret value(s) shorter than {} characters will not be masked in logs and reports",
+            short, MIN_SECRET_LEN
+        );
+    }
+}
+
+/// Mask every registered secret in a text
+pub fn redact(text: &str) -> String {
+    match REGISTERED.read() {
+        Ok(registered) if !registered.is_empty() => registered.redact(text),
+        _ => text.to_string(),
+    }
+}
+
+/// Copies of test results with every registered secret masked
+pub fn redact_results(results: &[TestResult]) -> Vec<TestResult> {
+    let mut results = results.to_vec();
+    if let Ok(registered) = REGISTERED.read() {
+        if !registered.is_empty() {
+            results
+                .iter_mut()
+                .for_each(|result| registered.redact_result(result));
+        }
+    }
+    results
+}
@@ -1,3 +1,4 @@
+use crate::client::{self, ClientOptions};
 use crate::common::{TestConfig, TestResult, TestRunner};
 use crate::error::Result;
 use async_trait::async_trait;
@@ -43,6 +44,7 @@ pub struct SecurityFinding {
 
 pub struct SecurityTestRunner {
     client: Client,
+    client_options: ClientOptions,
     scan_depth: u8,
     passive_only: bool,
 }
@@ -51,16 +53,29 @@ impl SecurityTestRunner {
     pub fn new(scan_depth: u8, passive_only: bool) -> Self {
         Self {
             client: Client::new(),
+            client_options: ClientOptions::default(),
             scan_depth,
             passive_only,
         }
     }
 
+    /// Override the TLS and proxy settings of every test config
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.client_options = client_options;
+        self
+    }
+
     async fn run_scan(&self, config: &SecurityTestConfig) -> Result<Vec<SecurityFinding>> {
         let mut findings = Vec::new();
+        let client = client::client_for(
+            &self.client,
+            &config.base.client_options(),
+            &self.client_options,
+            Client::builder(),
+        )?;
 
         // Basic security checks
-        if let Ok(response) = self.client.get(&config.target_url).send().await {
+        if let Ok(response) = client.get(&config.target_url).send().await {
             // Check for security headers (always run regardless of scan depth)
             findings.extend(self.check_security_headers(&response));
 
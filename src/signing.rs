@@ -0,0 +1,256 @@
+use crate::api_collection::CollectionAuth;
+use crate::error::{Error, Result};
+use crate::request_body::BodyType;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::{Client, Request, RequestBuilder, Response};
+use sha2::{Digest, Sha256};
+
+/// Canonical string signed by `hmac` auth when no template is configured
+pub const DEFAULT_HMAC_TEMPLATE: &str = "{method}\n{path}\n{timestamp}\n{body}";
+
+/// Everything except the RFC 3986 unreserved characters is percent-encoded
+const AWS_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Build, sign and send a request
+pub async fn send(
+    client: &Client,
+    auth: &CollectionAuth,
+    request: RequestBuilder,
+) -> Result<Response> {
+    let mut request = request.build()?;
+    sign(&mut request, auth, Utc::now())?;
+    Ok(client.execute(request).await?)
+}
+
+/// Reject body types that cannot be signed.
+///
+/// Signatures cover the exact body bytes, but multipart bodies are streamed
+/// and only produced while the request is sent.
+pub fn check_body_type(auth: &CollectionAuth, body_type: BodyType) -> Result<()> {
+    if auth.is_signature() && body_type == BodyType::Multipart {
+        return Err(Error::ConfigError(format!(
+            "{} auth cannot sign multipart bodies; use a json, form, raw or binary_file body",
+            auth.auth_type
+        )));
+    }
+    Ok(())
+}
+
+/// Add the signature headers of `hmac` or `aws_sigv4` auth to a complete request
+pub fn sign(request: &mut Request, auth: &CollectionAuth, now: DateTime<Utc>) -> Result<()> {
+    match auth.auth_type.as_str() {
+        "hmac" => sign_hmac(request, auth, now),
+        "aws_sigv4" => sign_aws_sigv4(request, auth, now),
+        other => Err(Error::ValidationError(format!(
+            "'{}' authentication does not sign requests",
+            other
+        ))),
+    }
+}
+
+/// Sign the canonical string built from `canonical_template`
+fn sign_hmac(request: &mut Request, auth: &CollectionAuth, now: DateTime<Utc>) -> Result<()> {
+    let secret = required(&auth.secret, "secret", "hmac")?;
+    let body = body(request)?;
+    let timestamp = now.timestamp().to_string();
+    let url = request.url();
+
+    let template = auth
+        .canonical_template
+        .as_deref()
+        .unwrap_or(DEFAULT_HMAC_TEMPLATE);
+    let canonical = template
+        .replace("{method}", request.method().as_str())
+        .replace("{host}", &host(url))
+        .replace("{path}", url.path())
+        .replace("{query}", url.query().unwrap_or(""))
+        .replace("{timestamp}", &timestamp)
+        .replace("{body_sha256}", &hex::encode(Sha256::digest(&body)))
+        .replace("{body}", &String::from_utf8_lossy(&body));
+
+    let digest = hmac_sha256(secret.as_bytes(), canonical.as_bytes());
+    let signature = match auth.signature_encoding.as_deref().unwrap_or("hex") {
+        "hex" => hex::encode(digest),
+        "base64" => base64::engine::general_purpose::STANDARD.encode(digest),
+        other => {
+            return Err(Error::ValidationError(format!(
+                "Unsupported signature_encoding '{}', expected hex or base64",
+                other
+            )))
+        }
+    };
+
+    let signature_header = auth.signature_header.as_deref().unwrap_or("X-Signature");
+    let timestamp_header = auth.timestamp_header.as_deref().unwrap_or("X-Timestamp");
+    let prefix = auth.signature_prefix.as_deref().unwrap_or("");
+    insert(request, timestamp_header, &timestamp)?;
+    insert(
+        request,
+        signature_header,
+        &format!("{}{}", prefix, signature),
+    )
+}
+
+/// AWS Signature Version 4 in the Authorization header
+fn sign_aws_sigv4(request: &mut Request, auth: &CollectionAuth, now: DateTime<Utc>) -> Result<()> {
+    let access_key_id = required(&auth.access_key_id, "access_key_id", "aws_sigv4")?;
+    let secret_access_key = required(&auth.secret_access_key, "secret_access_key", "aws_sigv4")?;
+    let region = required(&auth.region, "region", "aws_sigv4")?;
+    let service = required(&auth.service, "service", "aws_sigv4")?;
+
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(Sha256::digest(body(request)?));
+
+    insert(request, "x-amz-date", &amz_date)?;
+    if let Some(session_token) = &auth.session_token {
+        insert(request, "x-amz-security-token", session_token)?;
+    }
+    // S3 requires the payload hash as a header, other services only sign it
+    if service == "s3" {
+        insert(request, "x-amz-content-sha256", &payload_hash)?;
+    }
+
+    // The client adds Host when sending, so it is signed from the URL
+    let mut headers: Vec<(String, String)> = vec![("host".to_string(), host(request.url()))];
+    for (name, value) in request.headers() {
+        let name = name.as_str();
+        if name == "content-type" || name.starts_with("x-amz-") {
+            let value = value.to_str().map_err(|_| {
+                Error::ValidationError(format!("Header '{}' is not valid text", name))
+            })?;
+            headers.push((
+                name.to_string(),
+                value.split_whitespace().collect::<Vec<_>>().join(" "),
+            ));
+        }
+    }
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method(),
+        canonical_uri(request.url(), &service),
+        canonical_query(request.url()),
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let mut key = hmac_sha256(
+        format!("AWS4{}", secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    for part in [region.as_str(), service.as_str(), "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    insert(
+        request,
+        "authorization",
+        &format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            access_key_id, scope, signed_headers, signature
+        ),
+    )
+}
+
+/// Path segments URI-encoded once for S3 and twice for other services, as AWS expects
+fn canonical_uri(url: &url::Url, service: &str) -> String {
+    let path = url.path();
+    if service == "s3" {
+        return path
+            .split('/')
+            .map(|segment| uri_encode(&percent_decode_str(segment).decode_utf8_lossy()))
+            .collect::<Vec<_>>()
+            .join("/");
+    }
+    path.split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Query parameters URI-encoded and sorted by name, then value
+fn canonical_query(url: &url::Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| (uri_encode(&name), uri_encode(&value)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn uri_encode(text: &str) -> String {
+    utf8_percent_encode(text, AWS_ENCODE).to_string()
+}
+
+/// Host with the port when it is not the scheme default
+fn host(url: &url::Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// The request body, which must be in memory to be signed
+fn body(request: &Request) -> Result<Vec<u8>> {
+    match request.body() {
+        None => Ok(Vec::new()),
+        Some(body) => body.as_bytes().map(<[u8]>::to_vec).ok_or_else(|| {
+            Error::ValidationError("Streaming request bodies cannot be signed".to_string())
+        }),
+    }
+}
+
+fn insert(request: &mut Request, name: &str, value: &str) -> Result<()> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| Error::ValidationError(format!("Invalid header name '{}': {}", name, e)))?;
+    let value = HeaderValue::from_str(value).map_err(|e| {
+        Error::ValidationError(format!("Invalid value for header '{}': {}", name, e))
+    })?;
+    request.headers_mut().insert(name, value);
+    Ok(())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn required(value: &Option<String>, name: &str, auth_type: &str) -> Result<String> {
+    value.clone().ok_or_else(|| {
+        Error::ValidationError(format!("'{}' is required for {} auth", name, auth_type))
+    })
+}
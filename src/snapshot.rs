@@ -0,0 +1,220 @@
+use crate::assertions::AssertionResult;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Placeholder stored in place of ignored values
+const IGNORED: &str = "<ignored>";
+
+/// Snapshot (golden response) settings for an API test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Snapshot name, defaults to the test name
+    pub name: Option<String>,
+    /// Directory holding snapshot files, relative to the configuration file
+    #[serde(default = "default_snapshot_dir")]
+    pub dir: String,
+    /// Response headers stored in the snapshot
+    #[serde(default)]
+    pub headers: Vec<String>,
+    /// JSONPaths whose values are ignored, such as timestamps and IDs
+    #[serde(default)]
+    pub ignore_paths: Vec<String>,
+}
+
+fn default_snapshot_dir() -> String {
+    "__snapshots__".to_string()
+}
+
+impl SnapshotConfig {
+    /// Resolve the snapshot directory relative to the configuration directory
+    pub fn resolve_paths(&mut self, base_dir: &Path) {
+        if Path::new(&self.dir).is_relative() {
+            self.dir = base_dir.join(&self.dir).to_string_lossy().to_string();
+        }
+    }
+
+    /// Path of the snapshot file for a test
+    pub fn file_path(&self, test_name: &str) -> PathBuf {
+        let name: String = self
+            .name
+            .as_deref()
+            .unwrap_or(test_name)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Path::new(&self.dir).join(format!("{}.json", name))
+    }
+
+    /// Build the normalized snapshot of a response
+    pub fn capture(&self, body: &Value, headers: &HeaderMap) -> Value {
+        let mut body = body.clone();
+        if body.is_object() || body.is_array() {
+            for path in &self.ignore_paths {
+                body = jsonpath_lib::replace_with(body.clone(), path, &mut |_| {
+                    Some(Value::String(IGNORED.to_string()))
+                })
+                .unwrap_or(body);
+            }
+        }
+
+        let headers: Map<String, Value> = self
+            .headers
+            .iter()
+            .map(|name| {
+                let value = headers
+                    .get(name.as_str())
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| Value::String(v.to_string()))
+                    .unwrap_or(Value::Null);
+                (name.to_lowercase(), value)
+            })
+            .collect();
+
+        json!({
+            "headers": headers,
+            "body": body
+        })
+    }
+
+    /// Compare a response with its stored snapshot.
+    ///
+    /// The snapshot is written when it does not exist yet or when `update` is
+    /// set; otherwise every difference is reported as a failed check.
+    pub fn check(
+        &self,
+        test_name: &str,
+        body: &Value,
+        headers: &HeaderMap,
+        update: bool,
+    ) -> Vec<AssertionResult> {
+        let path = self.file_path(test_name);
+        let actual = self.capture(body, headers);
+        let display_path = path.display().to_string();
+
+        if update || !path.exists() {
+            let written = path
+                .parent()
+                .map(std::fs::create_dir_all)
+                .unwrap_or(Ok(()))
+                .and_then(|_| {
+                    let content = serde_json::to_string_pretty(&actual).unwrap_or_default();
+                    std::fs::write(&path, content + "\n")
+                });
+            return vec![match written {
+                Ok(()) => {
+                    AssertionResult::check("snapshot", json!(display_path), json!("written"), true)
+                }
+                Err(e) => {
+                    AssertionResult::check("snapshot", json!(display_path), Value::Null, false)
+                        .with_message(format!(
+                            "Failed to write snapshot '{}': {}",
+                            display_path, e
+                        ))
+                }
+            }];
+        }
+
+        let expected = match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()))
+        {
+            Ok(expected) => expected,
+            Err(e) => {
+                return vec![AssertionResult::check(
+                    "snapshot",
+                    json!(display_path),
+                    Value::Null,
+                    false,
+                )
+                .with_message(format!("Failed to read snapshot '{}': {}", display_path, e))]
+            }
+        };
+
+        let mut differences = Vec::new();
+        diff(
+            "headers",
+            &expected["headers"],
+            &actual["headers"],
+            &mut differences,
+        );
+        diff("$", &expected["body"], &actual["body"], &mut differences);
+
+        if differences.is_empty() {
+            return vec![AssertionResult::check(
+                "snapshot",
+                json!(display_path),
+                json!("matched"),
+                true,
+            )];
+        }
+
+        differences
+            .into_iter()
+            .map(|(path, expected, actual)| {
+                AssertionResult::check(format!("snapshot:{}", path), expected, actual, false)
+                    .with_message(format!("Response differs from snapshot at {}", path))
+            })
+            .collect()
+    }
+}
+
+/// Collect the differences between two JSON values as (path, expected, actual)
+fn diff(
+    path: &str,
+    expected: &Value,
+    actual: &Value,
+    differences: &mut Vec<(String, Value, Value)>,
+) {
+    if expected == &Value::String(IGNORED.to_string()) {
+        return;
+    }
+
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected_value) in expected {
+                let child = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual_value) => diff(&child, expected_value, actual_value, differences),
+                    None => differences.push((child, expected_value.clone(), Value::Null)),
+                }
+            }
+            for (key, actual_value) in actual {
+                if !expected.contains_key(key) {
+                    differences.push((
+                        format!("{}.{}", path, key),
+                        Value::Null,
+                        actual_value.clone(),
+                    ));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for (i, expected_value) in expected.iter().enumerate() {
+                let child = format!("{}[{}]", path, i);
+                match actual.get(i) {
+                    Some(actual_value) => diff(&child, expected_value, actual_value, differences),
+                    None => differences.push((child, expected_value.clone(), Value::Null)),
+                }
+            }
+            for (i, actual_value) in actual.iter().enumerate().skip(expected.len()) {
+                differences.push((
+                    format!("{}[{}]", path, i),
+                    Value::Null,
+                    actual_value.clone(),
+                ));
+            }
+        }
+        _ if expected != actual => {
+            differences.push((path.to_string(), expected.clone(), actual.clone()))
+        }
+        _ => {}
+    }
+}
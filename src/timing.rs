@@ -0,0 +1,200 @@
+use crate::assertions::AssertionResult;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{Client, ClientBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+/// Duration of each phase of an HTTP request in milliseconds.
+///
+/// DNS lookup and connect are measured on the connection the client opens for
+/// the request, and are 0 when it reuses a kept-alive connection. Connect
+/// covers the TCP connect and, for HTTPS, the TLS handshake. Time to first
+/// byte excludes that connection setup, and download covers reading the
+/// response body.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    /// DNS lookup
+    pub dns_ms: f64,
+    /// TCP connect and TLS handshake
+    pub connect_ms: f64,
+    /// Time from sending the request to receiving the response headers
+    pub ttfb_ms: f64,
+    /// Time to read the response body
+    pub download_ms: f64,
+    /// Sum of all phases
+    pub total_ms: f64,
+}
+
+impl PhaseTimings {
+    /// Time spent setting up the connection
+    pub fn connection_ms(&self) -> f64 {
+        self.dns_ms + self.connect_ms
+    }
+
+    /// Record the time between sending the request and receiving the headers,
+    /// with the connection setup measured while sending
+    pub fn record_headers(&mut self, elapsed: Duration, setup: ConnectionSetup) {
+        self.dns_ms = setup.dns.map_or(0.0, millis);
+        self.connect_ms = setup.connect.map_or(0.0, |connect| {
+            millis(connect.saturating_sub(setup.dns.unwrap_or_default()))
+        });
+        self.ttfb_ms = (millis(elapsed) - self.connection_ms()).max(0.0);
+        self.total_ms = self.connection_ms() + self.ttfb_ms + self.download_ms;
+    }
+
+    /// Record the time spent reading the response body
+    pub fn record_download(&mut self, elapsed: Duration) {
+        self.download_ms = millis(elapsed);
+        self.total_ms = self.connection_ms() + self.ttfb_ms + self.download_ms;
+    }
+}
+
+/// Upper bounds for each request phase in milliseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimingLimits {
+    /// Maximum DNS lookup time
+    pub max_dns_ms: Option<u64>,
+    /// Maximum TCP connect and TLS handshake time
+    pub max_connect_ms: Option<u64>,
+    /// Maximum time to first byte
+    pub max_ttfb_ms: Option<u64>,
+    /// Maximum body download time
+    pub max_download_ms: Option<u64>,
+}
+
+impl TimingLimits {
+    /// Check each configured limit against the measured timings
+    pub fn check(&self, timings: &PhaseTimings) -> Vec<AssertionResult> {
+        let phases = [
+            ("dns", self.max_dns_ms, timings.dns_ms),
+            ("connect", self.max_connect_ms, timings.connect_ms),
+            ("ttfb", self.max_ttfb_ms, timings.ttfb_ms),
+            ("download", self.max_download_ms, timings.download_ms),
+        ];
+        phases
+            .into_iter()
+            .filter_map(|(phase, limit, actual)| {
+                let limit = limit?;
+                let name = format!("timing:{}", phase);
+                Some(
+                    AssertionResult::check(
+                        name,
+                        json!(limit),
+                        json!(actual),
+                        actual <= limit as f64,
+                    )
+                    .with_message(format!(
+                        "{} took {:.2}ms, exceeding the limit of {}ms",
+                        phase, actual, limit
+                    )),
+                )
+            })
+            .collect()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Client builder for runners that report DNS and connect timings.
+///
+/// Requests sent with [`measure`] record the setup of any connection the
+/// client opens for them.
+pub fn timed_client_builder() -> ClientBuilder {
+    Client::builder()
+        .timeout(Duration::from_secs(30))
+        .dns_resolver(Arc::new(TimedResolver))
+        .connector_layer(TimedConnectLayer)
+}
+
+/// Setup of the connection a request opened, empty when it reused one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnectionSetup {
+    /// DNS lookup, `None` when the host needed none
+    pub dns: Option<Duration>,
+    /// Opening the connection, including the DNS lookup
+    pub connect: Option<Duration>,
+}
+
+tokio::task_local! {
+    static SETUP: Arc<Mutex<ConnectionSetup>>;
+}
+
+/// Run a request, returning its output with the setup of the connection it opened
+pub async fn measure<F: Future>(request: F) -> (F::Output, ConnectionSetup) {
+    let setup = Arc::new(Mutex::new(ConnectionSetup::default()));
+    let output = SETUP.scope(setup.clone(), request).await;
+    let setup = *setup.lock().unwrap_or_else(|e| e.into_inner());
+    (output, setup)
+}
+
+fn record(phase: impl FnOnce(&mut ConnectionSetup)) {
+    let _ = SETUP.try_with(|setup| phase(&mut setup.lock().unwrap_or_else(|e| e.into_inner())));
+}
+
+/// Resolves host names like the default resolver, timing each lookup
+struct TimedResolver;
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let start = Instant::now();
+            // The connector sets the port of the resolved addresses
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            let elapsed = start.elapsed();
+            record(|setup| setup.dns = Some(elapsed));
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Times the connections opened by a client's connector
+#[derive(Clone)]
+struct TimedConnectLayer;
+
+impl<S> Layer<S> for TimedConnectLayer {
+    type Service = TimedConnect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnect(inner)
+    }
+}
+
+#[derive(Clone)]
+struct TimedConnect<S>(S);
+
+impl<S, R> Service<R> for TimedConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let Ok(setup) = SETUP.try_with(Arc::clone) else {
+            return Box::pin(self.0.call(request));
+        };
+        // The connection may finish on another task, the lookup is recorded for this request
+        let connecting = SETUP.sync_scope(setup.clone(), || self.0.call(request));
+        Box::pin(SETUP.scope(setup, async move {
+            let start = Instant::now();
+            let connection = connecting.await;
+            let elapsed = start.elapsed();
+            record(|setup| setup.connect = Some(elapsed));
+            connection
+        }))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::client::{self, ClientOptions};
 use crate::common::{TestConfig, TestResult, TestRunner};
 use crate::error::Result;
 use async_trait::async_trait;
@@ -53,6 +54,7 @@ fn default_user_agent() -> String {
 
 pub struct WebTestRunner {
     client: Client,
+    client_options: ClientOptions,
     headless: bool,
     screenshot_dir: Option<String>,
 }
@@ -61,11 +63,18 @@ impl WebTestRunner {
     pub fn new(headless: bool, screenshot_dir: Option<String>) -> Self {
         Self {
             client: Client::new(),
+            client_options: ClientOptions::default(),
             headless,
             screenshot_dir,
         }
     }
 
+    /// Override the TLS and proxy settings of every test config
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        self.client_options = client_options;
+        self
+    }
+
     async fn run_web_test(&self, config: &WebTestConfig) -> Result<serde_json::Value> {
         // In a real implementation, this would use a headless browser like Chrome or Firefox
         // through a library like fantoccini, thirtyfour, or headless_chrome
@@ -85,8 +94,13 @@ impl WebTestRunner {
         }
 
         // Simulate browser navigation
-        let response = self
-            .client
+        let client = client::client_for(
+            &self.client,
+            &config.base.client_options(),
+            &self.client_options,
+            Client::builder(),
+        )?;
+        let response = client
             .get(&config.target_url)
             .header("User-Agent", &config.user_agent)
             .timeout(Duration::from_secs(config.wait_timeout_secs.unwrap_or(30)))
@@ -0,0 +1,1354 @@
+use qitops::api_collection::{ApiCollection, ApiCollectionRunner, CollectionFilter};
+use qitops::capture::{self, Capture, CaptureResponse};
+use qitops::collection_graph::DependencyGraph;
+use qitops::data_driven::{DataDrivenConfig, DataDrivenRunner};
+use qitops::environment::Environment;
+use qitops::scripting::{self, ScriptPhase};
+use qitops::secrets::{self, Redactor};
+use serde_json::json;
+use std::collections::HashMap;
+use wiremock::matchers::{header, method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+fn has_cookie(request: &Request, cookie: &str) -> bool {
+    request
+        .headers
+        .iter()
+        .filter(|(name, _)| name.as_str().eq_ignore_ascii_case("cookie"))
+        .flat_map(|(_, values)| values.iter())
+        .any(|value| value.as_str().split("; ").any(|c| c == cookie))
+}
+
+// Test session cookies, seeded cookies, cookie captures and cookie assertions
+#[tokio::test]
+async fn test_cookie_jar() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("set-cookie", "session=abc123; Path=/; HttpOnly")
+                .set_body_json(json!({"ok": true})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/profile"))
+        .and(|request: &Request| {
+            has_cookie(request, "session=abc123") && has_cookie(request, "tenant=acme")
+        })
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"name": "Ada"})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/profile"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+
+    let collection = |cookies: bool| -> ApiCollection {
+        let mut collection = json!({
+            "name": "Session",
+            "variables": {"base_url": server.uri()},
+            "environments": {"test": {"tenant": "acme"}},
+            "run_options": {"cookies": cookies, "stop_on_failure": false},
+            "requests": [
+                {
+                    "name": "Login",
+                    "id": "login",
+                    "url": "{{base_url}}/login",
+                    "method": "POST",
+                    "capture": {"session_id": "cookie:session"},
+                    "cookie_assertions": [
+                        {"path": "session", "operator": "equals", "value": "abc123"}
+                    ]
+                },
+                {
+                    "name": "Profile",
+                    "url": "{{base_url}}/profile",
+                    "method": "GET",
+                    "expected_status": 200,
+                    "cookie_assertions": [
+                        {"path": "tenant", "operator": "exists"},
+                        {"path": "missing", "operator": "not_exists"}
+                    ]
+                }
+            ]
+        });
+        if cookies {
+            collection["cookies"] =
+                json!([{"name": "tenant", "value": "{{tenant}}", "url": "{{base_url}}"}]);
+        }
+        serde_json::from_value(collection).unwrap()
+    };
+
+    let runner = ApiCollectionRunner::new();
+    let result = runner
+        .run_collection(&collection(true), "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    assert_eq!(result.variables["session_id"], "abc123");
+    let profile = result.request_results[1].details.as_ref().unwrap();
+    assert_eq!(profile["cookies"]["session"], json!("abc123"));
+
+    // Without the jar the session is lost between requests
+    let result = runner
+        .run_collection(&collection(false), "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "failed");
+    assert_eq!(result.variables["session_id"], "abc123");
+    assert_eq!(result.request_results[1].status, "failed");
+}
+
+// Test that seeded cookies require the cookie jar
+#[tokio::test]
+async fn test_seed_cookies_require_jar() {
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Seeds",
+        "cookies": [{"name": "a", "value": "1", "url": "http://localhost"}],
+        "requests": []
+    }))
+    .unwrap();
+    let error = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("run_options.cookies"));
+}
+
+// Test that independent requests run concurrently with scoped captures
+#[tokio::test]
+async fn test_parallel_execution() {
+    let server = MockServer::start().await;
+    for (name, value) in [("a", "1"), ("b", "2")] {
+        Mock::given(path(format!("/{}", name)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"value": value}))
+                    .set_delay(std::time::Duration::from_millis(300)),
+            )
+            .mount(&server)
+            .await;
+    }
+    Mock::given(path("/combined/1/2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&server)
+        .await;
+    Mock::given(path_regex("^/isolated/"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Graph",
+        "variables": {"base_url": server.uri()},
+        "run_options": {"sequential": false, "max_concurrency": 2},
+        "requests": [
+            {
+                "name": "Combined",
+                "url": "{{base_url}}/combined/{{a_value}}/{{b_value}}",
+                "method": "GET",
+                "depends_on": ["a", "b"],
+                "expected_status": 200
+            },
+            {
+                "name": "A", "id": "a", "url": "{{base_url}}/a", "method": "GET",
+                "capture": {"a_value": "$.value"}
+            },
+            {
+                "name": "B", "id": "b", "url": "{{base_url}}/b", "method": "GET",
+                "capture": {"b_value": "$.value"}
+            },
+            {
+                "name": "Isolated",
+                "url": "{{base_url}}/isolated/{{a_value}}",
+                "method": "GET",
+                "depends_on": ["b"],
+                "expected_status": 200
+            }
+        ]
+    }))
+    .unwrap();
+
+    let start = std::time::Instant::now();
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    // A and B overlap
+    assert!(start.elapsed() < std::time::Duration::from_millis(600));
+
+    let names: Vec<&str> = result
+        .request_results
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(names, ["Combined", "A", "B", "Isolated"]);
+    assert_eq!(result.variables["a_value"], "1");
+    assert_eq!(result.variables["b_value"], "2");
+
+    // Captures of requests that are not ancestors stay invisible
+    let requests = server.received_requests().await.unwrap();
+    let isolated = requests
+        .iter()
+        .find(|r| r.url.path().starts_with("/isolated/"))
+        .unwrap();
+    assert_eq!(isolated.url.path(), "/isolated/%7B%7Ba_value%7D%7D");
+}
+
+// Test that dependency cycles are rejected in parallel mode
+#[tokio::test]
+async fn test_parallel_cycle() {
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Cycle",
+        "run_options": {"sequential": false},
+        "requests": [
+            {"name": "A", "id": "a", "url": "http://localhost/a", "method": "GET", "depends_on": ["b"]},
+            {"name": "B", "id": "b", "url": "http://localhost/b", "method": "GET", "depends_on": ["a"]}
+        ]
+    }))
+    .unwrap();
+    let error = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("cycle"), "{}", error);
+}
+
+// Test that requests left out by stop_on_failure are reported as skipped
+#[tokio::test]
+async fn test_stop_on_failure_skips_remaining() {
+    let server = MockServer::start().await;
+    Mock::given(path("/fail"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    Mock::given(path("/ok"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    for sequential in [true, false] {
+        let collection: ApiCollection = serde_json::from_value(json!({
+            "name": "Stop",
+            "variables": {"base_url": server.uri()},
+            "run_options": {"sequential": sequential, "max_concurrency": 1},
+            "requests": [
+                {"name": "Fail", "url": "{{base_url}}/fail", "method": "GET", "expected_status": 200},
+                {"name": "Second", "url": "{{base_url}}/ok", "method": "GET"},
+                {"name": "Third", "url": "{{base_url}}/ok", "method": "GET"}
+            ]
+        }))
+        .unwrap();
+
+        let result = ApiCollectionRunner::new()
+            .run_collection(&collection, "test")
+            .await
+            .unwrap();
+        let statuses: Vec<&str> = result
+            .request_results
+            .iter()
+            .map(|r| r.status.as_str())
+            .collect();
+        assert_eq!(statuses, ["failed", "skipped", "skipped"]);
+        assert_eq!(
+            result.request_results[1].details.as_ref().unwrap()["skipped"],
+            "not run: 'Fail' failed and stop_on_failure is set"
+        );
+    }
+}
+
+// Test that a request that cannot be sent fails on its own, in sequential and parallel runs
+#[tokio::test]
+async fn test_send_error_reported_as_failed() {
+    let server = MockServer::start().await;
+    Mock::given(path("/ok"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    for (sequential, stop_on_failure) in
+        [(true, false), (false, false), (true, true), (false, true)]
+    {
+        let collection: ApiCollection = serde_json::from_value(json!({
+            "name": "Send errors",
+            "variables": {"base_url": server.uri()},
+            "run_options": {
+                "sequential": sequential,
+                "stop_on_failure": stop_on_failure,
+                "max_concurrency": 1
+            },
+            "requests": [
+                {
+                    "name": "Unreachable", "url": "http://127.0.0.1:1/", "method": "GET",
+                    "retry": {"max_retries": 0},
+                    "post_response": "assert(\"sent\", true);"
+                },
+                {"name": "Second", "url": "{{base_url}}/ok", "method": "GET"}
+            ]
+        }))
+        .unwrap();
+
+        let result = ApiCollectionRunner::new()
+            .run_collection(&collection, "test")
+            .await
+            .unwrap();
+        let statuses: Vec<&str> = result
+            .request_results
+            .iter()
+            .map(|r| r.status.as_str())
+            .collect();
+        let expected = if stop_on_failure { "skipped" } else { "passed" };
+        assert_eq!(statuses, ["failed", expected], "sequential: {}", sequential);
+        assert_eq!(result.request_results[0].name, "Unreachable");
+        assert_eq!(result.status, "failed");
+    }
+}
+
+// Test that sequential mode runs dependencies first, whatever their position in the file
+#[tokio::test]
+async fn test_sequential_topological_order() {
+    let server = MockServer::start().await;
+    Mock::given(path("/users/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1})))
+        .mount(&server)
+        .await;
+    Mock::given(path("/users/1/posts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Order",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {
+                "name": "Posts", "id": "posts", "url": "{{base_url}}/users/{{user_id}}/posts",
+                "method": "GET", "depends_on": ["user"], "expected_status": 200
+            },
+            {"name": "Health", "url": "{{base_url}}/users/1", "method": "GET"},
+            {
+                "name": "User", "id": "user", "url": "{{base_url}}/users/1", "method": "GET",
+                "capture": {"user_id": "$.id"}
+            }
+        ]
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    let names: Vec<&str> = result
+        .request_results
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(names, ["Health", "User", "Posts"]);
+
+    let graph = DependencyGraph::build(&collection).unwrap();
+    assert_eq!(graph.order(), [1, 2, 0]);
+    assert!(graph.to_dot("Order").contains("n2 -> n0;"));
+    assert!(graph.to_mermaid().contains("n2 --> n0"));
+}
+
+// Test that graph problems are all reported before any request is sent
+#[tokio::test]
+async fn test_graph_validation() {
+    let server = MockServer::start().await;
+    let invalid: ApiCollection = serde_json::from_value(json!({
+        "name": "Invalid",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {"name": "First", "id": "dup", "url": "{{base_url}}/1", "method": "GET"},
+            {"name": "Second", "id": "dup", "url": "{{base_url}}/2", "method": "GET"},
+            {"name": "Orphan", "url": "{{base_url}}/3", "method": "GET", "depends_on": ["nope"]}
+        ]
+    }))
+    .unwrap();
+    let error = ApiCollectionRunner::new()
+        .run_collection(&invalid, "test")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Duplicate request ID 'dup'"), "{}", error);
+    assert!(error.contains("unknown request 'nope'"), "{}", error);
+
+    let cyclic: ApiCollection = serde_json::from_value(json!({
+        "name": "Cycle",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {"name": "Start", "url": "{{base_url}}/start", "method": "GET"},
+            {"name": "A", "id": "a", "url": "{{base_url}}/a", "method": "GET", "depends_on": ["c"]},
+            {"name": "B", "id": "b", "url": "{{base_url}}/b", "method": "GET", "depends_on": ["a"]},
+            {"name": "C", "id": "c", "url": "{{base_url}}/c", "method": "GET", "depends_on": ["b"]}
+        ]
+    }))
+    .unwrap();
+    let error = ApiCollectionRunner::new()
+        .run_collection(&cyclic, "test")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("cycle: A -> C -> B -> A"), "{}", error);
+
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+// Test pre-request and post-response scripts
+#[tokio::test]
+async fn test_scripts() {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let server = MockServer::start().await;
+    let url = format!("{}/orders", server.uri());
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+    mac.update(format!("POST {}", url).as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Mock::given(method("POST"))
+        .and(path("/orders"))
+        .and(wiremock::matchers::header(
+            "x-signature",
+            signature.as_str(),
+        ))
+        .and(wiremock::matchers::header("x-run", "7"))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(json!({"id": 42, "items": [1, 2, 3]})),
+        )
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Scripts",
+        "variables": {"base_url": server.uri(), "secret": "s3cret"},
+        "defaults": {"headers": {"x-run": "{{run}}"}},
+        "pre_request": "vars.run = 7;",
+        "requests": [
+            {
+                "name": "Create order",
+                "url": "{{base_url}}/orders",
+                "method": "POST",
+                "headers": {"x-signature": "{{signature}}"},
+                "pre_request": "vars.signature = hmac_sha256(vars.secret, request.method + \" \" + request.url);",
+                "post_response": "assert(\"created\", response.status == 201);\nassert(\"three items\", response.body.items.len() == 3);\nassert(\"header seen\", request.headers[\"x-run\"] == \"7\", \"x-run was not resolved\");\nvars.order_id = response.body.id;"
+            },
+            {
+                "name": "Broken",
+                "url": "{{base_url}}/never",
+                "method": "GET",
+                "pre_request": "throw \"no token\";"
+            }
+        ],
+        "run_options": {"stop_on_failure": false}
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    let created = &result.request_results[0];
+    assert_eq!(created.status, "passed", "{:?}", created.details);
+    let checks = created.details.as_ref().unwrap()["assertions"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert!(checks
+        .iter()
+        .any(|c| c["name"] == "three items" && c["passed"] == true));
+    assert_eq!(result.variables["order_id"], "42");
+    assert_eq!(result.variables["run"], "7");
+
+    // A failing pre-request script fails the request without sending it
+    let broken = &result.request_results[1];
+    assert_eq!(broken.status, "failed");
+    let issues = broken.details.as_ref().unwrap()["validation_issues"].to_string();
+    assert!(issues.contains("pre_request script failed"), "{}", issues);
+    assert!(issues.contains("no token"), "{}", issues);
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|r| r.url.path() != "/never"));
+}
+
+// Test that scripts cannot import modules or evaluate code
+#[test]
+fn test_script_sandbox() {
+    let dir = std::env::temp_dir().join(format!("qitops_script_sandbox_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let module = dir.join("helpers.rhai");
+    std::fs::write(&module, "fn secret() { 42 }").unwrap();
+
+    let variables = std::collections::HashMap::new();
+    let import = format!(
+        "import \"{}\" as helpers;\nvars.value = helpers::secret();",
+        module.with_extension("").display()
+    );
+    for script in [import.as_str(), "vars.value = eval(\"40 + 2\");"] {
+        let outcome = scripting::run(
+            ScriptPhase::PreRequest,
+            script,
+            &variables,
+            &json!({}),
+            None,
+        );
+        assert!(outcome.failed, "{}", script);
+        assert!(outcome.variables.is_empty());
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test capturing from headers, status, cookies and regex matches, and required captures
+#[tokio::test]
+async fn test_capture_sources() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/orders"))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .insert_header("Location", "/orders/42")
+                .insert_header("ETag", "\"v1\"")
+                .insert_header("Set-Cookie", "sid=s-1; Path=/")
+                .insert_header("Content-Type", "text/html")
+                .set_body_string(r#"<form><input name="csrf" value="tok-9"></form>"#),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/orders/42"))
+        .and(wiremock::matchers::header("if-none-match", "\"v1\""))
+        .and(wiremock::matchers::header("x-csrf-token", "tok-9"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 42})))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Captures",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {
+                "name": "Create order",
+                "id": "create",
+                "url": "{{base_url}}/orders",
+                "method": "POST",
+                "capture": {
+                    "location": "header:Location",
+                    "etag": "header:etag",
+                    "created_status": "status",
+                    "sid": "cookie:sid",
+                    "csrf": {"from": "regex:name=\"csrf\" value=\"([^\"]+)\"", "required": true},
+                    "optional": "header:X-Missing"
+                }
+            },
+            {
+                "name": "Get order",
+                "depends_on": ["create"],
+                "url": "{{base_url}}{{location}}",
+                "method": "GET",
+                "headers": {"If-None-Match": "{{etag}}", "X-CSRF-Token": "{{csrf}}"},
+                "capture": {"order_id": "jsonpath:$.id"}
+            },
+            {
+                "name": "Missing capture",
+                "url": "{{base_url}}/orders/42",
+                "method": "GET",
+                "headers": {"If-None-Match": "{{etag}}", "X-CSRF-Token": "{{csrf}}"},
+                "capture": {"next_page": {"from": "$.next", "required": true}}
+            }
+        ],
+        "run_options": {"stop_on_failure": false}
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    let statuses: Vec<&str> = result
+        .request_results
+        .iter()
+        .map(|r| r.status.as_str())
+        .collect();
+    assert_eq!(
+        statuses,
+        ["passed", "passed", "failed"],
+        "{:?}",
+        result.request_results
+    );
+    assert_eq!(result.variables["location"], "/orders/42");
+    assert_eq!(result.variables["created_status"], "201");
+    assert_eq!(result.variables["sid"], "s-1");
+    assert_eq!(result.variables["csrf"], "tok-9");
+    assert_eq!(result.variables["order_id"], "42");
+    assert!(!result.variables.contains_key("optional"));
+
+    let details = result.request_results[2].details.as_ref().unwrap();
+    let issues = details["validation_issues"].to_string();
+    assert!(
+        issues.contains("Required capture 'next_page'"),
+        "{}",
+        issues
+    );
+}
+
+// Test run_if/skip_if conditions, repeat_until polling and for_each loops
+#[tokio::test]
+async fn test_conditions_and_loops() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/jobs"))
+        .respond_with(ResponseTemplate::new(202).set_body_json(json!({
+            "id": "j1",
+            "items": [{"id": 1, "sku": "A"}, {"id": 2, "sku": "B"}]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/jobs/j1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"state": "running"})))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/jobs/j1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"state": "done"})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/items/\d+$"))
+        .respond_with(|request: &Request| {
+            ResponseTemplate::new(200).set_body_json(json!({"path": request.url.path()}))
+        })
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/stuck"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"state": "running"})))
+        .mount(&server)
+        .await;
+
+    let until_done = json!({
+        "conditions": [{"path": "$.state", "operator": "equals", "value": "done"}],
+        "interval_ms": 10,
+        "max_attempts": 5
+    });
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Jobs",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {
+                "name": "Start job",
+                "url": "{{base_url}}/jobs",
+                "method": "POST",
+                "capture": {"job_id": "$.id", "job_items": "$.items"}
+            },
+            {
+                "name": "Wait for job",
+                "url": "{{base_url}}/jobs/{{job_id}}",
+                "method": "GET",
+                "run_if": "\"job_id\" in vars",
+                "repeat_until": until_done
+            },
+            {
+                "name": "Check item",
+                "url": "{{base_url}}/items/{{item.id}}",
+                "method": "GET",
+                "for_each": {"items": "job_items"},
+                "capture": {"last_sku": "regex:\"path\":\"(/items/\\d+)\""}
+            },
+            {
+                "name": "Production only",
+                "url": "{{base_url}}/never",
+                "method": "GET",
+                "run_if": "environment == \"production\""
+            },
+            {
+                "name": "Unless started",
+                "url": "{{base_url}}/never",
+                "method": "GET",
+                "skip_if": "vars.job_id == \"j1\""
+            },
+            {
+                "name": "Stuck",
+                "url": "{{base_url}}/stuck",
+                "method": "GET",
+                "repeat_until": {
+                    "conditions": [{"path": "$.state", "operator": "equals", "value": "done"}],
+                    "interval_ms": 10,
+                    "max_attempts": 2
+                }
+            }
+        ],
+        "run_options": {"stop_on_failure": false}
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    let summary: Vec<(&str, &str)> = result
+        .request_results
+        .iter()
+        .map(|r| (r.name.as_str(), r.status.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("Start job", "passed"),
+            ("Wait for job (attempt 1)", "passed"),
+            ("Wait for job (attempt 2)", "passed"),
+            ("Wait for job (attempt 3)", "passed"),
+            ("Check item [1/2]", "passed"),
+            ("Check item [2/2]", "passed"),
+            ("Production only", "skipped"),
+            ("Unless started", "skipped"),
+            ("Stuck (attempt 1)", "passed"),
+            ("Stuck (attempt 2)", "failed")
+        ]
+    );
+    assert_eq!(result.status, "failed");
+    assert_eq!(result.variables["last_sku"], "/items/2");
+
+    let stuck = result.request_results[9].details.as_ref().unwrap();
+    let issues = stuck["validation_issues"].to_string();
+    assert!(
+        issues.contains("repeat_until conditions not met after 2 attempts"),
+        "{}",
+        issues
+    );
+    let requests = server.received_requests().await.unwrap();
+    assert!(requests.iter().all(|r| r.url.path() != "/never"));
+}
+
+// Test that only allowed environment variables are imported and secrets are masked in results and reports
+#[tokio::test]
+async fn test_env_import_and_secrets() {
+    std::env::set_var("QITOPS_TEST_API_TOKEN", "tok-7f3a9c2e");
+    std::env::set_var("QITOPS_TEST_REGION", "eu-west-1");
+    std::env::set_var("QITOPS_UNLISTED_VALUE", "not-imported");
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"token": "tok-7f3a9c2e", "id": 7})),
+        )
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Secrets",
+        "variables": {"base_url": server.uri()},
+        "import_env": {"allow": ["QITOPS_TEST_API_TOKEN"], "prefix": "QITOPS_TEST_R"},
+        "secrets": ["QITOPS_TEST_API_TOKEN"],
+        "requests": [
+            {
+                "name": "Me",
+                "url": "{{base_url}}/me?region={{QITOPS_TEST_REGION}}",
+                "method": "GET",
+                "headers": {"Authorization": "Bearer {{QITOPS_TEST_API_TOKEN}}"},
+                "expected_status": 200,
+                "capture": {"echoed": "$.token", "user_id": "$.id"}
+            }
+        ]
+    }))
+    .unwrap();
+
+    let runner = ApiCollectionRunner::new();
+    let result = runner.run_collection(&collection, "test").await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+
+    assert_eq!(result.variables["QITOPS_TEST_API_TOKEN"], "********");
+    assert_eq!(result.variables["QITOPS_TEST_REGION"], "eu-west-1");
+    assert_eq!(result.variables["echoed"], "********");
+    assert_eq!(result.variables["user_id"], "7");
+    assert!(!result.variables.contains_key("QITOPS_UNLISTED_VALUE"));
+    assert!(!result.variables.contains_key("PATH"));
+
+    let output = serde_json::to_string(&result).unwrap();
+    assert!(!output.contains("tok-7f3a9c2e"), "{}", output);
+
+    // Reports mask registered secrets in results that were not redacted yet
+    let dir = std::env::temp_dir().join(format!("qitops-secrets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let leaked = qitops::common::TestResult {
+        name: "Leaked tok-7f3a9c2e".to_string(),
+        status: "failed".to_string(),
+        duration: 0.1,
+        details: Some(json!({"headers": {"Authorization": "Bearer tok-7f3a9c2e"}})),
+        timestamp: "2026-01-01T00:00:00Z".to_string(),
+    };
+    for format in ["json", "xml", "html", "csv"] {
+        let report = dir.join(format!("report.{}", format));
+        qitops::reporting::ReportGenerator::new(format.parse().unwrap(), report.clone())
+            .generate(std::slice::from_ref(&leaked))
+            .unwrap();
+        let content = std::fs::read_to_string(&report).unwrap();
+        assert!(!content.contains("tok-7f3a9c2e"), "{}: {}", format, content);
+        assert!(content.contains("********"), "{}: {}", format, content);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that a value captured under a secret name is masked before it is logged
+#[test]
+fn test_secret_capture_registered() {
+    let body = json!({"token": "cap-91d2e7", "id": 7});
+    let response = CaptureResponse {
+        status: 200,
+        headers: &reqwest::header::HeaderMap::new(),
+        body: &body,
+        text: &body.to_string(),
+        cookies: &serde_json::Map::new(),
+    };
+    let captures = HashMap::from([
+        ("session_token".to_string(), Capture::from("$.token")),
+        ("user_id".to_string(), Capture::from("$.id")),
+    ]);
+    let (captured, _) = capture::evaluate(&captures, &response, &["session_token"]);
+    assert_eq!(captured["session_token"], "cap-91d2e7");
+    assert_eq!(
+        secrets::redact("Captured variable 'session_token' with value: cap-91d2e7"),
+        "Captured variable 'session_token' with value: ********"
+    );
+    assert_eq!(secrets::redact("7"), "7");
+}
+
+// Test that secrets too short to mask inside text are reported once
+#[test]
+fn test_short_secrets() {
+    let mut redactor = Redactor::default();
+    assert_eq!(
+        redactor.extend(["tok-7f3a".to_string(), "ab".to_string(), String::new()]),
+        1
+    );
+    assert_eq!(redactor.extend(["ab".to_string()]), 0);
+    assert_eq!(redactor.redact("tok-7f3a ab"), "******** ab");
+}
+
+// Test that no environment variables are imported without import_env
+#[tokio::test]
+async fn test_env_not_imported_by_default() {
+    std::env::set_var("QITOPS_DEFAULT_SECRET", "should-not-leak");
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "No env",
+        "requests": []
+    }))
+    .unwrap();
+
+    let runner = ApiCollectionRunner::new();
+    let result = runner.run_collection(&collection, "test").await.unwrap();
+    assert!(result.variables.is_empty(), "{:?}", result.variables);
+}
+
+// Test that collection defaults and request overrides drive retries, with the attempts reported
+#[tokio::test]
+async fn test_retry_policy() {
+    let server = MockServer::start().await;
+    for route in ["/flaky", "/flaky-auth"] {
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/job"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"state": "running"})))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/job"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"state": "done"})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/down"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Retries",
+        "variables": {"base_url": server.uri()},
+        "defaults": {"retries": 2, "retry": {"initial_delay_ms": 1, "max_delay_ms": 5}},
+        "run_options": {"stop_on_failure": false},
+        "requests": [
+            {"name": "Simple", "url": "{{base_url}}/flaky", "method": "GET", "expected_status": 200},
+            {
+                "name": "Captured",
+                "url": "{{base_url}}/flaky-auth",
+                "method": "GET",
+                "expected_status": 200,
+                "capture": {"ok": "$.ok"}
+            },
+            {
+                "name": "Polled",
+                "url": "{{base_url}}/job",
+                "method": "GET",
+                "capture": {"state": "$.state"},
+                "retry": {
+                    "max_retries": 5,
+                    "initial_delay_ms": 1,
+                    "retry_until": [{"path": "$.state", "operator": "equals", "value": "done"}]
+                }
+            },
+            {
+                "name": "No retries",
+                "url": "{{base_url}}/down",
+                "method": "GET",
+                "expected_status": 200,
+                "capture": {"state": "$.state"},
+                "retry": {"max_retries": 0}
+            }
+        ]
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    let attempts = |index: usize| {
+        result.request_results[index].details.as_ref().unwrap()["attempts"]
+            .as_array()
+            .unwrap()
+            .clone()
+    };
+
+    for index in [0, 1] {
+        assert_eq!(result.request_results[index].status, "passed");
+        let attempts = attempts(index);
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0]["status"], 503);
+        assert_eq!(attempts[0]["retry_reason"], "status 503");
+        assert_eq!(attempts[1]["status"], 200);
+    }
+
+    assert_eq!(result.request_results[2].status, "passed");
+    assert_eq!(attempts(2).len(), 3);
+    assert_eq!(result.variables["state"], "done");
+
+    assert_eq!(result.request_results[3].status, "failed");
+    assert_eq!(attempts(3).len(), 1);
+}
+
+// Test that folders inherit auth, defaults and variables down the tree
+#[tokio::test]
+async fn test_folder_inheritance() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/health"))
+        .and(header("X-Client", "qitops"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2/users"))
+        .and(header("Authorization", "Bearer users-token"))
+        .and(header("X-Client", "qitops"))
+        .and(header("X-Folder", "users"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 7})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2/users/7/roles"))
+        .and(header("Authorization", "Bearer admin-token"))
+        .and(header("X-Folder", "admin"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Folders",
+        "variables": {"base_url": server.uri(), "version": "v1"},
+        "defaults": {"headers": {"X-Client": "qitops"}},
+        "requests": [
+            {"name": "Health", "url": "{{base_url}}/{{version}}/health", "method": "GET", "expected_status": 200}
+        ],
+        "folders": [
+            {
+                "name": "Users",
+                "auth": {"type": "bearer", "token": "users-token"},
+                "defaults": {"headers": {"X-Folder": "users"}},
+                "variables": {"version": "v2"},
+                "requests": [
+                    {
+                        "name": "Get user", "id": "user", "url": "{{base_url}}/{{version}}/users",
+                        "method": "GET", "expected_status": 200, "capture": {"user_id": "$.id"}
+                    }
+                ],
+                "folders": [
+                    {
+                        "name": "Admin",
+                        "auth": {"type": "bearer", "token": "admin-token"},
+                        "defaults": {"headers": {"X-Folder": "admin"}},
+                        "requests": [
+                            {
+                                "name": "Roles", "url": "{{base_url}}/{{version}}/users/{{user_id}}/roles",
+                                "method": "GET", "depends_on": ["user"], "expected_status": 200
+                            }
+                        ]
+                    }
+                ]
+            }
+        ]
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    let names: Vec<&str> = result
+        .request_results
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(names, ["Health", "Get user", "Roles"]);
+
+    let graph = DependencyGraph::build(&collection).unwrap();
+    assert_eq!(graph.order(), [0, 1, 2]);
+}
+
+// Test that subset runs select by folder, request ID and tag and pull in dependencies
+#[tokio::test]
+async fn test_subset_runs() {
+    let server = MockServer::start().await;
+    Mock::given(path_regex("^/.*"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1})))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Subsets",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {"name": "Login", "id": "login", "url": "{{base_url}}/login", "method": "GET", "capture": {"token": "$.id"}},
+            {"name": "Health", "id": "health", "url": "{{base_url}}/health", "method": "GET", "tags": ["smoke"]}
+        ],
+        "folders": [
+            {
+                "name": "Orders",
+                "requests": [
+                    {"name": "List orders", "id": "orders", "url": "{{base_url}}/orders", "method": "GET", "depends_on": ["login"], "tags": ["smoke"]}
+                ],
+                "folders": [
+                    {
+                        "name": "Refunds",
+                        "requests": [
+                            {"name": "Refund", "id": "refund", "url": "{{base_url}}/refunds", "method": "POST", "depends_on": ["orders"]}
+                        ]
+                    }
+                ]
+            },
+            {
+                "name": "Users",
+                "requests": [
+                    {"name": "List users", "id": "users", "url": "{{base_url}}/users", "method": "GET", "tags": ["slow"]}
+                ]
+            }
+        ]
+    }))
+    .unwrap();
+
+    let run = |filter: CollectionFilter| {
+        let collection = collection.clone();
+        async move {
+            ApiCollectionRunner::new()
+                .with_filter(filter)
+                .run_collection(&collection, "test")
+                .await
+                .map(|result| {
+                    result
+                        .request_results
+                        .into_iter()
+                        .map(|r| r.name)
+                        .collect::<Vec<_>>()
+                })
+        }
+    };
+
+    let folder = run(CollectionFilter {
+        folders: vec!["Orders/Refunds".to_string()],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(folder, ["Login", "List orders", "Refund"]);
+
+    let parent = run(CollectionFilter {
+        folders: vec!["Orders".to_string()],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(parent, ["Login", "List orders", "Refund"]);
+
+    let requests = run(CollectionFilter {
+        requests: vec!["health".to_string(), "users".to_string()],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(requests, ["Health", "List users"]);
+
+    let tagged = run(CollectionFilter {
+        tags: vec!["smoke".to_string()],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(tagged, ["Login", "Health", "List orders"]);
+
+    let combined = run(CollectionFilter {
+        folders: vec!["Orders".to_string()],
+        tags: vec!["smoke".to_string()],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    assert_eq!(combined, ["Login", "List orders"]);
+
+    let error = run(CollectionFilter {
+        tags: vec!["missing".to_string()],
+        ..Default::default()
+    })
+    .await
+    .unwrap_err()
+    .to_string();
+    assert!(
+        error.contains("No requests match the selection"),
+        "{}",
+        error
+    );
+}
+
+// Test response JSON Schemas with shared schemas and $ref to schema files relative to the collection
+#[tokio::test]
+async fn test_json_schema() {
+    let server = MockServer::start().await;
+    let user = json!({"id": 1, "name": "Ada", "address": {"city": "London"}});
+    Mock::given(path("/users/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&user))
+        .mount(&server)
+        .await;
+    Mock::given(path("/users"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([user])))
+        .mount(&server)
+        .await;
+    Mock::given(path("/users/2"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"id": "2", "name": "Alan", "address": {}})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(path("/health"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("qitops_json_schema_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("schemas")).unwrap();
+    std::fs::write(
+        dir.join("schemas/user.json"),
+        json!({
+            "type": "object",
+            "required": ["id", "name", "address"],
+            "properties": {
+                "id": {"type": "integer"},
+                "name": {"type": "string"},
+                "address": {"$ref": "address.json"}
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("schemas/address.json"),
+        json!({"type": "object", "required": ["city"]}).to_string(),
+    )
+    .unwrap();
+
+    let collection_path = dir.join("api_collection_schemas.json");
+    let write_collection = |requests: serde_json::Value| {
+        std::fs::write(
+            &collection_path,
+            json!({
+                "name": "Schemas",
+                "variables": {"base_url": server.uri()},
+                "run_options": {"stop_on_failure": false},
+                "schemas": {
+                    "user_list": {"type": "array", "items": {"$ref": "schemas/user.json"}}
+                },
+                "requests": requests
+            })
+            .to_string(),
+        )
+        .unwrap();
+        ApiCollectionRunner::load_collection(&collection_path, &Environment::default()).unwrap()
+    };
+
+    let collection = write_collection(json!([
+        {"name": "User", "url": "{{base_url}}/users/1", "method": "GET", "json_schema": {"$ref": "schemas/user.json"}},
+        {"name": "Users", "url": "{{base_url}}/users", "method": "GET", "json_schema": {"$ref": "#/schemas/user_list"}},
+        {"name": "Invalid user", "url": "{{base_url}}/users/2", "method": "GET", "json_schema": {"$ref": "schemas/user.json"}},
+        {"name": "Health", "url": "{{base_url}}/health", "method": "GET", "json_schema": {"type": "object"}}
+    ]));
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    let statuses: Vec<&str> = result
+        .request_results
+        .iter()
+        .map(|r| r.status.as_str())
+        .collect();
+    assert_eq!(
+        statuses,
+        ["passed", "passed", "failed", "failed"],
+        "{:?}",
+        result.request_results
+    );
+    let issues = |index: usize| {
+        result.request_results[index].details.as_ref().unwrap()["validation_issues"].to_string()
+    };
+    assert!(
+        issues(2).contains("\\\"2\\\" is not of type \\\"integer\\\""),
+        "{}",
+        issues(2)
+    );
+    assert!(
+        issues(2).contains("\\\"city\\\" is a required property"),
+        "{}",
+        issues(2)
+    );
+    assert!(
+        issues(3).contains("json_schema requires a JSON response"),
+        "{}",
+        issues(3)
+    );
+
+    // Invalid schemas are reported before any request is sent
+    let sent = server.received_requests().await.unwrap().len();
+    let invalid = write_collection(json!([
+        {"name": "User", "url": "{{base_url}}/users/1", "method": "GET", "json_schema": {"type": 5}}
+    ]));
+    let error = ApiCollectionRunner::new()
+        .run_collection(&invalid, "test")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Invalid JSON Schema"), "{}", error);
+    assert_eq!(server.received_requests().await.unwrap().len(), sent);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that JSON checks apply to a JSON body served with another Content-Type
+#[tokio::test]
+async fn test_json_checks_on_mislabelled_json() {
+    let server = MockServer::start().await;
+    Mock::given(path("/users/1"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"{"id": 1, "name": "Ada"}"#, "text/plain"),
+        )
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Mislabelled",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {
+                "name": "User", "url": "{{base_url}}/users/1", "method": "GET",
+                "expected_body": {"id": 1},
+                "expected_body_type": "object",
+                "json_schema": {"type": "object", "required": ["name"]},
+                "capture": {"name": "$.name"}
+            }
+        ]
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    assert_eq!(result.variables["name"], "Ada");
+}
+
+// Test that data rows applied to a collection keep its schema file references working
+#[tokio::test]
+async fn test_data_driven_json_schema_file() {
+    let server = MockServer::start().await;
+    Mock::given(path_regex(r"^/users/\d+$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 1, "name": "Ada"})))
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("qitops_data_schema_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("schemas")).unwrap();
+    std::fs::write(
+        dir.join("schemas/user.json"),
+        json!({"type": "object", "required": ["id", "name"]}).to_string(),
+    )
+    .unwrap();
+    let collection_path = dir.join("api_collection_users.json");
+    std::fs::write(
+        &collection_path,
+        json!({
+            "name": "Users",
+            "variables": {"base_url": server.uri()},
+            "requests": [
+                {
+                    "name": "User {{id}}", "url": "{{base_url}}/users/{{id}}", "method": "GET",
+                    "json_schema": {"$ref": "schemas/user.json"}
+                }
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let collection =
+        ApiCollectionRunner::load_collection(&collection_path, &Environment::default()).unwrap();
+    let config: DataDrivenConfig = serde_json::from_value(json!({
+        "data_source": {"type": "inline", "data": [{"id": "1"}, {"id": "2"}]}
+    }))
+    .unwrap();
+    let runner = DataDrivenRunner::new(config).unwrap();
+    for row in runner.data_rows() {
+        let updated = runner
+            .apply_collection_replacements(&collection, row)
+            .unwrap();
+        assert_eq!(updated.base_dir, collection.base_dir);
+        let result = ApiCollectionRunner::new()
+            .run_collection(&updated, "test")
+            .await
+            .unwrap();
+        assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
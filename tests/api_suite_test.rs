@@ -0,0 +1,165 @@
+use qitops::api_suite::{ApiSuiteConfig, ApiSuiteRunner, TestFilter};
+use qitops::environment::Environment;
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn start_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/users"))
+        .and(header("authorization", "Bearer secret"))
+        .and(header("x-client", "qitops"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([{"id": 1}]))
+                .set_delay(Duration::from_millis(100)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "ok"})))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn suite_json(base_url: String) -> serde_json::Value {
+    json!({
+        "name": "User service",
+        "base_url": base_url,
+        "headers": {"x-client": "qitops"},
+        "auth": {"type": "bearer", "token": "secret"},
+        "retry": {"max_retries": 0},
+        "concurrency": 4,
+        "tests": [
+            {
+                "name": "List users",
+                "tags": ["users", "smoke"],
+                "url": "/users",
+                "method": "GET",
+                "expected_status": 200,
+                "assertions": [{"path": "$", "operator": "length", "value": 1}]
+            },
+            {
+                "name": "Health check",
+                "tags": ["smoke"],
+                "url": "health",
+                "method": "GET",
+                "auth": {"type": "api_key", "key_name": "x-api-key", "key_value": "other"},
+                "assertions": [{"path": "$.status", "operator": "equals", "value": "ok"}]
+            },
+            {
+                "name": "Missing user",
+                "tags": ["users", "slow"],
+                "url": "/users/404",
+                "method": "GET",
+                "expected_status": 404
+            }
+        ]
+    })
+}
+
+fn suite(base_url: String) -> ApiSuiteConfig {
+    serde_json::from_value(suite_json(base_url)).unwrap()
+}
+
+// Test that suite settings apply to every test and results keep suite order
+#[tokio::test]
+async fn test_suite_shared_settings() {
+    let server = start_server().await;
+    let results = ApiSuiteRunner::new()
+        .run_suite(&suite(server.uri()), "test")
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["List users", "Health check", "Missing user"]);
+    for result in &results {
+        assert_eq!(result.status, "passed", "{:?}", result.details);
+    }
+    assert_eq!(
+        results[0].details.as_ref().unwrap()["tags"],
+        json!(["users", "smoke"])
+    );
+}
+
+// Test tag and name filters
+#[tokio::test]
+async fn test_suite_filters() {
+    let server = start_server().await;
+    let suite = suite(server.uri());
+    let run = |filter: TestFilter| {
+        let suite = suite.clone();
+        async move {
+            ApiSuiteRunner::new()
+                .with_filter(filter)
+                .run_suite(&suite, "test")
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.name)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let smoke = run(TestFilter {
+        tags: vec!["smoke".to_string()],
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(smoke, ["List users", "Health check"]);
+
+    let users_not_slow = run(TestFilter {
+        tags: vec!["users".to_string()],
+        exclude_tags: vec!["slow".to_string()],
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(users_not_slow, ["List users"]);
+
+    let by_name = run(TestFilter {
+        name: Some("USER".to_string()),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(by_name, ["List users", "Missing user"]);
+}
+
+// Test loading a suite file and overriding its concurrency
+#[tokio::test]
+async fn test_suite_from_file() {
+    let server = start_server().await;
+    let mut config = suite_json(server.uri());
+    let users_test = config["tests"][0].clone();
+    config["tests"] = json!([users_test.clone(), users_test.clone(), users_test]);
+
+    let dir = std::env::temp_dir().join(format!("qitops-suite-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("api_suite_users.json");
+    std::fs::write(&path, config.to_string()).unwrap();
+    assert!(ApiSuiteConfig::is_suite_file(&path).unwrap());
+    let suite = ApiSuiteConfig::load(&path, &Environment::new("test")).unwrap();
+
+    // Three 100ms requests run one after the other
+    let start = std::time::Instant::now();
+    let results = ApiSuiteRunner::new()
+        .with_concurrency(Some(1))
+        .run_suite(&suite, "test")
+        .await
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    assert!(results.iter().all(|r| r.status == "passed"));
+
+    // ...and together with the suite concurrency
+    let start = std::time::Instant::now();
+    ApiSuiteRunner::new()
+        .run_suite(&suite, "test")
+        .await
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_millis(300));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,553 @@
+use qitops::api::{ApiTestConfig, ApiTestRunner};
+use qitops::common::TestRunner;
+use qitops::retry::RetryConfig;
+use serde_json::json;
+use wiremock::matchers::{body_string, body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Test that every failing check is reported instead of only the first one
+#[tokio::test]
+async fn test_all_failed_checks_are_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/users/1"))
+        .respond_with(
+            ResponseTemplate::new(404)
+                .insert_header("x-api-version", "1")
+                .set_body_json(json!({"id": 2, "name": "Other"})),
+        )
+        .mount(&server)
+        .await;
+
+    let config = json!({
+        "name": "Get user",
+        "description": "Collects every failure",
+        "environment": "test",
+        "url": format!("{}/users/1", server.uri()),
+        "method": "GET",
+        "expected_status": 200,
+        "expected_headers": {"x-api-version": "2"},
+        "expected_body": {"id": 1, "name": "Other"},
+        "assertions": [
+            {"path": "$.name", "operator": "equals", "value": "User"}
+        ],
+        "retry": {"max_retries": 0}
+    });
+
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+
+    let details = result.details.unwrap();
+    let checks = details["assertions"].as_array().unwrap();
+    let failed: Vec<&str> = checks
+        .iter()
+        .filter(|c| !c["passed"].as_bool().unwrap())
+        .map(|c| c["name"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(checks.len(), 5);
+    assert_eq!(
+        failed,
+        vec!["status", "header:x-api-version", "body:id", "$.name equals"]
+    );
+    assert_eq!(checks[0]["actual"], json!(404));
+}
+
+// Test XPath, text and binary assertions on non-JSON responses
+#[tokio::test]
+async fn test_non_json_response_assertions() {
+    let server = MockServer::start().await;
+    Mock::given(path("/soap"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><Result code="7">OK</Result></soap:Body></soap:Envelope>"#,
+            "text/xml; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(path("/health"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("status: UP (v1.2.3)", "text/plain"))
+        .mount(&server)
+        .await;
+    Mock::given(path("/download"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0u8, 159, 146, 150]))
+        .mount(&server)
+        .await;
+
+    let runner = ApiTestRunner::new();
+    let soap = json!({
+        "name": "SOAP", "description": "", "environment": "test",
+        "url": format!("{}/soap", server.uri()), "method": "GET",
+        "assertions": [
+            {"path": "//s:Body/Result", "operator": "equals", "value": "OK",
+             "namespaces": {"s": "http://schemas.xmlsoap.org/soap/envelope/"}},
+            {"path": "number(//Result/@code)", "operator": "gt", "value": 5}
+        ]
+    });
+    let text = json!({
+        "name": "Health", "description": "", "environment": "test",
+        "url": format!("{}/health", server.uri()), "method": "GET",
+        "assertions": [
+            {"operator": "contains", "value": "UP"},
+            {"operator": "matches", "value": "v\\d+\\.\\d+\\.\\d+"}
+        ]
+    });
+    let binary = json!({
+        "name": "Download", "description": "", "environment": "test",
+        "url": format!("{}/download", server.uri()), "method": "GET",
+        "response_type": "binary",
+        "assertions": [
+            {"operator": "size", "value": 4},
+            {"operator": "sha256", "value": "b02a591131217cb579165aeccf0d94569acffb9934c84d6c813d77e3abedd233"}
+        ]
+    });
+
+    let soap_result = runner.run(&soap).await.unwrap();
+    assert_eq!(soap_result.status, "passed", "{:?}", soap_result.details);
+
+    let text_result = runner.run(&text).await.unwrap();
+    assert_eq!(text_result.status, "passed", "{:?}", text_result.details);
+
+    let binary_result = runner.run(&binary).await.unwrap();
+    assert_eq!(
+        binary_result.status, "passed",
+        "{:?}",
+        binary_result.details
+    );
+    assert_eq!(binary_result.details.unwrap()["body"]["size"], json!(4));
+}
+
+// Test that JSON checks apply to a JSON body served with another Content-Type
+// unless a non-JSON response_type is set explicitly
+#[tokio::test]
+async fn test_json_checks_on_mislabelled_json() {
+    let server = MockServer::start().await;
+    Mock::given(path("/users/1"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(r#"{"id": 1, "name": "User"}"#, "text/plain"),
+        )
+        .mount(&server)
+        .await;
+
+    let mut config = json!({
+        "name": "Get user", "description": "", "environment": "test",
+        "url": format!("{}/users/1", server.uri()), "method": "GET",
+        "expected_body": {"id": 1},
+        "json_schema": {"type": "object", "required": ["name"]}
+    });
+
+    let runner = ApiTestRunner::new();
+    let result = runner.run(&config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    // Assertions alone see the parsed document too
+    let assertions_only = json!({
+        "name": "Get user", "description": "", "environment": "test",
+        "url": format!("{}/users/1", server.uri()), "method": "GET",
+        "assertions": [{"path": "$.id", "operator": "equals", "value": 1}]
+    });
+    let result = runner.run(&assertions_only).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    config["response_type"] = json!("text");
+    let result = runner.run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+    let details = result.details.unwrap();
+    assert_eq!(details["assertions"][0]["name"], "body");
+}
+
+// Test that every assertion is reported when a JSON response does not parse
+#[tokio::test]
+async fn test_assertions_on_invalid_json() {
+    let server = MockServer::start().await;
+    Mock::given(path("/broken"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("{\"id\": 1,", "application/json"))
+        .mount(&server)
+        .await;
+
+    let config = json!({
+        "name": "Broken", "description": "", "environment": "test",
+        "url": format!("{}/broken", server.uri()), "method": "GET",
+        "assertions": [
+            {"path": "$.id", "operator": "equals", "value": 1},
+            {"path": "$.name", "operator": "exists"},
+            {"operator": "size", "value": 9}
+        ]
+    });
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+
+    let details = result.details.unwrap();
+    let checks = details["assertions"].as_array().unwrap();
+    let names: Vec<&str> = checks.iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["body", "$.id equals", "$.name exists", "body size"]);
+    assert_eq!(checks[1]["message"], "Response is not valid JSON");
+    assert_eq!(checks[2]["passed"], json!(false));
+    assert_eq!(checks[3]["passed"], json!(true));
+}
+
+// Test form, multipart, raw and binary_file request bodies
+#[tokio::test]
+async fn test_request_body_types() {
+    let server = MockServer::start().await;
+    Mock::given(path("/form"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(body_string("user=jane+doe&age=42"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(path("/upload"))
+        .and(body_string_contains("name=\"kind\""))
+        .and(body_string_contains("filename=\"report.csv\""))
+        .and(body_string_contains("id,total"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(path("/soap"))
+        .and(header("content-type", "application/soap+xml"))
+        .and(body_string("<Ping/>"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(path("/blob"))
+        .and(header("content-type", "application/octet-stream"))
+        .and(body_string("id,total\n1,10\n"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let dir = std::env::temp_dir().join(format!("qitops-body-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("report.csv"), "id,total\n1,10\n").unwrap();
+
+    let config = |name: &str, extra: serde_json::Value| {
+        let mut config = json!({
+            "name": name, "description": "", "environment": "test",
+            "url": format!("{}/{}", server.uri(), name), "method": "POST",
+            "expected_status": 200,
+            "retry": {"max_retries": 0}
+        });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value::<ApiTestConfig>(config).unwrap()
+    };
+
+    let form = config(
+        "form",
+        json!({"body_type": "form", "body": {"user": "jane doe", "age": 42}}),
+    );
+    let mut upload = config(
+        "upload",
+        json!({"body_type": "multipart", "body": [
+            {"name": "kind", "value": "monthly"},
+            {"name": "file", "file": "report.csv", "content_type": "text/csv"}
+        ]}),
+    );
+    upload.resolve_paths(&dir);
+    let soap = config(
+        "soap",
+        json!({"body_type": "raw", "body": "<Ping/>",
+               "headers": {"Content-Type": "application/soap+xml"}}),
+    );
+    let mut blob = config(
+        "blob",
+        json!({"body_type": "binary_file", "body": "report.csv"}),
+    );
+    blob.resolve_paths(&dir);
+
+    let runner = ApiTestRunner::new();
+    for test_config in [form, upload, soap, blob] {
+        let result = runner.run(&test_config).await.unwrap();
+        assert_eq!(
+            result.status, "passed",
+            "{}: {:?}",
+            result.name, result.details
+        );
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test the per-phase timing breakdown and phase limits
+#[tokio::test]
+async fn test_timing_breakdown() {
+    let server = MockServer::start().await;
+    Mock::given(path("/slow"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"ok": true}))
+                .set_delay(std::time::Duration::from_millis(150)),
+        )
+        .mount(&server)
+        .await;
+
+    // A host name, so the request needs a DNS lookup
+    let url = server.uri().replace("127.0.0.1", "localhost");
+    let config = json!({
+        "name": "Slow endpoint", "description": "", "environment": "test",
+        "url": format!("{}/slow", url), "method": "GET",
+        "max_dns_ms": 5000,
+        "max_connect_ms": 5000,
+        "max_ttfb_ms": 50,
+        "max_download_ms": 5000,
+        "retry": {"max_retries": 0}
+    });
+
+    let runner = ApiTestRunner::new();
+    let result = runner.run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+
+    let details = result.details.unwrap();
+    let timing = &details["timing"];
+    assert!(timing["dns_ms"].as_f64().unwrap() > 0.0, "{}", timing);
+    assert!(timing["connect_ms"].as_f64().unwrap() > 0.0, "{}", timing);
+    assert!(timing["ttfb_ms"].as_f64().unwrap() >= 150.0);
+    let phases: f64 = ["dns_ms", "connect_ms", "ttfb_ms", "download_ms"]
+        .iter()
+        .map(|phase| timing[phase].as_f64().unwrap())
+        .sum();
+    assert!((timing["total_ms"].as_f64().unwrap() - phases).abs() < 0.001);
+
+    let checks = details["assertions"].as_array().unwrap();
+    let ttfb = checks.iter().find(|c| c["name"] == "timing:ttfb").unwrap();
+    let download = checks
+        .iter()
+        .find(|c| c["name"] == "timing:download")
+        .unwrap();
+    assert_eq!(ttfb["passed"], json!(false));
+    assert_eq!(download["passed"], json!(true));
+    for phase in ["timing:dns", "timing:connect"] {
+        let check = checks.iter().find(|c| c["name"] == phase).unwrap();
+        assert_eq!(check["passed"], json!(true));
+    }
+
+    // The next request reuses the kept-alive connection
+    let result = runner.run(&config).await.unwrap();
+    let timing = &result.details.unwrap()["timing"];
+    assert_eq!(timing["dns_ms"], json!(0.0));
+    assert_eq!(timing["connect_ms"], json!(0.0));
+}
+
+// Test that snapshots are written, compared with ignored paths and updated
+#[tokio::test]
+async fn test_snapshot_mode() {
+    let server = MockServer::start().await;
+    let responses = [
+        (
+            "v1",
+            json!({"id": 1, "name": "Widget", "updated_at": "2024-01-01"}),
+        ),
+        (
+            "v2",
+            json!({"id": 2, "name": "Widget", "updated_at": "2024-02-01"}),
+        ),
+        (
+            "v3",
+            json!({"id": 3, "name": "Gadget", "updated_at": "2024-03-01"}),
+        ),
+    ];
+    for (version, body) in &responses {
+        Mock::given(path(format!("/{}/item", version)))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-api-version", *version)
+                    .set_body_json(body),
+            )
+            .mount(&server)
+            .await;
+    }
+
+    let dir = std::env::temp_dir().join(format!("qitops-snapshots-{}", std::process::id()));
+    let config = |version: &str| {
+        let mut config: ApiTestConfig = serde_json::from_value(json!({
+            "name": "Get item", "description": "", "environment": "test",
+            "url": format!("{}/{}/item", server.uri(), version), "method": "GET",
+            "snapshot": {
+                "dir": "snapshots",
+                "headers": ["content-type"],
+                "ignore_paths": ["$.id", "$.updated_at"]
+            },
+            "retry": {"max_retries": 0}
+        }))
+        .unwrap();
+        config.resolve_paths(&dir);
+        config
+    };
+    let checks = |result: &qitops::common::TestResult| {
+        result.details.as_ref().unwrap()["assertions"]
+            .as_array()
+            .unwrap()
+            .clone()
+    };
+
+    // The first run stores the snapshot
+    let runner = ApiTestRunner::new();
+    let result = runner.run(&config("v1")).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+    let stored: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(dir.join("snapshots/get_item.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stored["body"]["id"], json!("<ignored>"));
+    assert_eq!(stored["headers"]["content-type"], json!("application/json"));
+
+    // Ignored paths may change
+    let result = runner.run(&config("v2")).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    // Other changes are reported as differences
+    let result = runner.run(&config("v3")).await.unwrap();
+    assert_eq!(result.status, "failed");
+    let failed: Vec<serde_json::Value> = checks(&result)
+        .into_iter()
+        .filter(|c| c["passed"] == json!(false))
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["name"], json!("snapshot:$.name"));
+    assert_eq!(failed[0]["expected"], json!("Widget"));
+    assert_eq!(failed[0]["actual"], json!("Gadget"));
+
+    // Updating rewrites the stored copy
+    let updater = ApiTestRunner::new().with_update_snapshots(true);
+    let result = updater.run(&config("v3")).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+    let result = runner.run(&config("v3")).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that the default retry policy matches an empty retry block and an API test without one
+#[test]
+fn test_retry_defaults() {
+    let empty: RetryConfig = serde_json::from_value(json!({})).unwrap();
+    let config: ApiTestConfig = serde_json::from_value(json!({
+        "name": "Defaults", "description": "", "environment": "test",
+        "url": "http://localhost", "method": "GET"
+    }))
+    .unwrap();
+    for policy in [
+        RetryConfig::default(),
+        RetryConfig::with_retries(3),
+        config.retry,
+    ] {
+        assert_eq!(policy.max_retries, empty.max_retries);
+        assert_eq!(policy.retry_status_codes, [408, 429, 500, 502, 503, 504]);
+        assert!(policy.respect_retry_after);
+        assert_eq!(policy.max_retry_after_ms, empty.max_retry_after_ms);
+    }
+    assert_eq!(empty.max_retries, 3);
+}
+
+// Test that Retry-After is honoured and every attempt is recorded
+#[tokio::test]
+async fn test_retry_after_and_attempt_history() {
+    let server = MockServer::start().await;
+    Mock::given(path("/limited"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/limited"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"ok": true})))
+        .mount(&server)
+        .await;
+
+    let config = json!({
+        "name": "Rate limited", "description": "", "environment": "test",
+        "url": format!("{}/limited", server.uri()), "method": "GET",
+        "expected_status": 200,
+        "retry": {"max_retries": 2, "initial_delay_ms": 1, "max_retry_after_ms": 250}
+    });
+
+    let start = std::time::Instant::now();
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+    // The 30 second Retry-After is capped by max_retry_after_ms
+    assert!(start.elapsed() >= std::time::Duration::from_millis(250));
+
+    let details = result.details.unwrap();
+    let attempts = details["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0]["status"], json!(429));
+    assert_eq!(attempts[0]["delay_ms"], json!(250));
+    assert_eq!(attempts[0]["retry_reason"], json!("status 429"));
+    assert_eq!(attempts[1]["status"], json!(200));
+    assert_eq!(attempts[1]["delay_ms"], json!(0));
+    assert!(attempts[1]["latency_ms"].is_number());
+
+    // Exhausted retries report the last response instead of an error
+    let config = json!({
+        "name": "Always limited", "description": "", "environment": "test",
+        "url": format!("{}/missing", server.uri()), "method": "GET",
+        "expected_status": 200,
+        "retry": {"max_retries": 1, "initial_delay_ms": 1, "retry_status_codes": [404]}
+    });
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+    let details = result.details.unwrap();
+    assert_eq!(details["status_code"], json!(404));
+    assert_eq!(details["attempts"].as_array().unwrap().len(), 2);
+}
+
+// Test polling until the response body satisfies retry_until
+#[tokio::test]
+async fn test_retry_until_polling() {
+    let server = MockServer::start().await;
+    Mock::given(path("/jobs/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "pending"})))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/jobs/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "done"})))
+        .mount(&server)
+        .await;
+
+    Mock::given(path("/jobs/2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "pending"})))
+        .mount(&server)
+        .await;
+
+    let config = |job: u32| {
+        json!({
+            "name": "Job status", "description": "", "environment": "test",
+            "url": format!("{}/jobs/{}", server.uri(), job), "method": "GET",
+            "retry": {
+                "max_retries": 3,
+                "initial_delay_ms": 1,
+                "max_delay_ms": 5,
+                "retry_until": [{"path": "$.status", "operator": "equals", "value": "done"}]
+            }
+        })
+    };
+
+    let result = ApiTestRunner::new().run(&config(1)).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+    let details = result.details.unwrap();
+    assert_eq!(details["body"]["status"], json!("done"));
+    let attempts = details["attempts"].as_array().unwrap();
+    assert_eq!(attempts.len(), 3);
+    assert_eq!(
+        attempts[0]["retry_reason"],
+        json!("retry_until condition not met")
+    );
+
+    // A condition that is never met is reported as a failed check
+    let result = ApiTestRunner::new().run(&config(2)).await.unwrap();
+    assert_eq!(result.status, "failed");
+    let details = result.details.unwrap();
+    assert_eq!(details["body"]["status"], json!("pending"));
+    assert_eq!(details["attempts"].as_array().unwrap().len(), 4);
+    let until = details["assertions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == "retry_until")
+        .unwrap()
+        .clone();
+    assert_eq!(until["passed"], json!(false));
+}
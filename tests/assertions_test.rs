@@ -0,0 +1,83 @@
+use qitops::assertions::{evaluate_all, Assertion, AssertionOperator};
+use serde_json::json;
+
+fn assertion(path: &str, operator: AssertionOperator, value: serde_json::Value) -> Assertion {
+    Assertion {
+        path: path.to_string(),
+        operator,
+        value: Some(value),
+        namespaces: None,
+    }
+}
+
+// Test that every operator passes against a matching body
+#[test]
+fn test_assertion_operators_pass() {
+    let body = json!({
+        "data": {
+            "id": 42,
+            "name": "Widget",
+            "tags": ["new", "sale"],
+            "price": 9.5,
+            "status": "active",
+            "owner": null
+        }
+    });
+
+    let assertions = vec![
+        Assertion {
+            path: "$.data.id".to_string(),
+            operator: AssertionOperator::Exists,
+            value: None,
+            namespaces: None,
+        },
+        Assertion {
+            path: "$.data.missing".to_string(),
+            operator: AssertionOperator::NotExists,
+            value: None,
+            namespaces: None,
+        },
+        assertion("$.data.owner", AssertionOperator::Equals, json!(null)),
+        assertion("$.data.name", AssertionOperator::Equals, json!("Widget")),
+        assertion("$.data.name", AssertionOperator::NotEquals, json!("Gadget")),
+        assertion("$.data.tags", AssertionOperator::Contains, json!("sale")),
+        assertion("$.data.name", AssertionOperator::Matches, json!("^Wid")),
+        assertion("$.data.price", AssertionOperator::Gt, json!(9)),
+        assertion("$.data.price", AssertionOperator::Lte, json!(9.5)),
+        assertion("$.data.tags", AssertionOperator::Length, json!(2)),
+        assertion("$.data.id", AssertionOperator::TypeOf, json!("integer")),
+        assertion(
+            "$.data.status",
+            AssertionOperator::OneOf,
+            json!(["active", "pending"]),
+        ),
+    ];
+
+    let results = evaluate_all(&assertions, &body);
+    for result in &results {
+        assert!(result.passed, "{} should pass: {:?}", result.name, result);
+    }
+}
+
+// Test that failures carry the actual value and a message
+#[test]
+fn test_assertion_failure_details() {
+    let body = json!({"items": [{"id": 1}, {"id": 2}]});
+
+    let results = evaluate_all(
+        &[
+            assertion("$.items", AssertionOperator::Length, json!(3)),
+            assertion("$.items[*].id", AssertionOperator::Equals, json!([1, 3])),
+            assertion("$.count", AssertionOperator::Gt, json!(0)),
+        ],
+        &body,
+    );
+
+    assert!(results.iter().all(|r| !r.passed));
+    assert_eq!(results[1].actual, json!([1, 2]));
+    assert!(results[2]
+        .message
+        .as_deref()
+        .unwrap()
+        .contains("matched no value"));
+}
@@ -0,0 +1,220 @@
+use qitops::api::{ApiTestConfig, ApiTestRunner};
+use qitops::client::{ClientOptions, ProxyConfig, TlsConfig};
+use qitops::common::{load_config, TestRunner};
+use qitops::environment::Environment;
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose,
+};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Generate a CA, a server certificate for localhost and a client certificate
+/// into `dir`, returning the rustls server configuration requiring client certificates
+fn generate_pki(dir: &Path) -> ServerConfig {
+    let ca_key = KeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "QitOps Test CA");
+    let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+
+    let server_key = KeyPair::generate().unwrap();
+    let server_cert = CertificateParams::new(vec!["localhost".to_string()])
+        .unwrap()
+        .signed_by(&server_key, &ca_cert, &ca_key)
+        .unwrap();
+
+    let client_key = KeyPair::generate().unwrap();
+    let mut client_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    client_params
+        .distinguished_name
+        .push(DnType::CommonName, "qitops-client");
+    client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let client_cert = client_params
+        .signed_by(&client_key, &ca_cert, &ca_key)
+        .unwrap();
+
+    std::fs::write(dir.join("ca.pem"), ca_cert.pem()).unwrap();
+    std::fs::write(dir.join("client.pem"), client_cert.pem()).unwrap();
+    std::fs::write(dir.join("client.key"), client_key.serialize_pem()).unwrap();
+
+    let mut roots = RootCertStore::empty();
+    roots.add(ca_cert.der().clone()).unwrap();
+    let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+        .build()
+        .unwrap();
+
+    ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(
+            vec![CertificateDer::from(server_cert.der().to_vec())],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(server_key.serialize_der())),
+        )
+        .unwrap()
+}
+
+/// Start an HTTPS server that requires a client certificate and answers every request
+async fn start_mtls_server(config: ServerConfig) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let body = r#"{"secure":true}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    port
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qitops-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn api_config(url: String) -> serde_json::Value {
+    json!({
+        "name": "Secure ping",
+        "description": "",
+        "environment": "test",
+        "url": url,
+        "method": "GET",
+        "expected_status": 200,
+        "assertions": [{"path": "$.secure", "operator": "equals", "value": true}],
+        "retry": {"max_retries": 0}
+    })
+}
+
+// Test mutual TLS with certificate paths relative to the configuration file
+#[tokio::test]
+async fn test_mutual_tls_from_config() {
+    let dir = temp_dir("mtls");
+    let port = start_mtls_server(generate_pki(&dir)).await;
+
+    let mut config = api_config(format!("https://localhost:{}/ping", port));
+    config["tls"] = json!({
+        "ca_cert": "ca.pem",
+        "client_cert": "client.pem",
+        "client_key": "client.key"
+    });
+    let config_path = dir.join("api_mtls.json");
+    std::fs::write(&config_path, config.to_string()).unwrap();
+
+    let test_config: ApiTestConfig = load_config(&config_path, &Environment::default()).unwrap();
+    let result = ApiTestRunner::new().run(&test_config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+    // Connect covers the TLS handshake
+    assert!(
+        result.details.unwrap()["timing"]["connect_ms"]
+            .as_f64()
+            .unwrap()
+            > 0.0
+    );
+
+    // Without a client certificate the server rejects the handshake
+    let mut config = api_config(format!("https://localhost:{}/ping", port));
+    config["tls"] = json!({"ca_cert": dir.join("ca.pem")});
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that command line overrides apply to configs without TLS settings
+#[tokio::test]
+async fn test_client_option_overrides() {
+    let dir = temp_dir("tls-override");
+    let port = start_mtls_server(generate_pki(&dir)).await;
+    let config = api_config(format!("https://localhost:{}/ping", port));
+
+    // The private CA is unknown to the default trust store
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+
+    let client_cert = |insecure: bool| TlsConfig {
+        ca_cert: None,
+        client_cert: Some(dir.join("client.pem").to_string_lossy().to_string()),
+        client_key: Some(dir.join("client.key").to_string_lossy().to_string()),
+        insecure_skip_verify: insecure,
+    };
+
+    let runner = ApiTestRunner::new().with_client_options(ClientOptions {
+        tls: Some(TlsConfig {
+            ca_cert: Some(dir.join("ca.pem").to_string_lossy().to_string()),
+            ..client_cert(false)
+        }),
+        proxy: None,
+    });
+    let result = runner.run(&config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    let runner = ApiTestRunner::new().with_client_options(ClientOptions {
+        tls: Some(client_cert(true)),
+        proxy: None,
+    });
+    let result = runner.run(&config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that requests are sent through the configured proxy
+#[tokio::test]
+async fn test_requests_use_proxy() {
+    let proxy = MockServer::start().await;
+    Mock::given(path("/ping"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"secure": true})))
+        .expect(1)
+        .mount(&proxy)
+        .await;
+
+    let runner = ApiTestRunner::new().with_client_options(ClientOptions {
+        tls: None,
+        proxy: Some(ProxyConfig {
+            url: proxy.uri(),
+            ..Default::default()
+        }),
+    });
+    let result = runner
+        .run(&api_config(
+            "http://service.qitops.invalid/ping".to_string(),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+}
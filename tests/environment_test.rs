@@ -0,0 +1,300 @@
+use qitops::api::ApiTestConfig;
+use qitops::api_collection::{ApiCollection, ApiCollectionRunner};
+use qitops::common::load_config;
+use qitops::environment::{self, Environment};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qitops_env_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn variables(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+// Test loading flat and named environments from JSON, YAML and .env files
+#[test]
+fn test_env_files() {
+    let dir = temp_dir("files");
+    let json_file = dir.join("env.json");
+    std::fs::write(
+        &json_file,
+        r#"{"base_url": "http://json", "port": 8080, "debug": true}"#,
+    )
+    .unwrap();
+    let yaml_file = dir.join("envs.yaml");
+    std::fs::write(
+        &yaml_file,
+        "staging:\n  base_url: http://staging\nproduction:\n  base_url: http://production\n",
+    )
+    .unwrap();
+    let dotenv_file = dir.join(".env.local");
+    std::fs::write(
+        &dotenv_file,
+        "# comment\nexport API_KEY=abc123\nGREETING=\"hello world\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        environment::load_file(&json_file, "any").unwrap(),
+        variables(&[
+            ("base_url", "http://json"),
+            ("port", "8080"),
+            ("debug", "true")
+        ])
+    );
+    assert_eq!(
+        environment::load_file(&yaml_file, "staging").unwrap(),
+        variables(&[("base_url", "http://staging")])
+    );
+    assert_eq!(
+        environment::load_file(&dotenv_file, "any").unwrap(),
+        variables(&[("API_KEY", "abc123"), ("GREETING", "hello world")])
+    );
+
+    let error = environment::load_file(&yaml_file, "qa")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("no environment 'qa' (found production, staging)"),
+        "{}",
+        error
+    );
+    let unsupported = dir.join("env.toml");
+    std::fs::write(&unsupported, "").unwrap();
+    assert!(environment::load_file(&unsupported, "any").is_err());
+
+    assert_eq!(
+        environment::parse_override("token=a=b").unwrap(),
+        ("token".to_string(), "a=b".to_string())
+    );
+    assert!(environment::parse_override("token").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test precedence: command line > environment file > configuration environment > configuration variables
+#[test]
+fn test_precedence() {
+    let dir = temp_dir("precedence");
+    let env_file = dir.join("staging.env");
+    std::fs::write(&env_file, "host=file-host\nport=9000\n").unwrap();
+
+    let environment = Environment::new("staging")
+        .with_file(&env_file)
+        .unwrap()
+        .with_overrides(variables(&[("port", "9999")]));
+    let config_variables = variables(&[
+        ("scheme", "http"),
+        ("host", "variables-host"),
+        ("path", "variables"),
+    ]);
+    let environments = HashMap::from([(
+        "staging".to_string(),
+        variables(&[("host", "env-host"), ("path", "staging")]),
+    )]);
+
+    assert_eq!(
+        environment.resolve(Some(&config_variables), Some(&environments)),
+        variables(&[
+            ("scheme", "http"),
+            ("host", "file-host"),
+            ("path", "staging"),
+            ("port", "9999")
+        ])
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that load_config resolves {{name}} and ${NAME} placeholders for the selected environment
+#[test]
+fn test_load_config_resolves_environment() {
+    let dir = temp_dir("load_config");
+    let config_path = dir.join("api_users.json");
+    std::fs::write(
+        &config_path,
+        json!({
+            "name": "Users",
+            "description": "",
+            "url": "{{base_url}}/users/{{user_id}}",
+            "method": "GET",
+            "headers": {
+                "X-Api-Key": "${QITOPS_ENV_TEST_KEY}",
+                "X-Secret": "${QITOPS_ENV_TEST_SECRET}",
+                "X-Unknown": "{{unknown}}"
+            },
+            "variables": {"base_url": "http://localhost:1", "user_id": "1"},
+            "environments": {"staging": {"base_url": "http://staging.local"}}
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let env_file = dir.join("env.yml");
+    std::fs::write(&env_file, "staging:\n  QITOPS_ENV_TEST_KEY: from-file\n").unwrap();
+
+    let environment = Environment::new("staging")
+        .with_file(&env_file)
+        .unwrap()
+        .with_overrides(variables(&[
+            ("user_id", "42"),
+            ("QITOPS_ENV_TEST_SECRET", "p$ss{${QITOPS_ENV_TEST_KEY}}"),
+        ]));
+    let config: ApiTestConfig = load_config(&config_path, &environment).unwrap();
+    assert_eq!(config.url, "http://staging.local/users/42");
+    assert_eq!(config.base.environment, "staging");
+    let headers = config.headers.unwrap();
+    assert_eq!(headers["X-Api-Key"], "from-file");
+    // Values containing '$', '{' or '}' are inserted literally
+    assert_eq!(headers["X-Secret"], "p$ss{${QITOPS_ENV_TEST_KEY}}");
+    assert_eq!(headers["X-Unknown"], "{{unknown}}");
+
+    // Without an environment file or overrides the configuration variables apply
+    let config: ApiTestConfig = load_config(&config_path, &Environment::new("production")).unwrap();
+    assert_eq!(config.url, "http://localhost:1/users/1");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Test that runner variables override collection variables and environments
+#[tokio::test]
+async fn test_collection_variables_override() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v2/items"))
+        .and(header("X-Tenant", "cli"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Overrides",
+        "variables": {"base_url": "http://localhost:1", "version": "v1", "tenant": "collection"},
+        "environments": {"test": {"version": "v2", "tenant": "environment"}},
+        "requests": [
+            {
+                "name": "Items",
+                "url": "{{base_url}}/{{version}}/items",
+                "method": "GET",
+                "headers": {"X-Tenant": "{{tenant}}"},
+                "expected_status": 200
+            }
+        ]
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .with_variables(variables(&[("base_url", &server.uri()), ("tenant", "cli")]))
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    assert_eq!(result.variables["version"], "v2");
+}
+
+// Test that runner variables also override folder variables, in sequential and parallel runs
+#[tokio::test]
+async fn test_folder_variables_override() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v3/users"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    for sequential in [true, false] {
+        let collection: ApiCollection = serde_json::from_value(json!({
+            "name": "Folder overrides",
+            "variables": {"base_url": server.uri(), "version": "v1"},
+            "run_options": {"sequential": sequential},
+            "folders": [
+                {
+                    "name": "Users",
+                    "variables": {"version": "v2"},
+                    "requests": [
+                        {
+                            "name": "Users", "url": "{{base_url}}/{{version}}/users",
+                            "method": "GET", "expected_status": 200
+                        }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let result = ApiCollectionRunner::new()
+            .with_variables(variables(&[("version", "v3")]))
+            .run_collection(&collection, "test")
+            .await
+            .unwrap();
+        assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    }
+}
+
+// Test that collection files get ${NAME} substitution and schema validation, keeping {{name}} for the run
+#[tokio::test]
+async fn test_load_collection_environment() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v2/items"))
+        .and(header("X-Api-Key", "from-cli"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let dir = temp_dir("collection");
+    let collection_path = dir.join("items.json");
+    std::fs::write(
+        &collection_path,
+        json!({
+            "name": "Items",
+            "variables": {"base_url": "${QITOPS_ENV_TEST_BASE_URL}", "version": "v1"},
+            "environments": {"test": {"version": "v2"}},
+            "requests": [
+                {
+                    "name": "Items",
+                    "url": "{{base_url}}/{{version}}/items",
+                    "method": "GET",
+                    "headers": {"X-Api-Key": "${QITOPS_ENV_TEST_COLLECTION_KEY}"},
+                    "expected_status": 200
+                }
+            ]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let environment = Environment::new("test").with_overrides(variables(&[
+        ("QITOPS_ENV_TEST_BASE_URL", &server.uri()),
+        ("QITOPS_ENV_TEST_COLLECTION_KEY", "from-cli"),
+    ]));
+    let collection = ApiCollectionRunner::load_collection(&collection_path, &environment).unwrap();
+    assert_eq!(collection.requests[0].url, "{{base_url}}/{{version}}/items");
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+
+    // Collections are validated against the collection schema
+    std::fs::write(
+        &collection_path,
+        json!({"name": "Items", "requests": [{"name": "No URL"}]}).to_string(),
+    )
+    .unwrap();
+    let error = ApiCollectionRunner::load_collection(&collection_path, &environment)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("url"), "{}", error);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
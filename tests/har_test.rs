@@ -0,0 +1,197 @@
+use qitops::api_collection::{ApiCollection, ApiCollectionRunner};
+use qitops::har::{self, HarFilter, HarImportOptions};
+use qitops::performance_enhanced::EnhancedPerformanceConfig;
+use serde_json::{json, Value};
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn entry(method: &str, url: &str, status: u16, mime: &str, text: &str) -> Value {
+    json!({
+        "request": {
+            "method": method,
+            "url": url,
+            "headers": [
+                {"name": ":authority", "value": "app.example.com"},
+                {"name": "Accept", "value": "application/json"},
+                {"name": "Cookie", "value": "session=abc"},
+                {"name": "Authorization", "value": "Bearer abc"}
+            ]
+        },
+        "response": {"status": status, "content": {"mimeType": mime, "text": text}}
+    })
+}
+
+fn har(base: &str) -> Value {
+    let mut post = entry(
+        "POST",
+        &format!("{}/api/orders", base),
+        201,
+        "application/json",
+        r#"{"id": 7}"#,
+    );
+    post["request"]["postData"] =
+        json!({"mimeType": "application/json", "text": r#"{"sku": "A-1"}"#});
+    let mut login = entry(
+        "POST",
+        &format!("{}/login", base),
+        200,
+        "text/html",
+        "<html></html>",
+    );
+    login["request"]["postData"] = json!({
+        "mimeType": "application/x-www-form-urlencoded",
+        "params": [{"name": "user", "value": "ada"}]
+    });
+    let mut script = entry("GET", &format!("{}/bundle", base), 200, "text/plain", "");
+    script["_resourceType"] = json!("script");
+
+    json!({
+        "log": {
+            "pages": [{"title": "Shop"}],
+            "entries": [
+                entry("GET", &format!("{}/api/orders?page=1", base), 200, "application/json", r#"{"items": [], "total": 0}"#),
+                entry("GET", &format!("{}/static/app.css", base), 200, "text/css", ""),
+                script,
+                entry("GET", "https://www.google-analytics.com/collect", 204, "", ""),
+                post,
+                entry("GET", &format!("{}/api/orders?page=1", base), 200, "application/json", r#"{"items": [], "total": 0}"#),
+                entry("GET", &format!("{}/health", base), 200, "application/json", "{}"),
+                login
+            ]
+        }
+    })
+}
+
+// Test filtering and converting HAR entries into a collection
+#[test]
+fn test_import_collection() {
+    let options = HarImportOptions {
+        filter: HarFilter {
+            exclude: vec!["/health$".to_string()],
+            ..Default::default()
+        },
+        body_assertions: true,
+        ..Default::default()
+    };
+    let conversion = har::import_collection(&har("https://app.example.com"), &options).unwrap();
+    let collection: ApiCollection = serde_json::from_value(conversion.document.clone()).unwrap();
+
+    assert_eq!(collection.name, "Shop");
+    assert_eq!(
+        collection.variables.as_ref().unwrap()["base_url"],
+        "https://app.example.com"
+    );
+    let names: Vec<&str> = collection
+        .requests
+        .iter()
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        ["GET /api/orders", "POST /api/orders", "POST /login"]
+    );
+
+    let list = &collection.requests[0];
+    assert_eq!(list.url, "{{base_url}}/api/orders?page=1");
+    assert_eq!(list.expected_status, Some(200));
+    assert_eq!(list.expected_body_type.as_deref(), Some("object"));
+    let assertions = serde_json::to_value(list.assertions.as_ref().unwrap()).unwrap();
+    assert_eq!(assertions[0]["path"], "$.items");
+    assert_eq!(assertions[1]["path"], "$.total");
+    let headers = list.headers.as_ref().unwrap();
+    assert_eq!(headers.len(), 2);
+    assert_eq!(headers["authorization"], "Bearer abc");
+
+    assert_eq!(collection.requests[1].body, Some(json!({"sku": "A-1"})));
+    assert_eq!(collection.requests[1].expected_status, Some(201));
+    assert_eq!(collection.requests[2].body, Some(json!({"user": "ada"})));
+
+    let warnings = conversion.warnings.join("\n");
+    assert!(
+        warnings.contains("Skipped 2 static asset request(s)"),
+        "{}",
+        warnings
+    );
+    assert!(
+        warnings.contains("www.google-analytics.com"),
+        "{}",
+        warnings
+    );
+    assert!(warnings.contains("Skipped 1 request(s) matching an exclude pattern"));
+}
+
+// Test that scenario weights follow request frequency
+#[test]
+fn test_import_performance() {
+    let options = HarImportOptions {
+        name: Some("Shop load".to_string()),
+        filter: HarFilter {
+            hosts: vec!["*.example.com".to_string()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let conversion = har::import_performance(&har("https://app.example.com"), &options).unwrap();
+    let config: EnhancedPerformanceConfig =
+        serde_json::from_value(conversion.document.clone()).unwrap();
+
+    assert_eq!(config.base.name, "Shop load");
+    let weights: Vec<(&str, u32)> = config
+        .scenarios
+        .iter()
+        .map(|s| (s.name.as_str(), s.weight))
+        .collect();
+    assert_eq!(
+        weights,
+        [
+            ("GET /api/orders", 2),
+            ("POST /api/orders", 1),
+            ("GET /health", 1),
+            ("POST /login", 1)
+        ]
+    );
+    assert_eq!(config.scenarios[1].body, Some(json!({"sku": "A-1"})));
+    assert!(conversion
+        .warnings
+        .iter()
+        .any(|w| w.contains("'POST /login' is not JSON")));
+}
+
+// Test that an imported collection replays against the captured service
+#[tokio::test]
+async fn test_imported_collection_runs() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"items": [], "total": 0})))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/orders"))
+        .and(body_json(json!({"sku": "A-1"})))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({"id": 8})))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/login"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&server)
+        .await;
+
+    let options = HarImportOptions {
+        body_assertions: true,
+        ..Default::default()
+    };
+    let conversion = har::import_collection(&har(&server.uri()), &options).unwrap();
+    let collection: ApiCollection = serde_json::from_value(conversion.document).unwrap();
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+}
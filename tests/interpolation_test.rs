@@ -0,0 +1,290 @@
+use qitops::api_collection::{ApiCollection, ApiCollectionRunner};
+use qitops::data_driven::{replace_placeholders, DataDrivenConfig, DataDrivenRunner};
+use qitops::interpolation::Interpolator;
+use regex::Regex;
+use serde_json::json;
+use std::collections::HashMap;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn interpolate(interpolator: &Interpolator, input: &str, variables: &[(&str, &str)]) -> String {
+    let variables: HashMap<String, String> = variables
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    interpolator.interpolate(input, &variables, |_| {}).unwrap()
+}
+
+// Test the format of every dynamic variable
+#[test]
+fn test_dynamic_variables() {
+    let interpolator = Interpolator::new();
+    let formats = [
+        (
+            "{{$uuid}}",
+            r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$",
+        ),
+        ("{{$timestamp}}", r"^\d{10}$"),
+        ("{{$timestampMs}}", r"^\d{13}$"),
+        (
+            "{{$isoDate}}",
+            r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d{3}Z$",
+        ),
+        ("{{$randomInt}}", r"^\d{1,4}$"),
+        ("{{$randomInt(-5,-1)}}", r"^-[1-5]$"),
+        ("{{$randomBoolean}}", r"^(true|false)$"),
+        ("{{$randomString(8)}}", r"^[A-Za-z0-9]{8}$"),
+        ("{{$randomFirstName}}", r"^[A-Z][a-z]+$"),
+        ("{{$randomEmail}}", r"^[a-z]+\.[a-z]+\.\d{4}@example\.com$"),
+    ];
+    for (input, format) in formats {
+        let value = interpolate(&interpolator, input, &[]);
+        assert!(
+            Regex::new(format).unwrap().is_match(&value),
+            "{} -> {}",
+            input,
+            value
+        );
+    }
+
+    // Every occurrence gets a fresh value
+    let pair = interpolate(&interpolator, "{{$uuid}} {{$uuid}}", &[]);
+    let (first, second) = pair.split_once(' ').unwrap();
+    assert_ne!(first, second);
+
+    // Variables are resolved before the dynamic variables that use them
+    let encoded = interpolate(
+        &interpolator,
+        "Basic {{$base64({{user}}:{{password}})}}",
+        &[("user", "ada"), ("password", "lovelace")],
+    );
+    assert_eq!(encoded, "Basic YWRhOmxvdmVsYWNl");
+}
+
+// Test that seeded interpolators repeat their random values
+#[test]
+fn test_seeded_values_repeat() {
+    let input = "{{$uuid}} {{$randomInt(1,100)}} {{$randomEmail}} {{$randomString}}";
+    let first = interpolate(&Interpolator::seeded(42), input, &[]);
+    let second = interpolate(&Interpolator::seeded(42), input, &[]);
+    let other = interpolate(&Interpolator::seeded(7), input, &[]);
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+// Test that unknown names are left in place and invalid arguments are rejected
+#[test]
+fn test_unknown_and_invalid() {
+    let interpolator = Interpolator::new();
+    let mut missing = Vec::new();
+    let value = interpolator
+        .interpolate(
+            "{{known}} {{unknown}} {{$unknown}}",
+            &HashMap::from([("known".to_string(), "1".to_string())]),
+            |name| missing.push(name.to_string()),
+        )
+        .unwrap();
+    assert_eq!(value, "1 {{unknown}} {{$unknown}}");
+    assert_eq!(missing, ["unknown", "$unknown"]);
+
+    for input in [
+        "{{$randomInt(5)}}",
+        "{{$randomInt(9,1)}}",
+        "{{$randomString(x)}}",
+    ] {
+        assert!(
+            interpolator
+                .interpolate(input, &HashMap::new(), |_| {})
+                .is_err(),
+            "{}",
+            input
+        );
+    }
+}
+
+// Test that data-driven replacements use the same engine
+#[test]
+fn test_data_driven_placeholders() {
+    let mut config = json!({
+        "url": "{{base_url}}/users/{{id}}",
+        "body": {"email": "{{$randomEmail}}", "tags": ["{{id}}"]}
+    });
+    let row = HashMap::from([("id".to_string(), "7".to_string())]);
+    replace_placeholders(&mut config, &row, &Interpolator::seeded(1)).unwrap();
+
+    assert_eq!(config["url"], "{{base_url}}/users/7");
+    assert_eq!(config["body"]["tags"][0], "7");
+    assert!(config["body"]["email"]
+        .as_str()
+        .unwrap()
+        .ends_with("@example.com"));
+}
+
+// Test that collection requests send dynamic values
+#[tokio::test]
+async fn test_collection_dynamic_variables() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/users"))
+        .respond_with(|request: &wiremock::Request| {
+            ResponseTemplate::new(201).set_body_raw(request.body.clone(), "application/json")
+        })
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Dynamic",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {
+                "name": "Create user",
+                "url": "{{base_url}}/users",
+                "method": "POST",
+                "headers": {"Content-Type": "application/json"},
+                "body": {"id": "{{$uuid}}", "email": "{{$randomEmail}}", "age": "{{$randomInt(18,99)}}"},
+                "expected_status": 201,
+                "capture": {"email": "$.email", "age": "$.age"}
+            }
+        ]
+    }))
+    .unwrap();
+
+    let run = |seed| {
+        let collection = collection.clone();
+        async move {
+            ApiCollectionRunner::new()
+                .with_seed(seed)
+                .run_collection(&collection, "test")
+                .await
+                .unwrap()
+        }
+    };
+    let first = run(3).await;
+    let second = run(3).await;
+    assert_eq!(first.status, "passed", "{:?}", first.request_results);
+    assert!(first.variables["email"].ends_with("@example.com"));
+    let age: u32 = first.variables["age"].parse().unwrap();
+    assert!((18..=99).contains(&age));
+    assert_eq!(first.variables["email"], second.variables["email"]);
+}
+
+// Test that post-response scripts see the dynamic values that were sent
+#[tokio::test]
+async fn test_scripts_see_sent_dynamic_values() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/orders"))
+        .respond_with(|request: &wiremock::Request| {
+            ResponseTemplate::new(201).set_body_raw(request.body.clone(), "application/json")
+        })
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Sent values",
+        "variables": {"base_url": server.uri()},
+        "requests": [
+            {
+                "name": "Create order",
+                "url": "{{base_url}}/orders",
+                "method": "POST",
+                "body": {"id": "{{$uuid}}"},
+                "pre_request": "vars.seen = request.body.id;",
+                "post_response": "assert(\"same id\", request.body.id == response.body.id);"
+            }
+        ]
+    }))
+    .unwrap();
+
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    // Pre-request scripts run before dynamic variables are generated
+    assert_eq!(result.variables["seen"], "{{$uuid}}");
+}
+
+// Test that a seed gives each request the same values whatever order the requests run in
+#[tokio::test]
+async fn test_seeded_values_per_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(|request: &wiremock::Request| {
+            ResponseTemplate::new(200).set_body_raw(request.body.clone(), "application/json")
+        })
+        .mount(&server)
+        .await;
+
+    let collection = |sequential: bool, reorder: bool| {
+        let requests: Vec<_> = (1..=3)
+            .map(|id| {
+                let mut request = json!({
+                    "id": format!("user_{}", id),
+                    "name": format!("User {}", id),
+                    "url": format!("{{{{base_url}}}}/users/{}", id),
+                    "method": "POST",
+                    "body": {"id": "{{$uuid}}"},
+                    "capture": {format!("id_{}", id): "$.id"}
+                });
+                // The first request runs last when it depends on the third
+                if reorder && id == 1 {
+                    request["depends_on"] = json!(["user_3"]);
+                }
+                request
+            })
+            .collect();
+        serde_json::from_value::<ApiCollection>(json!({
+            "name": "Seeded",
+            "variables": {"base_url": server.uri()},
+            "run_options": {"sequential": sequential},
+            "requests": requests
+        }))
+        .unwrap()
+    };
+
+    let mut runs = Vec::new();
+    for (sequential, reorder) in [(true, false), (true, true), (false, false), (false, true)] {
+        let result = ApiCollectionRunner::new()
+            .with_seed(11)
+            .run_collection(&collection(sequential, reorder), "test")
+            .await
+            .unwrap();
+        assert_eq!(result.status, "passed", "{:?}", result.request_results);
+        runs.push(["id_1", "id_2", "id_3"].map(|name| result.variables[name].clone()));
+    }
+    assert!(runs.iter().all(|run| run == &runs[0]), "{:?}", runs);
+    assert_ne!(runs[0][0], runs[0][1]);
+}
+
+// Test that data rows applied to a collection leave dynamic variables to the collection runner
+#[test]
+fn test_data_driven_collection_keeps_dynamic_variables() {
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Data",
+        "requests": [
+            {
+                "name": "User {{id}}",
+                "url": "{{base_url}}/users/{{id}}",
+                "method": "POST",
+                "body": {"request_id": "{{$uuid}}", "auth": "{{$base64({{id}}:secret)}}"}
+            }
+        ]
+    }))
+    .unwrap();
+    let config: DataDrivenConfig = serde_json::from_value(json!({
+        "data_source": {"type": "inline", "data": [{"id": "7"}]}
+    }))
+    .unwrap();
+    let runner = DataDrivenRunner::new(config).unwrap();
+
+    let updated = runner
+        .apply_collection_replacements(&collection, &runner.data_rows()[0])
+        .unwrap();
+    let request = &updated.requests[0];
+    assert_eq!(request.url, "{{base_url}}/users/7");
+    assert_eq!(
+        request.body,
+        Some(json!({"request_id": "{{$uuid}}", "auth": "{{$base64(7:secret)}}"}))
+    );
+}
@@ -0,0 +1,236 @@
+use qitops::api::ApiTestRunner;
+use qitops::api_collection::{ApiCollection, ApiCollectionRunner};
+use qitops::common::TestRunner;
+use serde_json::{json, Value};
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn mount_token(server: &MockServer, form: &str, response: Value, expect: u64) {
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains(form))
+        .respond_with(ResponseTemplate::new(200).set_body_json(response))
+        .expect(expect)
+        .mount(server)
+        .await;
+}
+
+async fn mount_api(server: &MockServer, token: &str, status: u16) {
+    Mock::given(method("GET"))
+        .and(path("/orders"))
+        .and(header(
+            "authorization",
+            format!("Bearer {}", token).as_str(),
+        ))
+        .respond_with(ResponseTemplate::new(status).set_body_json(json!({"items": []})))
+        .mount(server)
+        .await;
+}
+
+fn collection(server: &MockServer, auth: Value, requests: usize) -> ApiCollection {
+    let requests: Vec<Value> = (0..requests)
+        .map(|i| {
+            json!({
+                "name": format!("List orders {}", i),
+                "url": "{{base_url}}/orders",
+                "method": "GET",
+                "expected_status": 200
+            })
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "name": "OAuth2",
+        "variables": {"base_url": server.uri(), "client_secret": "s3cret"},
+        "auth": auth,
+        "requests": requests
+    }))
+    .unwrap()
+}
+
+/// Add a last request whose script checks the token variables the run holds by then
+fn check_tokens(mut collection: ApiCollection, script: &str) -> ApiCollection {
+    let mut check = collection.requests.last().unwrap().clone();
+    check.name = "Check tokens".to_string();
+    check.post_response = Some(script.to_string());
+    collection.requests.push(check);
+    collection
+}
+
+// Test that a client_credentials token is fetched once and reused for the run
+#[tokio::test]
+async fn test_client_credentials_cached() {
+    let server = MockServer::start().await;
+    mount_token(
+        &server,
+        "grant_type=client_credentials",
+        json!({"access_token": "t1", "token_type": "Bearer", "expires_in": 3600}),
+        1,
+    )
+    .await;
+    mount_api(&server, "t1", 200).await;
+
+    let auth = json!({
+        "type": "oauth2",
+        "token_url": "{{base_url}}/token",
+        "client_id": "qitops",
+        "client_secret": "{{client_secret}}",
+        "scope": "orders:read"
+    });
+    let collection = check_tokens(
+        collection(&server, auth, 2),
+        r#"assert("access token", vars.oauth2_access_token == "t1");"#,
+    );
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    assert_eq!(result.variables["oauth2_token_type"], "Bearer");
+    // Tokens are secrets in the result
+    assert_eq!(result.variables["oauth2_access_token"], "********");
+
+    let token_request = &server.received_requests().await.unwrap()[0];
+    let form = String::from_utf8_lossy(&token_request.body).to_string();
+    assert!(form.contains("client_secret=s3cret"), "{}", form);
+    assert!(form.contains("scope=orders%3Aread"), "{}", form);
+}
+
+// Test that a rejected token is refreshed with the refresh token and the request resent
+#[tokio::test]
+async fn test_refresh_on_unauthorized() {
+    let server = MockServer::start().await;
+    mount_token(
+        &server,
+        "grant_type=password",
+        json!({"access_token": "stale", "refresh_token": "r1"}),
+        1,
+    )
+    .await;
+    mount_token(
+        &server,
+        "refresh_token=r1",
+        json!({"access_token": "fresh", "expires_in": 3600}),
+        1,
+    )
+    .await;
+    mount_api(&server, "stale", 401).await;
+    mount_api(&server, "fresh", 200).await;
+
+    let auth = json!({
+        "type": "oauth2",
+        "grant_type": "password",
+        "token_url": "{{base_url}}/token",
+        "client_id": "qitops",
+        "client_auth": "basic",
+        "username": "ada",
+        "password": "lovelace"
+    });
+    // The old refresh token stays usable when the server does not issue a new one
+    let collection = check_tokens(
+        collection(&server, auth, 1),
+        r#"assert("access token", vars.oauth2_access_token == "fresh");
+assert("refresh token", vars.oauth2_refresh_token == "r1");"#,
+    );
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    // Tokens are secrets in the result
+    assert_eq!(result.variables["oauth2_access_token"], "********");
+    assert_eq!(result.variables["oauth2_refresh_token"], "********");
+}
+
+// Test the authorization code grant with PKCE and refreshing an expired token
+#[tokio::test]
+async fn test_authorization_code_expiry() {
+    let server = MockServer::start().await;
+    mount_token(
+        &server,
+        "code_verifier=verifier",
+        json!({"access_token": "a1", "expires_in": 5, "refresh_token": "r1"}),
+        1,
+    )
+    .await;
+    mount_token(
+        &server,
+        "grant_type=refresh_token",
+        json!({"access_token": "a2", "expires_in": 3600, "refresh_token": "r2"}),
+        1,
+    )
+    .await;
+    mount_api(&server, "a1", 200).await;
+    mount_api(&server, "a2", 200).await;
+
+    let auth = json!({
+        "type": "oauth2",
+        "grant_type": "authorization_code",
+        "token_url": "{{base_url}}/token",
+        "client_id": "qitops",
+        "code": "c0de",
+        "code_verifier": "verifier",
+        "redirect_uri": "http://localhost/callback"
+    });
+    let collection = check_tokens(
+        collection(&server, auth, 2),
+        r#"assert("access token", vars.oauth2_access_token == "a2");
+assert("refresh token", vars.oauth2_refresh_token == "r2");"#,
+    );
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+    // Tokens are secrets in the result
+    assert_eq!(result.variables["oauth2_access_token"], "********");
+    assert_eq!(result.variables["oauth2_refresh_token"], "********");
+
+    let received = server.received_requests().await.unwrap();
+    let authorizations: Vec<&str> = received
+        .iter()
+        .filter(|r| r.url.path() == "/orders")
+        .filter_map(|r| Some(r.headers.get(&"authorization".into())?.last().as_str()))
+        .collect();
+    assert_eq!(authorizations, ["Bearer a1", "Bearer a2", "Bearer a2"]);
+}
+
+// Test oauth2 auth on a single API test and a failing token endpoint
+#[tokio::test]
+async fn test_api_test_oauth2() {
+    let server = MockServer::start().await;
+    mount_token(
+        &server,
+        "grant_type=client_credentials",
+        json!({"access_token": "t1"}),
+        1,
+    )
+    .await;
+    mount_api(&server, "t1", 200).await;
+
+    let config = json!({
+        "name": "List orders",
+        "description": "Orders behind OAuth2",
+        "timeout": 30,
+        "environment": "test",
+        "url": format!("{}/orders", server.uri()),
+        "method": "GET",
+        "expected_status": 200,
+        "retries": 0,
+        "auth": {
+            "type": "oauth2",
+            "token_url": format!("{}/token", server.uri()),
+            "client_id": "qitops"
+        }
+    });
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    let mut rejected = config.clone();
+    rejected["auth"]["token_url"] = json!(format!("{}/missing", server.uri()));
+    let error = ApiTestRunner::new().run(&rejected).await;
+    let message = match error {
+        Ok(result) => result.details.unwrap().to_string(),
+        Err(e) => e.to_string(),
+    };
+    assert!(message.contains("Token request"), "{}", message);
+}
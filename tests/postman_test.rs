@@ -0,0 +1,180 @@
+use qitops::api_collection::ApiCollection;
+use qitops::postman;
+use serde_json::json;
+
+fn postman_collection() -> serde_json::Value {
+    json!({
+        "info": {
+            "name": "Shop",
+            "description": "Shop API",
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+        },
+        "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]},
+        "variable": [
+            {"key": "base_url", "value": "https://shop.example.com"},
+            {"key": "unused", "value": "x", "disabled": true}
+        ],
+        "item": [
+            {
+                "name": "Orders",
+                "auth": {
+                    "type": "apikey",
+                    "apikey": [
+                        {"key": "key", "value": "x-api-key"},
+                        {"key": "value", "value": "{{api_key}}"},
+                        {"key": "in", "value": "header"}
+                    ]
+                },
+                "item": [
+                    {
+                        "name": "Create order",
+                        "event": [
+                            {
+                                "listen": "test",
+                                "script": {"exec": [
+                                    "pm.test(\"created\", function () {",
+                                    "    pm.response.to.have.status(201);",
+                                    "});",
+                                    "var jsonData = pm.response.json();",
+                                    "pm.expect(jsonData.status).to.eql('open');",
+                                    "pm.collectionVariables.set(\"order_id\", jsonData.data.id);",
+                                    "pm.expect(pm.response.responseTime).to.be.below(500);"
+                                ]}
+                            },
+                            {"listen": "prerequest", "script": {"exec": ["console.log('hi')"]}}
+                        ],
+                        "request": {
+                            "method": "post",
+                            "header": [
+                                {"key": "Content-Type", "value": "application/json"},
+                                {"key": "X-Debug", "value": "1", "disabled": true}
+                            ],
+                            "body": {
+                                "mode": "raw",
+                                "raw": "{\"sku\": \"A-1\", \"quantity\": 2}",
+                                "options": {"raw": {"language": "json"}}
+                            },
+                            "url": {"raw": "{{base_url}}/orders", "host": ["{{base_url}}"], "path": ["orders"]}
+                        }
+                    }
+                ]
+            },
+            {
+                "name": "Get order",
+                "request": {
+                    "method": "GET",
+                    "url": {
+                        "raw": "{{base_url}}/orders/:order_id?expand=items",
+                        "variable": [{"key": "order_id", "value": ""}]
+                    }
+                }
+            },
+            {
+                "name": "Login",
+                "request": {
+                    "method": "POST",
+                    "auth": {"type": "noauth"},
+                    "body": {
+                        "mode": "urlencoded",
+                        "urlencoded": [
+                            {"key": "user", "value": "ada"},
+                            {"key": "remember", "value": "1", "disabled": true}
+                        ]
+                    },
+                    "url": "{{base_url}}/login"
+                }
+            }
+        ]
+    })
+}
+
+// Test converting a Postman collection and environment
+#[test]
+fn test_import() {
+    let environment = json!({
+        "name": "staging",
+        "values": [
+            {"key": "base_url", "value": "https://staging.shop.example.com", "enabled": true},
+            {"key": "old", "value": "x", "enabled": false}
+        ]
+    });
+    let conversion = postman::import(&postman_collection(), &[environment]).unwrap();
+    let collection: ApiCollection = serde_json::from_value(conversion.document.clone()).unwrap();
+
+    assert_eq!(collection.name, "Shop");
+    assert_eq!(collection.description.as_deref(), Some("Shop API"));
+    assert_eq!(collection.auth.as_ref().unwrap().auth_type, "bearer");
+    assert_eq!(
+        collection.auth.as_ref().unwrap().token.as_deref(),
+        Some("{{token}}")
+    );
+    let variables = collection.variables.as_ref().unwrap();
+    assert_eq!(variables.len(), 1);
+    assert_eq!(variables["base_url"], "https://shop.example.com");
+    let staging = &collection.environments.as_ref().unwrap()["staging"];
+    assert_eq!(staging.len(), 1);
+
+    let create = &collection.requests[0];
+    assert_eq!(create.name, "Orders / Create order");
+    assert_eq!(create.method, "POST");
+    assert_eq!(create.url, "{{base_url}}/orders");
+    assert_eq!(create.body, Some(json!({"sku": "A-1", "quantity": 2})));
+    let headers = create.headers.as_ref().unwrap();
+    assert_eq!(headers["x-api-key"], "{{api_key}}");
+    assert!(!headers.contains_key("X-Debug"));
+    assert_eq!(create.expected_status, Some(201));
+    assert_eq!(create.capture.as_ref().unwrap()["order_id"], "$.data.id");
+    let assertions = serde_json::to_value(create.assertions.as_ref().unwrap()).unwrap();
+    assert_eq!(assertions[0]["path"], "$.status");
+    assert_eq!(assertions[0]["value"], "open");
+
+    let get = &collection.requests[1];
+    assert_eq!(get.url, "{{base_url}}/orders/{{order_id}}?expand=items");
+
+    let login = &collection.requests[2];
+    assert_eq!(
+        serde_json::to_value(login.body_type).unwrap(),
+        json!("form")
+    );
+    assert_eq!(login.body, Some(json!({"user": "ada"})));
+
+    let warnings = conversion.warnings.join("\n");
+    assert!(warnings.contains("1 line(s) of the test script of 'Orders / Create order'"));
+    assert!(warnings.contains("prerequest script of 'Orders / Create order'"));
+    assert!(warnings.contains("'Login' disables authentication"));
+}
+
+// Test that exported collections import back to the same requests
+#[test]
+fn test_export_round_trip() {
+    let imported = postman::import(&postman_collection(), &[]).unwrap();
+    let collection: ApiCollection = serde_json::from_value(imported.document).unwrap();
+
+    let exported = postman::export(&collection).unwrap();
+    assert_eq!(
+        exported.document["info"]["schema"],
+        postman::COLLECTION_SCHEMA
+    );
+    assert_eq!(exported.document["auth"]["type"], "bearer");
+
+    let reimported = postman::import(&exported.document, &[]).unwrap();
+    assert!(reimported.warnings.is_empty(), "{:?}", reimported.warnings);
+    let again: ApiCollection = serde_json::from_value(reimported.document).unwrap();
+    assert_eq!(again.requests.len(), collection.requests.len());
+    for (before, after) in collection.requests.iter().zip(&again.requests) {
+        assert_eq!(before.name, after.name);
+        assert_eq!(before.method, after.method);
+        assert_eq!(before.url, after.url);
+        assert_eq!(before.body, after.body);
+        assert_eq!(before.headers, after.headers);
+        assert_eq!(before.expected_status, after.expected_status);
+        assert_eq!(before.capture, after.capture);
+    }
+
+    let environment = postman::export_environment(
+        "staging",
+        &[("base_url".to_string(), "https://staging".to_string())].into(),
+    );
+    assert_eq!(environment["values"][0]["key"], "base_url");
+    assert_eq!(environment["values"][0]["enabled"], true);
+}
@@ -0,0 +1,213 @@
+use base64::Engine as _;
+use chrono::{TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use qitops::api::ApiTestRunner;
+use qitops::api_collection::{ApiCollection, ApiCollectionRunner, CollectionAuth};
+use qitops::common::TestRunner;
+use qitops::signing;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn aws_auth(service: &str) -> CollectionAuth {
+    CollectionAuth {
+        auth_type: "aws_sigv4".to_string(),
+        access_key_id: Some("AKIDEXAMPLE".to_string()),
+        secret_access_key: Some("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string()),
+        region: Some("us-east-1".to_string()),
+        service: Some(service.to_string()),
+        ..Default::default()
+    }
+}
+
+// Test against the get-vanilla and query ordering cases of the AWS SigV4 test suite
+#[test]
+fn test_aws_sigv4_test_suite() {
+    let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+    let client = reqwest::Client::new();
+
+    let mut request = client
+        .get("https://example.amazonaws.com/")
+        .build()
+        .unwrap();
+    signing::sign(&mut request, &aws_auth("service"), now).unwrap();
+    assert_eq!(request.headers()["x-amz-date"], "20150830T123600Z");
+    assert_eq!(
+        request.headers()["authorization"],
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+         SignedHeaders=host;x-amz-date, \
+         Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    );
+
+    let mut request = client
+        .get("https://example.amazonaws.com/?Param2=value2&Param1=value1")
+        .build()
+        .unwrap();
+    signing::sign(&mut request, &aws_auth("service"), now).unwrap();
+    let authorization = request.headers()["authorization"].to_str().unwrap();
+    assert!(
+        authorization.ends_with(
+            "Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        ),
+        "{}",
+        authorization
+    );
+
+    // S3 also sends the payload hash
+    let mut request = client
+        .put("https://bucket.s3.amazonaws.com/key")
+        .body("data")
+        .build()
+        .unwrap();
+    signing::sign(&mut request, &aws_auth("s3"), now).unwrap();
+    assert_eq!(
+        request.headers()["x-amz-content-sha256"],
+        "3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7"
+    );
+    let authorization = request.headers()["authorization"].to_str().unwrap();
+    assert!(authorization.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date"));
+}
+
+// Test HMAC signatures computed from the interpolated collection request
+#[tokio::test]
+async fn test_hmac_collection() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/orders"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&server)
+        .await;
+
+    let collection: ApiCollection = serde_json::from_value(json!({
+        "name": "Signed",
+        "variables": {"base_url": server.uri(), "hmac_secret": "s3cret", "sku": "A-1"},
+        "auth": {
+            "type": "hmac",
+            "secret": "{{hmac_secret}}",
+            "canonical_template": "{method}|{path}|{query}|{timestamp}|{body_sha256}",
+            "signature_header": "Authorization",
+            "signature_prefix": "HMAC ",
+            "signature_encoding": "base64",
+            "timestamp_header": "X-Request-Time"
+        },
+        "requests": [{
+            "name": "Create order",
+            "url": "{{base_url}}/orders?dry_run=true",
+            "method": "POST",
+            "body": {"sku": "{{sku}}"},
+            "expected_status": 201
+        }]
+    }))
+    .unwrap();
+    let result = ApiCollectionRunner::new()
+        .run_collection(&collection, "test")
+        .await
+        .unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.request_results);
+
+    let request = &server.received_requests().await.unwrap()[0];
+    let header = |name: &str| {
+        request
+            .headers
+            .get(&name.into())
+            .unwrap()
+            .last()
+            .as_str()
+            .to_string()
+    };
+    let timestamp = header("x-request-time");
+    let body_sha256: String = Sha256::digest(&request.body)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+    mac.update(format!("POST|/orders|dry_run=true|{}|{}", timestamp, body_sha256).as_bytes());
+    let expected = base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+    assert_eq!(header("authorization"), format!("HMAC {}", expected));
+    assert!(String::from_utf8_lossy(&request.body).contains("A-1"));
+}
+
+// Test SigV4 on a single API test
+#[tokio::test]
+async fn test_aws_sigv4_api_test() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/prod/orders"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let config = json!({
+        "name": "List orders",
+        "description": "API Gateway",
+        "timeout": 30,
+        "environment": "test",
+        "url": format!("{}/prod/orders", server.uri()),
+        "method": "GET",
+        "expected_status": 200,
+        "auth": {
+            "type": "aws_sigv4",
+            "access_key_id": "AKIDEXAMPLE",
+            "secret_access_key": "secret",
+            "session_token": "session",
+            "region": "eu-west-1",
+            "service": "execute-api"
+        }
+    });
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "passed", "{:?}", result.details);
+
+    let request = &server.received_requests().await.unwrap()[0];
+    // Header values are split on commas by the mock server
+    let authorization = request
+        .headers
+        .get(&"authorization".into())
+        .unwrap()
+        .iter()
+        .map(|value| value.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert!(
+        authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"),
+        "{}",
+        authorization
+    );
+    assert!(authorization.contains("/eu-west-1/execute-api/aws4_request"));
+    assert!(authorization.contains("SignedHeaders=host;x-amz-date;x-amz-security-token"));
+}
+
+// Test that signed requests with a multipart body are rejected
+#[tokio::test]
+async fn test_signed_multipart_rejected() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/upload"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let config = json!({
+        "name": "Upload",
+        "description": "",
+        "environment": "test",
+        "url": format!("{}/upload", server.uri()),
+        "method": "POST",
+        "body_type": "multipart",
+        "body": [{"name": "kind", "value": "report"}],
+        "expected_status": 200,
+        "auth": {"type": "hmac", "secret": "s3cret"}
+    });
+    let result = ApiTestRunner::new().run(&config).await.unwrap();
+    assert_eq!(result.status, "failed");
+    let error = result.details.unwrap()["error"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(
+        error.contains("hmac auth cannot sign multipart bodies"),
+        "{}",
+        error
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+}